@@ -0,0 +1,271 @@
+//! Hacker News Search API client.
+//!
+//! Algolia indexes all of hacker news. A single `items/:id` request returns
+//! an item with its whole comment tree which is far cheaper than walking the
+//! tree one request per comment through the firebase API.
+use crate::{
+    source::{ItemSource, ItemTree},
+    ApiError, ApiResult, Item, NetworkSettings,
+};
+use futures::{future::BoxFuture, FutureExt as _};
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::time::Duration;
+
+/// Item with nested children as returned by the `items` end point.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlgoliaItem {
+    pub id: u64,
+    #[serde(default)]
+    pub created_at_i: u64,
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// `null` for deleted items.
+    pub author: Option<String>,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub text: Option<String>,
+    pub points: Option<u64>,
+    pub parent_id: Option<u64>,
+    pub story_id: Option<u64>,
+    #[serde(default)]
+    pub children: Vec<AlgoliaItem>,
+    /// Poll options.
+    #[serde(default)]
+    pub options: Vec<AlgoliaItem>,
+}
+
+impl AlgoliaItem {
+    /// Flatten the tree into items with each parent before its children.
+    pub fn into_items(self) -> Vec<Item> {
+        let mut items = Vec::new();
+        let mut stack = vec![self];
+
+        while let Some(node) = stack.pop() {
+            let AlgoliaItem {
+                id,
+                created_at_i,
+                ty,
+                author,
+                title,
+                url,
+                text,
+                points,
+                parent_id,
+                story_id,
+                children,
+                options,
+            } = node;
+
+            let deleted = author.is_none() && text.is_none() && title.is_none();
+            let poll = (ty == "pollopt").then(|| parent_id.or(story_id)).flatten();
+
+            items.push(Item {
+                id,
+                kids: children.iter().map(|child| child.id).collect(),
+                text,
+                url,
+                title,
+                score: points.unwrap_or_default(),
+                time: created_at_i,
+                by: author.unwrap_or_default(),
+                dead: false,
+                deleted,
+                descendants: (ty == "story" || ty == "poll")
+                    .then(|| children.iter().map(AlgoliaItem::count).sum()),
+                parent: parent_id.filter(|_| poll.is_none()),
+                parts: options.iter().map(|option| option.id).collect(),
+                poll,
+                ty,
+            });
+
+            // Reversed so the first child is visited first.
+            stack.extend(children.into_iter().rev());
+        }
+
+        items
+    }
+
+    /// Number of items in this tree.
+    fn count(&self) -> u64 {
+        1 + self.children.iter().map(AlgoliaItem::count).sum::<u64>()
+    }
+}
+
+/// A search hit.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlgoliaHit {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+    #[serde(default)]
+    pub created_at_i: u64,
+    pub author: Option<String>,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub story_text: Option<String>,
+    pub comment_text: Option<String>,
+    pub points: Option<u64>,
+    pub num_comments: Option<u64>,
+    pub parent_id: Option<u64>,
+    pub story_id: Option<u64>,
+    #[serde(rename = "_tags", default)]
+    pub tags: Vec<String>,
+}
+
+impl AlgoliaHit {
+    /// Convert into an item. Hits don't carry child ids so `kids` is empty.
+    pub fn into_item(self) -> Option<Item> {
+        const TYPES: [&str; 5] = ["story", "comment", "poll", "pollopt", "job"];
+
+        let ty = self
+            .tags
+            .iter()
+            .find(|tag| TYPES.contains(&tag.as_str()))
+            .cloned()
+            .unwrap_or_else(|| "story".to_owned());
+
+        Some(Item {
+            id: self.object_id.parse().ok()?,
+            kids: Vec::new(),
+            text: self.comment_text.or(self.story_text),
+            url: self.url,
+            title: self.title,
+            score: self.points.unwrap_or_default(),
+            time: self.created_at_i,
+            by: self.author.unwrap_or_default(),
+            dead: false,
+            deleted: false,
+            ty,
+            parent: self.parent_id,
+            descendants: self.num_comments,
+            parts: Vec::new(),
+            poll: None,
+        })
+    }
+}
+
+/// A page of search results.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SearchPage {
+    pub hits: Vec<AlgoliaHit>,
+    /// Page number starting at 0.
+    pub page: usize,
+    /// Total number of pages.
+    #[serde(rename = "nbPages")]
+    pub pages: usize,
+}
+
+/// Hacker News Search API client.
+pub struct AlgoliaClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl AlgoliaClient {
+    /// Base url of the public Algolia API.
+    pub const API_END_POINT: &'static str = "https://hn.algolia.com/api/v1";
+
+    /// Create a new client for the Algolia end point.
+    pub fn new() -> ApiResult<Self> {
+        Self::with_base_url(Self::API_END_POINT)
+    }
+
+    /// Create a new client with a different base url such as a local
+    /// fixture server.
+    pub fn with_base_url(base_url: impl Into<String>) -> ApiResult<Self> {
+        Self::with_network(base_url, &NetworkSettings::default())
+    }
+
+    /// Create a new client that connects with the proxy, certificates and
+    /// user agent of `network`.
+    pub fn with_network(base_url: impl Into<String>, network: &NetworkSettings) -> ApiResult<Self> {
+        let base_url = base_url.into().trim_end_matches('/').to_owned();
+        let builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(60))
+            .gzip(true)
+            .use_rustls_tls();
+        let client = network
+            .apply(builder)?
+            .build()
+            .map_err(|err| ApiError::Config(format!("Failed to build the http client: {err}")))?;
+
+        Ok(Self { client, base_url })
+    }
+
+    /// The base url all API paths are relative to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get_json<T>(&self, url: String, id: Option<u64>) -> ApiResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|err| ApiError::request(err, id, &url))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Err(ApiError::NotFound { id, url }),
+            status if !status.is_success() => return Err(ApiError::Status { status, id, url }),
+            _ => (),
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|err| ApiError::request(err, id, &url))?;
+
+        serde_json::from_slice(&body).map_err(|source| ApiError::Decode { id, url, source })
+    }
+
+    /// Get an item with its nested children.
+    pub async fn item(&self, id: u64) -> ApiResult<AlgoliaItem> {
+        self.get_json(format!("{}/items/{id}", self.base_url), Some(id))
+            .await
+    }
+
+    /// Search all of hacker news. `tags` filters the results such as `story`
+    /// or `comment,author_pg`. Pages start at 0.
+    pub async fn search(
+        &self,
+        query: &str,
+        tags: Option<&str>,
+        page: usize,
+    ) -> ApiResult<SearchPage> {
+        let mut url = reqwest::Url::parse(&format!("{}/search", self.base_url)).map_err(|err| {
+            ApiError::Config(format!("Invalid base url {}: {err}", self.base_url))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("query", query)
+            .append_pair("page", &page.to_string());
+        if let Some(tags) = tags {
+            url.query_pairs_mut().append_pair("tags", tags);
+        }
+
+        self.get_json(url.into(), None).await
+    }
+}
+
+impl ItemSource for AlgoliaClient {
+    fn name(&self) -> &'static str {
+        "algolia"
+    }
+
+    fn item_tree(&self, id: u64) -> BoxFuture<'_, ApiResult<ItemTree>> {
+        async move {
+            Ok(ItemTree {
+                items: self.item(id).await?.into_items(),
+                failed: 0,
+            })
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod algolia_tests;
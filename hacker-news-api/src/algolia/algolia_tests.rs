@@ -0,0 +1,87 @@
+use super::AlgoliaClient;
+use crate::{ApiError, ItemSource};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Serve the fixtures over http on a local port and return the base url.
+async fn fixture_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+            let (status, body) = if path == "/items/1" {
+                ("200 OK", include_str!("fixtures/item.json"))
+            } else if path.starts_with("/search?") && path.contains("query=rust") {
+                ("200 OK", include_str!("fixtures/search.json"))
+            } else {
+                ("404 Not Found", r#"{"error":"Not Found"}"#)
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    format!("http://{address}")
+}
+
+#[tokio::test]
+async fn item_tree_is_flattened() {
+    let client = AlgoliaClient::with_base_url(fixture_server().await).unwrap();
+    let tree = client.item_tree(1).await.unwrap();
+    assert_eq!(tree.failed, 0);
+    let items = tree.items;
+
+    let ids = items.iter().map(|item| item.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+    let story = &items[0];
+    assert_eq!(story.kids, vec![2, 4]);
+    assert_eq!(story.descendants, Some(4));
+    assert_eq!(story.score, 42);
+
+    let deleted = &items[3];
+    assert!(deleted.deleted);
+    assert_eq!(deleted.kids, vec![5]);
+    assert_eq!(items[4].parent, Some(4));
+}
+
+#[tokio::test]
+async fn search_hits_map_to_items() {
+    let client = AlgoliaClient::with_base_url(fixture_server().await).unwrap();
+    let page = client.search("rust", None, 0).await.unwrap();
+    assert_eq!(page.pages, 1);
+
+    let items = page
+        .hits
+        .into_iter()
+        .filter_map(|hit| hit.into_item())
+        .collect::<Vec<_>>();
+    assert_eq!(items[0].ty, "story");
+    assert_eq!(items[0].descendants, Some(12));
+    assert_eq!(items[1].ty, "comment");
+    assert_eq!(items[1].text.as_deref(), Some("Rust is great"));
+}
+
+#[tokio::test]
+async fn missing_item_is_not_found() {
+    let client = AlgoliaClient::with_base_url(fixture_server().await).unwrap();
+    let err = client.item(99).await.unwrap_err();
+    assert!(matches!(err, ApiError::NotFound { id: Some(99), .. }));
+}
@@ -9,91 +9,194 @@
 //! the structure of the REST API, multiple requests and connections are
 //! required to do most operations.
 use crate::{
-    types::{Item, ItemEventData, ResultExt, StoriesEventData, User},
-    ArticleType,
+    source::{ItemSource, ItemTree},
+    types::{
+        Item, ItemEventData, ItemResult, ResultExt, StoriesEventData, Updates, UpdatesEventData,
+        User,
+    },
+    ApiError, ApiResult, ArticleType,
 };
-use anyhow::{Context as _, Result};
 use bytes::Bytes;
 use futures::{
-    future, stream::FuturesOrdered, FutureExt as _, TryFutureExt, TryStream, TryStreamExt,
-};
-use log::{error, info, warn};
-use reqwest::{header, IntoUrl};
-use serde::Deserialize;
-use std::{
-    future::Future,
-    net::{SocketAddr, ToSocketAddrs},
-    sync::Arc,
-    time::Duration,
-};
-use tokio::{
-    sync::mpsc::{self, Receiver, Sender},
-    task::JoinHandle,
+    future::{self, BoxFuture},
+    stream::{self, BoxStream},
+    FutureExt as _, StreamExt, TryStream, TryStreamExt,
 };
+use log::{info, warn};
+use reqwest::{header, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{future::Future, sync::Arc, time::Instant};
+use tokio::{sync::mpsc::Sender, time::timeout};
 #[cfg(feature = "trace")]
 use tracing::instrument;
 
-pub struct Resolver {
-    ip: Vec<SocketAddr>,
-}
+mod builder;
+mod cache;
+mod change_feed;
+mod comment_tree;
+mod list_feed;
+mod metrics;
+mod network;
+mod profile;
+mod recording;
+mod sse;
+mod subscription;
+mod throttle;
 
-impl Resolver {
-    fn new(host: &str) -> Result<Self> {
-        Ok(Self {
-            ip: host.to_socket_addrs()?.collect(),
-        })
-    }
-}
-
-impl reqwest::dns::Resolve for Resolver {
-    fn resolve(&self, _name: reqwest::dns::Name) -> reqwest::dns::Resolving {
-        let iter: Box<dyn Iterator<Item = SocketAddr> + Send> =
-            Box::new(self.ip.clone().into_iter());
-        Box::pin(async { Ok(iter) })
-    }
-}
+pub use builder::{ApiClientBuilder, DnsResolution, Resolver, TlsSettings};
+pub use cache::{CacheOptions, CacheStats, FreshnessPolicy, ItemCache};
+pub use change_feed::ChangeFeed;
+pub use comment_tree::{CommentTree, TreeNode, TreeOptions};
+pub use list_feed::{ListDelta, ListFeed, MovedId, RankedId};
+use metrics::Metrics;
+pub use metrics::{Endpoint, EndpointMetrics, Histogram, MetricsSnapshot, LATENCY_BUCKETS_MS};
+pub use network::{NetworkSettings, ProxySettings};
+pub use profile::SubmissionsPage;
+use recording::Tape;
+pub use recording::{ReplayTiming, Transport, RECORDING_FILE};
+pub use sse::{EventDecoder, PatchData, RawEvent, StreamEnd, StreamEvent};
+pub use subscription::{status_stream, Subscription, SubscriptionStatus, SupervisorConfig};
+use subscription::{supervise, StreamOptions};
+use throttle::Throttle;
+pub use throttle::{RateLimit, RetryPolicy};
 
 /// Hacker News Api client.
 pub struct ApiClient {
     client: reqwest::Client,
+    base_url: String,
+    throttle: Throttle,
+    tape: Option<Tape>,
+    cache: Option<Arc<ItemCache>>,
+    metrics: Arc<Metrics>,
 }
 
 impl ApiClient {
-    const API_END_POINT: &'static str = "https://hacker-news.firebaseio.com/v0";
-
-    /// Create a new API client.
-    pub fn new() -> Result<Self> {
-        let resolver = Arc::new(Resolver::new("hacker-news.firebaseio.com:443")?);
-
-        Ok(Self {
-            client: reqwest::Client::builder()
-                .connect_timeout(Duration::from_secs(5))
-                .gzip(true)
-                .dns_resolver(resolver)
-                .tcp_keepalive(Duration::from_secs(60))
-                .pool_max_idle_per_host(10)
-                .use_rustls_tls()
-                .no_proxy()
-                .build()
-                .context("Failed to create api client")?,
-        })
+    pub(crate) const API_END_POINT: &'static str = "https://hacker-news.firebaseio.com/v0";
+
+    /// Create a new API client for the firebase end point.
+    pub fn new() -> ApiResult<Self> {
+        Self::builder().build()
+    }
+
+    /// Create a builder to configure a new API client.
+    pub fn builder() -> ApiClientBuilder {
+        ApiClientBuilder::new()
+    }
+
+    /// The base url all API paths are relative to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The item cache if one is configured.
+    pub fn item_cache(&self) -> Option<&Arc<ItemCache>> {
+        self.cache.as_ref()
+    }
+
+    /// Request counts, latency, bytes, errors and open event streams since
+    /// the client was created.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Fetch and decode a json resource. Requests are throttled and
+    /// transient failures are retried with a backoff.
+    async fn get_json<T>(&self, url: String, id: Option<u64>) -> ApiResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.throttle.acquire().await;
+                self.try_get_json(&url, id).await
+            };
+            if let Err(err) = &result {
+                self.metrics.error(err);
+            }
+            match result {
+                Err(err) if err.is_transient() => {
+                    attempt += 1;
+                    let Some(delay) = self.throttle.retry.delay(attempt) else {
+                        return Err(err);
+                    };
+                    warn!("Retrying {url} attempt {attempt} in {delay:?}: {err}");
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fetch and decode a json resource once. Firebase returns `null` for
+    /// resources that don't exist which is reported as [`ApiError::NotFound`].
+    async fn try_get_json<T>(&self, url: &str, id: Option<u64>) -> ApiResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = url.to_owned();
+        let (status, body) = self.fetch(&url, id).await?;
+
+        match status {
+            StatusCode::NOT_FOUND => return Err(ApiError::NotFound { id, url }),
+            status if !status.is_success() => return Err(ApiError::Status { status, id, url }),
+            _ => (),
+        }
+
+        if body.as_ref() == b"null" {
+            return Err(ApiError::NotFound { id, url });
+        }
+
+        serde_json::from_slice(&body).map_err(|source| ApiError::Decode { id, url, source })
+    }
+
+    /// Fetch the status and body of a resource from the API or the replayed
+    /// recording.
+    async fn fetch(&self, url: &str, id: Option<u64>) -> ApiResult<(StatusCode, Bytes)> {
+        if let Some(Tape::Replay(replayer)) = self.tape.as_ref() {
+            let response =
+                replayer
+                    .response(self.path(url))
+                    .await
+                    .ok_or_else(|| ApiError::NotRecorded {
+                        url: url.to_owned(),
+                    })?;
+            return Ok((response.status, response.body));
+        }
+
+        let started = Instant::now();
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|err| ApiError::request(err, id, url))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|err| ApiError::request(err, id, url))?;
+        self.metrics
+            .request(self.path(url), started.elapsed(), body.len());
+
+        if let Some(Tape::Record(recorder)) = self.tape.as_ref() {
+            recorder.response(self.path(url), status, started.elapsed(), &body);
+        }
+        Ok((status, body))
+    }
+
+    /// Path of the url relative to the base url.
+    fn path<'a>(&self, url: &'a str) -> &'a str {
+        url.strip_prefix(self.base_url.as_str())
+            .unwrap_or(url)
+            .trim_start_matches('/')
     }
 
     /// Make firebase api call.
     #[cfg_attr(feature = "trace", instrument(skip_all))]
-    async fn call(&self, limit: usize, api: &str) -> Result<Vec<Item>> {
+    async fn call(&self, limit: usize, api: &str) -> ApiResult<Vec<Item>> {
         let mut ids = self
-            .client
-            .get(format!("{}/{api}", Self::API_END_POINT))
-            .send()
-            // .inspect_err(|err| {
-            //     dbg!(err);
-            // })
-            // .inspect_ok(|result| {
-            //     info!("Using http version {:?}", result.version());
-            //     info!("Headers: {:?}", result.headers())
-            // })
-            .and_then(|resp| resp.json::<Vec<u64>>())
+            .get_json::<Vec<u64>>(format!("{}/{api}", self.base_url), None)
             .await?;
 
         ids.truncate(limit);
@@ -105,7 +208,7 @@ impl ApiClient {
         &self,
         limit: usize,
         article_type: ArticleType,
-    ) -> impl Future<Output = Result<Vec<Item>>> + use<'_> {
+    ) -> impl Future<Output = ApiResult<Vec<Item>>> + use<'_> {
         match article_type {
             ArticleType::New => self.call(limit, "newstories.json"),
             ArticleType::Best => self.call(limit, "beststories.json"),
@@ -116,78 +219,170 @@ impl ApiClient {
         }
     }
 
-    /// Get a single item via item id.
+    /// Get a single item via item id. Fresh items are served from the item
+    /// cache when one is configured.
     #[cfg_attr(feature = "trace", instrument(skip_all))]
-    pub fn item(&self, id: u64) -> impl Future<Output = Result<Item>> + use<'_> {
-        self.client
-            .get(format!("{}/item/{id}.json", Self::API_END_POINT,))
-            .send()
-            .and_then(|result| result.json::<Item>())
-            .map_err(anyhow::Error::new)
+    pub async fn item(&self, id: u64) -> ApiResult<Item> {
+        if let Some(cache) = &self.cache {
+            if let Some(item) = cache.get(id).await {
+                return Ok(item);
+            }
+        }
+
+        let item: Item = self
+            .get_json(format!("{}/item/{id}.json", self.base_url), Some(id))
+            .await?;
+        if let Some(cache) = &self.cache {
+            cache.put(&item).await;
+        }
+        Ok(item)
     }
 
-    /// Get multiple ids by item id.
+    /// Get a single item via item id keeping deleted, dead and missing items.
     #[cfg_attr(feature = "trace", instrument(skip_all))]
-    pub fn items(&self, ids: &[u64]) -> impl TryStream<Ok = Item, Error = anyhow::Error> {
+    pub async fn item_result(&self, id: u64) -> ApiResult<ItemResult> {
+        match self.item(id).await {
+            Ok(item) => Ok(ItemResult::from(item)),
+            Err(ApiError::NotFound { .. }) => Ok(ItemResult::Missing(id)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get multiple items by item id keeping deleted, dead and missing items
+    /// in the order of the ids. The number of requests in flight is bounded
+    /// by the client's concurrency cap.
+    #[cfg_attr(feature = "trace", instrument(skip_all))]
+    pub fn item_results(
+        &self,
+        ids: &[u64],
+    ) -> impl TryStream<Ok = ItemResult, Error = ApiError> + use<'_> {
         // The firebase api only provides the option to get each item one by
         // one.
-        ids.iter()
-            .copied()
-            .map(|id| {
-                let client = &self.client;
-                client
-                    .get(format!("{}/item/{id}.json", Self::API_END_POINT,))
-                    .send()
-                    .map(move |result| result.with_context(|| format!("Failed to fetch item {id}")))
-                    .and_then(move |resp| {
-                        resp.json::<Item>().map(move |result| {
-                            result.with_context(|| format!("Invalid item json {id}"))
-                        })
-                    })
-            })
-            .collect::<FuturesOrdered<_>>()
-            .try_filter(|item| future::ready(!(item.dead || item.deleted)))
-            .into_stream()
+        stream::iter(ids.to_vec())
+            .map(|id| self.item_result(id))
+            .buffered(self.throttle.max_concurrent())
+    }
+
+    /// Get multiple items by item id. Deleted, dead and missing items are
+    /// skipped.
+    #[cfg_attr(feature = "trace", instrument(skip_all))]
+    pub fn items(&self, ids: &[u64]) -> impl TryStream<Ok = Item, Error = ApiError> + use<'_> {
+        self.item_results(ids)
+            .try_filter_map(|result| future::ready(Ok(result.into_present())))
+    }
+
+    /// Get an item with all of its descendants. Deleted and dead items are
+    /// kept. Each parent is followed by its replies in display order.
+    /// Descendants that fail to fetch are counted and left out.
+    #[cfg_attr(feature = "trace", instrument(skip_all))]
+    pub async fn item_tree(&self, id: u64) -> ApiResult<ItemTree> {
+        let tree = self.comment_tree(id, TreeOptions::default()).await?;
+        Ok(ItemTree {
+            items: tree.depth_first().map(|node| node.item.clone()).collect(),
+            failed: tree.failed(),
+        })
     }
 
     /// Get user by user handle.
-    pub fn user(&self, handle: &str) -> impl Future<Output = Result<User>> + use<'_> {
-        self.client
-            .get(format!("{}/user/{handle}.json", Self::API_END_POINT))
-            .send()
-            .and_then(|resp| resp.json::<User>())
-            .map_err(anyhow::Error::new)
+    pub fn user(&self, handle: &str) -> impl Future<Output = ApiResult<User>> + use<'_> {
+        self.get_json(format!("{}/user/{handle}.json", self.base_url), None)
+    }
+
+    /// Get the current largest item id.
+    pub fn max_item(&self) -> impl Future<Output = ApiResult<u64>> + use<'_> {
+        self.get_json(format!("{}/maxitem.json", self.base_url), None)
     }
 
-    /// Subscribe to a a server side event and return a stream that yields the generic
-    /// event data type.
-    fn event_source<EventData>(
+    /// Get the recently changed items and profiles.
+    pub fn updates(&self) -> impl Future<Output = ApiResult<Updates>> + use<'_> {
+        self.get_json(format!("{}/updates.json", self.base_url), None)
+    }
+
+    /// Subscribe to a a server side event and return a stream that yields the typed
+    /// events. Events with an invalid payload are logged and skipped.
+    async fn event_source<EventData>(
         &self,
-        url: impl IntoUrl,
-    ) -> impl Future<
-        Output = reqwest::Result<impl TryStream<Ok = Option<EventData>, Error = anyhow::Error>>,
-    >
+        url: String,
+        id: Option<u64>,
+    ) -> ApiResult<impl TryStream<Ok = StreamEvent<EventData>, Error = ApiError> + Unpin>
     where
-        EventData: for<'a> Deserialize<'a>,
+        EventData: DeserializeOwned,
     {
-        self.client
-            .get(url)
+        let mut decoder = EventDecoder::default();
+        Ok(self
+            .event_chunks(url, id)
+            .await?
+            .map_ok(move |chunk| {
+                stream::iter(
+                    decoder
+                        .decode(&chunk)
+                        .into_iter()
+                        .filter_map(|raw| StreamEvent::from_raw(&raw).log_error().ok())
+                        .flatten()
+                        .map(Ok),
+                )
+            })
+            .try_flatten())
+    }
+
+    /// Open an event stream and return its raw chunks from the API or the
+    /// replayed recording.
+    async fn event_chunks(
+        &self,
+        url: String,
+        id: Option<u64>,
+    ) -> ApiResult<BoxStream<'static, ApiResult<Bytes>>> {
+        if let Some(Tape::Replay(replayer)) = self.tape.as_ref() {
+            return Ok(replayer.stream(self.path(&url)).map(Ok).boxed());
+        }
+
+        let response = self
+            .client
+            .get(&url)
             .header(header::ACCEPT, "text/event-stream")
             .send()
-            .map_ok(|response| {
-                response
-                    .bytes_stream()
-                    .map_ok(parse_event)
-                    .map_err(anyhow::Error::new)
-            })
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ApiError::request(err, id, &url))
+            .inspect_err(|err| self.metrics.error(err))?;
+
+        let path = self.path(&url).to_owned();
+        let guard = self.metrics.stream_opened();
+        let chunks = response.bytes_stream().map_err(move |err| {
+            // Keep the stream counted as open until the stream is dropped.
+            let _ = &guard;
+            ApiError::request(err, id, &url)
+        });
+
+        Ok(match self.tape.as_ref() {
+            Some(Tape::Record(recorder)) => {
+                let recorder = recorder.clone();
+                let stream = recorder.next_stream();
+                let opened = Instant::now();
+                chunks
+                    .inspect_ok(move |chunk| recorder.event(&path, stream, opened.elapsed(), chunk))
+                    .boxed()
+            }
+            _ => chunks.boxed(),
+        })
     }
 
-    /// Top stories event-source stream.
+    /// Top stories event-source stream. Returns how the stream ended.
     pub async fn articles_list_stream(
         &self,
         article_type: ArticleType,
         sender: Sender<StoriesEventData>,
-    ) -> Result<()> {
+    ) -> ApiResult<StreamEnd> {
+        self.list_events(article_type, sender, StreamOptions::default())
+            .await
+    }
+
+    async fn list_events(
+        &self,
+        article_type: ArticleType,
+        sender: Sender<StoriesEventData>,
+        options: StreamOptions,
+    ) -> ApiResult<StreamEnd> {
         let path = match article_type {
             ArticleType::New => "newstories.json",
             ArticleType::Best => "beststories.json",
@@ -196,86 +391,190 @@ impl ApiClient {
             ArticleType::Show => "showstories.json",
             ArticleType::Job => "jobstories.json",
         };
-        let mut stream = self
-            .event_source::<StoriesEventData>(format!("{}/{path}", Self::API_END_POINT))
+        let stream = self
+            .event_source::<StoriesEventData>(format!("{}/{path}", self.base_url), None)
             .await?;
 
-        while let Some(event) = stream.try_next().await? {
-            if let Some(data) = event {
-                sender.send(data).await?;
-            }
-        }
-        Ok(())
+        forward_events(stream, sender, options, |last, patch| {
+            Ok(StoriesEventData {
+                path: patch.path.clone(),
+                data: patch.apply(&last.data)?,
+            })
+        })
+        .await
+    }
+
+    /// Subscribe to updates to a story via server side event sourcing. Returns
+    /// how the stream ended.
+    pub async fn item_stream(
+        &self,
+        story_id: u64,
+        sender: Sender<ItemEventData>,
+    ) -> ApiResult<StreamEnd> {
+        self.item_events(story_id, sender, StreamOptions::default())
+            .await
     }
 
-    /// Subscribe to updates to a story via server side event sourcing.
-    pub async fn item_stream(&self, story_id: u64, sender: Sender<ItemEventData>) -> Result<()> {
-        let mut stream = self
-            .event_source::<ItemEventData>(format!("{}/item/{story_id}.json", Self::API_END_POINT))
+    async fn item_events(
+        &self,
+        story_id: u64,
+        sender: Sender<ItemEventData>,
+        options: StreamOptions,
+    ) -> ApiResult<StreamEnd> {
+        let stream = self
+            .event_source::<ItemEventData>(
+                format!("{}/item/{story_id}.json", self.base_url),
+                Some(story_id),
+            )
             .await?;
 
-        while let Some(event) = stream.try_next().await? {
-            if let Some(data) = event {
-                sender.send(data).await?;
-            }
-        }
-        info!("item stream has exited.");
-        Ok(())
+        let end = forward_events(stream, sender, options, |last, patch| {
+            Ok(ItemEventData {
+                path: patch.path.clone(),
+                data: patch.apply(&last.data)?,
+            })
+        })
+        .await?;
+        info!("item stream has exited: {end:?}");
+        Ok(end)
+    }
+
+    /// Changed items and profiles event-source stream. Returns how the stream
+    /// ended.
+    pub async fn updates_stream(&self, sender: Sender<UpdatesEventData>) -> ApiResult<StreamEnd> {
+        self.update_events(sender, StreamOptions::default()).await
+    }
+
+    async fn update_events(
+        &self,
+        sender: Sender<UpdatesEventData>,
+        options: StreamOptions,
+    ) -> ApiResult<StreamEnd> {
+        let stream = self
+            .event_source::<UpdatesEventData>(format!("{}/updates.json", self.base_url), None)
+            .await?;
+
+        forward_events(stream, sender, options, |last, patch| {
+            Ok(UpdatesEventData {
+                path: patch.path.clone(),
+                data: patch.apply(&last.data)?,
+            })
+        })
+        .await
+    }
+
+    /// Create a supervised subscription to an article list that reconnects
+    /// when the event stream is lost.
+    pub fn subscribe_article_list(
+        self: &Arc<Self>,
+        article_type: ArticleType,
+        config: SupervisorConfig,
+    ) -> Subscription<StoriesEventData> {
+        let client = self.clone();
+        supervise(
+            format!("{article_type} list"),
+            config,
+            move |sender, options| {
+                let client = client.clone();
+                async move { client.list_events(article_type, sender, options).await }
+            },
+        )
+    }
+
+    /// Create a supervised subscription to an item that reconnects when the
+    /// event stream is lost.
+    pub fn subscribe_item(
+        self: &Arc<Self>,
+        item_id: u64,
+        config: SupervisorConfig,
+    ) -> Subscription<ItemEventData> {
+        let client = self.clone();
+        supervise(format!("item {item_id}"), config, move |sender, options| {
+            let client = client.clone();
+            async move { client.item_events(item_id, sender, options).await }
+        })
+    }
+
+    /// Create a supervised subscription to the changed items and profiles
+    /// that reconnects when the event stream is lost. Use a [`ChangeFeed`] to
+    /// share it.
+    pub fn subscribe_updates(
+        self: &Arc<Self>,
+        config: SupervisorConfig,
+    ) -> Subscription<UpdatesEventData> {
+        let client = self.clone();
+        supervise("updates".to_owned(), config, move |sender, options| {
+            let client = client.clone();
+            async move { client.update_events(sender, options).await }
+        })
+    }
+}
+
+impl ItemSource for ApiClient {
+    fn name(&self) -> &'static str {
+        "firebase"
+    }
+
+    fn item_tree(&self, id: u64) -> BoxFuture<'_, ApiResult<ItemTree>> {
+        ApiClient::item_tree(self, id).boxed()
     }
 }
 
-/// Parse an event from the event-source.
-fn parse_event<EventData>(bytes: Bytes) -> Option<EventData>
+/// Forward put events to the sender. Patch events are applied to the last
+/// put event and the patched data is forwarded.
+async fn forward_events<EventData>(
+    mut stream: impl TryStream<Ok = StreamEvent<EventData>, Error = ApiError> + Unpin,
+    sender: Sender<EventData>,
+    options: StreamOptions,
+    patch: impl Fn(&EventData, &PatchData) -> anyhow::Result<EventData>,
+) -> ApiResult<StreamEnd>
 where
-    EventData: for<'a> Deserialize<'a>,
+    EventData: Clone + Send + Sync + 'static,
 {
-    let mut lines = bytes.split(|b| *b == b'\n');
-    lines
-        .next()?
-        .starts_with(b"event: put")
-        .then(|| lines.next())?
-        .filter(|data| data.starts_with(b"data: "))
-        .and_then(|data| {
-            serde_json::from_slice::<EventData>(&data[6..])
-                .with_context(|| {
-                    format!(
-                        "Failed to deserialize event payload: {}",
-                        String::from_utf8_lossy(&data[6..])
-                    )
-                })
-                .log_error()
-                .ok()
-        })
-}
+    let mut last = None;
 
-/// Create a subscription to the top stories event stream. Provides a receive
-/// channel and a task handle for consuming events and canceling the task.
-pub fn subscribe_to_article_list(
-    article_type: ArticleType,
-) -> (Receiver<StoriesEventData>, JoinHandle<()>) {
-    let (tx, rx) = mpsc::channel(100);
+    loop {
+        let next = match options.stale_after {
+            Some(stale_after) => match timeout(stale_after, stream.try_next()).await {
+                Ok(next) => next?,
+                Err(_) => return Ok(StreamEnd::Stale),
+            },
+            None => stream.try_next().await?,
+        };
+        let Some(event) = next else {
+            break;
+        };
+        options.received();
 
-    let handle = tokio::spawn(async move {
-        loop {
-            match ApiClient::new() {
-                Ok(client) => {
-                    if let Err(err) = client.articles_list_stream(article_type, tx.clone()).await {
-                        warn!("Event stream for {article_type:?} severed {err}");
-                        // The task running this may have been dropped/cancelled. As a result we don't
-                        // need to restart this event subscription.
-                        break;
-                    }
-                    info!("Started article list subscription");
-                }
-                Err(err) => {
-                    // If we can not connect we will retry every minute.
-                    error!("Failed to create client {err}");
-                }
+        match event {
+            StreamEvent::Put(data) => {
+                last = Some(data.clone());
+                sender.send(data).await?;
+            }
+            StreamEvent::Patch(patch_data) => {
+                let Some(patched) = last
+                    .as_ref()
+                    .and_then(|last| patch(last, &patch_data).log_error().ok())
+                else {
+                    warn!("Ignoring patch event {patch_data:?}");
+                    continue;
+                };
+                last = Some(patched.clone());
+                sender.send(patched).await?;
             }
-            tokio::time::sleep(Duration::from_mins(1)).await;
-            info!("Restarting article list subscription");
+            StreamEvent::KeepAlive => (),
+            StreamEvent::Cancel => return Ok(StreamEnd::Cancelled),
+            StreamEvent::AuthRevoked => return Ok(StreamEnd::AuthRevoked),
         }
-    });
+    }
+    Ok(StreamEnd::Closed)
+}
 
-    (rx, handle)
+/// Create a supervised subscription to an article list event stream with the
+/// default supervisor settings.
+pub fn subscribe_to_article_list(
+    client: Arc<ApiClient>,
+    article_type: ArticleType,
+) -> Subscription<StoriesEventData> {
+    client.subscribe_article_list(article_type, SupervisorConfig::default())
 }
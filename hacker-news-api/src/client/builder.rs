@@ -0,0 +1,277 @@
+//! Builder for configuring an [`ApiClient`].
+use super::{
+    cache::ItemCache,
+    network::NetworkSettings,
+    recording::{ReplayTiming, Tape, Transport},
+    throttle::{RateLimit, RetryPolicy, Throttle},
+    ApiClient,
+};
+use crate::{ApiError, ApiResult};
+use reqwest::{dns::Resolve, tls, Url};
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Resolves every lookup to a fixed set of socket addresses.
+pub struct Resolver {
+    ip: Vec<SocketAddr>,
+}
+
+impl Resolver {
+    /// Resolve the `host:port` once and pin all future lookups to the result.
+    pub fn new(host: &str) -> std::io::Result<Self> {
+        Ok(Self {
+            ip: host.to_socket_addrs()?.collect(),
+        })
+    }
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, _name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let iter: Box<dyn Iterator<Item = SocketAddr> + Send> =
+            Box::new(self.ip.clone().into_iter());
+        Box::pin(async { Ok(iter) })
+    }
+}
+
+/// Forwards lookups to a shared custom resolver.
+struct SharedResolver(Arc<dyn Resolve>);
+
+impl Resolve for SharedResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        self.0.resolve(name)
+    }
+}
+
+/// How the client resolves the host of the base url.
+#[derive(Clone, Default)]
+pub enum DnsResolution {
+    /// Resolve the base url host once when the client is built and re-use
+    /// the addresses for every connection. Falls back to the system
+    /// resolver when requests may go through a proxy.
+    #[default]
+    Pinned,
+    /// Use the system resolver for every connection.
+    System,
+    /// Use the provided resolver.
+    Custom(Arc<dyn Resolve>),
+}
+
+/// TLS settings for the client.
+#[derive(Debug, Clone, Default)]
+pub struct TlsSettings {
+    /// Minimum accepted TLS version.
+    pub min_version: Option<tls::Version>,
+    /// Only allow https connections.
+    pub https_only: bool,
+    /// Accept invalid certificates. Only useful for local stand-in servers.
+    pub danger_accept_invalid_certs: bool,
+}
+
+/// Builder for an [`ApiClient`].
+#[derive(Clone)]
+pub struct ApiClientBuilder {
+    base_url: String,
+    connect_timeout: Duration,
+    timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    tls: TlsSettings,
+    network: NetworkSettings,
+    resolution: DnsResolution,
+    max_concurrent_requests: usize,
+    rate_limit: Option<RateLimit>,
+    retry: RetryPolicy,
+    transport: Transport,
+    cache: Option<Arc<ItemCache>>,
+}
+
+impl Default for ApiClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: ApiClient::API_END_POINT.to_owned(),
+            connect_timeout: Duration::from_secs(5),
+            timeout: None,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            pool_max_idle_per_host: 10,
+            tls: TlsSettings::default(),
+            network: NetworkSettings::default(),
+            resolution: DnsResolution::default(),
+            max_concurrent_requests: 16,
+            rate_limit: None,
+            retry: RetryPolicy::default(),
+            transport: Transport::default(),
+            cache: None,
+        }
+    }
+}
+
+impl ApiClientBuilder {
+    /// Create a builder with the default firebase settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the base url of the API. Paths such as `item/1.json` are
+    /// appended to it.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Set the connection timeout.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the total request timeout. Server side event streams are long
+    /// lived so this should only be used for short lived clients.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the tcp keep alive interval.
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    /// Set the maximum number of idle pooled connections per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// Set the TLS settings.
+    pub fn tls(mut self, tls: TlsSettings) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Set the proxy, extra root certificates and user agent.
+    pub fn network(mut self, network: NetworkSettings) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Set how the base url host is resolved.
+    pub fn dns_resolution(mut self, resolution: DnsResolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Set the maximum number of requests in flight. Event streams are not
+    /// counted.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.max_concurrent_requests = max;
+        self
+    }
+
+    /// Set the token bucket rate limit for requests. Event streams are not
+    /// rate limited.
+    pub fn rate_limit(mut self, rate_limit: Option<RateLimit>) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Set the retry policy for requests that fail with a transient error.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set where responses come from. Record to or replay from a fixture
+    /// directory for offline runs.
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Serve fresh items from the cache and store fetched items in it.
+    pub fn item_cache(mut self, cache: Option<Arc<ItemCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Build the API client.
+    pub fn build(self) -> ApiResult<ApiClient> {
+        let url = Url::parse(&self.base_url).map_err(|err| {
+            ApiError::Config(format!("Invalid base url {}: {err}", self.base_url))
+        })?;
+
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .gzip(true)
+            .tcp_keepalive(self.tcp_keepalive)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .use_rustls_tls()
+            .https_only(self.tls.https_only)
+            .danger_accept_invalid_certs(self.tls.danger_accept_invalid_certs);
+        builder = self.network.apply(builder)?;
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(version) = self.tls.min_version {
+            builder = builder.min_tls_version(version);
+        }
+
+        if let Transport::Replay {
+            timing: ReplayTiming::Faster(speed),
+            ..
+        } = self.transport
+        {
+            if speed.is_nan() || speed <= 0. {
+                return Err(ApiError::Config(format!(
+                    "Replay speed must be above zero, got {speed}"
+                )));
+            }
+        }
+        let tape = Tape::open(&self.transport).map_err(|err| {
+            ApiError::Config(format!("Failed to open {:?}: {err}", self.transport))
+        })?;
+        // A replaying client makes no requests so there is nothing to resolve
+        // and a pinned resolver would send the proxy lookup to the base url
+        // host.
+        let resolution = match (&tape, self.resolution) {
+            (Some(Tape::Replay(_)), _) => DnsResolution::System,
+            (_, DnsResolution::Pinned) if self.network.proxy.is_proxied() => DnsResolution::System,
+            (_, resolution) => resolution,
+        };
+
+        builder = match resolution {
+            DnsResolution::Pinned => {
+                let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+                    return Err(ApiError::Config(format!(
+                        "Base url {} has no host or port",
+                        self.base_url
+                    )));
+                };
+                let resolver = Resolver::new(&format!("{host}:{port}")).map_err(|err| {
+                    ApiError::Config(format!("Failed to resolve {host}:{port}: {err}"))
+                })?;
+                builder.dns_resolver(Arc::new(resolver))
+            }
+            DnsResolution::System => builder,
+            DnsResolution::Custom(resolver) => {
+                builder.dns_resolver(Arc::new(SharedResolver(resolver)))
+            }
+        };
+
+        Ok(ApiClient {
+            client: builder.build().map_err(|err| {
+                ApiError::Config(format!("Failed to build the http client: {err}"))
+            })?,
+            base_url: self.base_url,
+            throttle: Throttle::new(self.max_concurrent_requests, self.rate_limit, self.retry),
+            tape,
+            cache: self.cache,
+            metrics: Default::default(),
+        })
+    }
+}
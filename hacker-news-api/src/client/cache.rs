@@ -0,0 +1,391 @@
+//! Persistent item cache.
+//!
+//! Each item is stored as a json file holding the item and the time it was
+//! fetched. Whether a cached item can be used depends on its age: stories
+//! keep changing score and comment count, but comments can only be edited
+//! for a short while after they are posted. Missing items are never cached
+//! since they may still be written.
+//!
+//! Reads and writes use async file io. Loading the items stored by earlier
+//! runs and evicting beyond the entry limit run on a blocking thread in the
+//! background, started by the write that needs them.
+use crate::Item;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Number of item files in each cache sub directory.
+const SHARD_SIZE: u64 = 10_000;
+
+/// How long cached items can be used before they are fetched again.
+#[derive(Debug, Clone, Copy)]
+pub struct FreshnessPolicy {
+    /// How long an item that may still change is used.
+    pub max_age: Duration,
+    /// How long after posting a comment can be edited.
+    pub edit_window: Duration,
+    /// How long a comment fetched after its edit window is used. `None`
+    /// never fetches it again.
+    pub settled_max_age: Option<Duration>,
+}
+
+impl Default for FreshnessPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: Duration::from_secs(60),
+            edit_window: Duration::from_secs(2 * 60 * 60),
+            settled_max_age: None,
+        }
+    }
+}
+
+impl FreshnessPolicy {
+    /// Whether an item fetched at `fetched` can still be used at `now`. Both
+    /// are unix times in seconds.
+    pub fn is_fresh(&self, item: &Item, fetched: u64, now: u64) -> bool {
+        let settled =
+            item.ty == "comment" && fetched.saturating_sub(item.time) >= self.edit_window.as_secs();
+        let max_age = if settled {
+            self.settled_max_age
+        } else {
+            Some(self.max_age)
+        };
+        max_age.is_none_or(|max_age| now.saturating_sub(fetched) < max_age.as_secs())
+    }
+}
+
+/// Item cache settings.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheOptions {
+    pub freshness: FreshnessPolicy,
+    /// Number of items kept. The least recently fetched items are evicted
+    /// beyond this.
+    pub max_entries: usize,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            freshness: FreshnessPolicy::default(),
+            max_entries: 200_000,
+        }
+    }
+}
+
+/// Cache counters since the cache was opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups for items that were not cached.
+    pub misses: u64,
+    /// Lookups for cached items that were too old to use.
+    pub stale: u64,
+    /// Items removed to stay within the entry limit.
+    pub evictions: u64,
+}
+
+/// Cached items known to the process.
+#[derive(Default)]
+struct Index {
+    /// Time each cached item was written, used to pick evictions.
+    written: HashMap<u64, SystemTime>,
+    /// Whether the items stored by earlier runs have been added.
+    loaded: bool,
+}
+
+/// A stored item.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Unix time in seconds the item was fetched.
+    fetched: u64,
+    item: Item,
+}
+
+/// An on disk cache of items keyed by id. It can be shared by clients in
+/// the same process and by processes using the same directory.
+pub struct ItemCache {
+    dir: PathBuf,
+    options: CacheOptions,
+    index: Mutex<Index>,
+    /// Held while loading the index or evicting.
+    maintenance: tokio::sync::Mutex<()>,
+    /// A background maintenance task is waiting to run.
+    maintenance_pending: AtomicBool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ItemCache {
+    /// Open or create a cache in `dir`. The items stored by earlier runs are
+    /// found by [`ItemCache::maintain`].
+    pub fn open(dir: impl Into<PathBuf>, options: CacheOptions) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        debug!("Opened item cache {dir:?}");
+
+        Ok(Self {
+            dir,
+            options,
+            index: Mutex::default(),
+            maintenance: tokio::sync::Mutex::new(()),
+            maintenance_pending: AtomicBool::new(false),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stale: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        })
+    }
+
+    /// The cached item if it is still fresh.
+    pub async fn get(&self, id: u64) -> Option<Item> {
+        let evicted = {
+            let index = self.index.lock().unwrap();
+            index.loaded && !index.written.contains_key(&id)
+        };
+        let entry = match tokio::fs::read(self.path(id)).await {
+            // The file of an evicted item may not be removed yet.
+            Ok(_) if evicted => None,
+            Ok(content) => serde_json::from_slice::<Entry>(&content)
+                .inspect_err(|err| warn!("Ignoring corrupt cache entry {id}: {err}"))
+                .ok(),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Failed to read cache entry {id}: {err}");
+                None
+            }
+        };
+
+        match entry {
+            Some(entry)
+                if self
+                    .options
+                    .freshness
+                    .is_fresh(&entry.item, entry.fetched, now()) =>
+            {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.item)
+            }
+            Some(_) => {
+                debug!("Cached item {id} is stale");
+                self.stale.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Store a freshly fetched item. Maintenance is started in the
+    /// background when the index isn't loaded yet or is over the limit.
+    pub async fn put(self: &Arc<Self>, item: &Item) {
+        if let Err(err) = self.write(item).await {
+            warn!("Failed to cache item {}: {err}", item.id);
+            return;
+        }
+
+        let needs_maintenance = {
+            let mut index = self.index.lock().unwrap();
+            index.written.insert(item.id, SystemTime::now());
+            !index.loaded || index.written.len() > self.options.max_entries
+        };
+        if needs_maintenance && !self.maintenance_pending.swap(true, Ordering::AcqRel) {
+            let cache = self.clone();
+            tokio::spawn(async move { cache.maintain().await });
+        }
+    }
+
+    /// Remove an item so the next lookup fetches it.
+    pub async fn remove(&self, id: u64) {
+        self.index.lock().unwrap().written.remove(&id);
+        if let Err(err) = tokio::fs::remove_file(self.path(id)).await {
+            if err.kind() != ErrorKind::NotFound {
+                warn!("Failed to remove cache entry {id}: {err}");
+            }
+        }
+    }
+
+    /// Add the items stored by earlier runs to the index if that wasn't done
+    /// yet and evict the least recently written items beyond the entry
+    /// limit. Runs on a blocking thread and waits for maintenance already
+    /// under way.
+    pub async fn maintain(self: &Arc<Self>) {
+        let _guard = self.maintenance.lock().await;
+        self.maintenance_pending.store(false, Ordering::Release);
+
+        let cache = self.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            cache.load();
+            cache.evict();
+        })
+        .await;
+        if let Err(err) = result {
+            warn!("Item cache maintenance failed: {err}");
+        }
+    }
+
+    /// Number of cached items known so far. Items stored by earlier runs are
+    /// counted once [`ItemCache::maintain`] has run.
+    pub fn len(&self) -> usize {
+        self.index.lock().unwrap().written.len()
+    }
+
+    /// `true` when no items are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Counters since the cache was opened.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stale: self.stale.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir
+            .join((id / SHARD_SIZE).to_string())
+            .join(format!("{id}.json"))
+    }
+
+    /// Write the entry to a temporary file and move it in place so readers
+    /// never see a partial entry. The temporary file is unique to the write
+    /// so concurrent writes of an item don't interleave.
+    async fn write(&self, item: &Item) -> io::Result<()> {
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+
+        let path = self.path(item.id);
+        if let Some(shard) = path.parent() {
+            tokio::fs::create_dir_all(shard).await?;
+        }
+        let content = serde_json::to_vec(&Entry {
+            fetched: now(),
+            item: item.clone(),
+        })?;
+        let temp = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temp, content).await?;
+        tokio::fs::rename(&temp, &path).await
+    }
+
+    /// Add the items stored by earlier runs to the index once. Items written
+    /// since the cache was opened keep their time.
+    fn load(&self) {
+        if self.index.lock().unwrap().loaded {
+            return;
+        }
+        let stored = match self.stored_items() {
+            Ok(stored) => stored,
+            Err(err) => {
+                warn!("Failed to list cached items in {:?}: {err}", self.dir);
+                Vec::new()
+            }
+        };
+
+        let mut index = self.index.lock().unwrap();
+        for (id, written) in stored {
+            index.written.entry(id).or_insert(written);
+        }
+        index.loaded = true;
+        debug!("Loaded item cache index with {} items", index.written.len());
+    }
+
+    /// Id and modification time of every item file.
+    fn stored_items(&self) -> io::Result<Vec<(u64, SystemTime)>> {
+        let mut stored = Vec::new();
+        for shard in fs::read_dir(&self.dir)? {
+            let shard = shard?;
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(shard.path())? {
+                let file = file?;
+                let id = file
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|id| id.parse().ok());
+                if let Some(id) = id {
+                    stored.push((id, file.metadata()?.modified()?));
+                }
+            }
+        }
+        Ok(stored)
+    }
+
+    /// Remove the least recently written tenth of the entries when over the
+    /// limit. The entries are sorted and their files removed without holding
+    /// the index lock.
+    fn evict(&self) {
+        let mut entries = {
+            let index = self.index.lock().unwrap();
+            if index.written.len() <= self.options.max_entries {
+                return;
+            }
+            index
+                .written
+                .iter()
+                .map(|(id, written)| (*written, *id))
+                .collect::<Vec<_>>()
+        };
+        let target = self.options.max_entries - self.options.max_entries / 10;
+        let count = entries.len() - target;
+        entries.sort_unstable();
+
+        let mut evicted = Vec::with_capacity(count);
+        {
+            let mut index = self.index.lock().unwrap();
+            for (written, id) in entries.into_iter().take(count) {
+                // Skip items written again since.
+                if index.written.get(&id) == Some(&written) {
+                    index.written.remove(&id);
+                    evicted.push(id);
+                }
+            }
+        }
+        for id in &evicted {
+            remove_file(&self.path(*id));
+        }
+        self.evictions
+            .fetch_add(evicted.len() as u64, Ordering::Relaxed);
+        debug!("Evicted {} cached items", evicted.len());
+    }
+}
+
+fn remove_file(path: &Path) {
+    if let Err(err) = fs::remove_file(path) {
+        if err.kind() != ErrorKind::NotFound {
+            warn!("Failed to remove cache entry {path:?}: {err}");
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod cache_tests;
@@ -0,0 +1,138 @@
+use super::{CacheOptions, CacheStats, FreshnessPolicy, ItemCache};
+use crate::{ApiClient, Item, ReplayTiming, Transport};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+const HOUR: u64 = 60 * 60;
+
+fn item(id: u64, ty: &str, time: u64) -> Item {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "type": ty,
+        "by": "a",
+        "time": time,
+    }))
+    .unwrap()
+}
+
+/// An empty cache directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("hn-cache-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn freshness_depends_on_age() {
+    let policy = FreshnessPolicy::default();
+    let story = item(1, "story", 0);
+    assert!(policy.is_fresh(&story, 10 * HOUR, 10 * HOUR + 30));
+    assert!(!policy.is_fresh(&story, 10 * HOUR, 10 * HOUR + 120));
+
+    // Fetched while it could still be edited.
+    let comment = item(2, "comment", 0);
+    assert!(!policy.is_fresh(&comment, HOUR, HOUR + 120));
+    // Fetched after the edit window.
+    assert!(policy.is_fresh(&comment, 3 * HOUR, 1000 * HOUR));
+
+    let policy = FreshnessPolicy {
+        settled_max_age: Some(Duration::from_secs(24 * HOUR)),
+        ..Default::default()
+    };
+    assert!(policy.is_fresh(&comment, 3 * HOUR, 20 * HOUR));
+    assert!(!policy.is_fresh(&comment, 3 * HOUR, 30 * HOUR));
+}
+
+#[tokio::test]
+async fn stores_and_evicts() {
+    let dir = TempDir::new("evict");
+    let options = CacheOptions {
+        max_entries: 10,
+        ..Default::default()
+    };
+    let cache = Arc::new(ItemCache::open(&dir.0, options).unwrap());
+
+    assert!(cache.get(1).await.is_none());
+    for id in 1..=11 {
+        cache.put(&item(id, "comment", 0)).await;
+    }
+    // Eviction runs in the background.
+    cache.maintain().await;
+    assert_eq!(cache.len(), 9);
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            misses: 1,
+            evictions: 2,
+            ..Default::default()
+        }
+    );
+
+    let mut cached = 0;
+    for id in 1..=11 {
+        cached += usize::from(cache.get(id).await.is_some());
+    }
+    assert_eq!(cached, 9);
+    assert_eq!(cache.stats().hits, 9);
+
+    cache.remove(11).await;
+    let cache = Arc::new(ItemCache::open(&dir.0, options).unwrap());
+    assert_eq!(cache.len(), 0);
+    cache.maintain().await;
+    assert_eq!(cache.len(), 8);
+}
+
+#[tokio::test]
+async fn concurrent_writes_of_an_item() {
+    let dir = TempDir::new("concurrent");
+    let cache = Arc::new(ItemCache::open(&dir.0, CacheOptions::default()).unwrap());
+
+    let writes = (0..20).map(|_| {
+        let cache = cache.clone();
+        tokio::spawn(async move { cache.put(&item(1, "story", 0)).await })
+    });
+    for write in writes.collect::<Vec<_>>() {
+        write.await.unwrap();
+    }
+
+    assert_eq!(cache.get(1).await.map(|item| item.id), Some(1));
+    cache.maintain().await;
+    assert_eq!(cache.len(), 1);
+    // No temporary files are left behind.
+    let shard = std::fs::read_dir(dir.0.join("0")).unwrap().count();
+    assert_eq!(shard, 1);
+}
+
+#[tokio::test]
+async fn client_uses_cache() {
+    let dir = TempDir::new("client");
+    let cache = Arc::new(ItemCache::open(&dir.0, CacheOptions::default()).unwrap());
+    let client = ApiClient::builder()
+        .transport(Transport::Replay {
+            dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/client/comment_tree/fixtures"),
+            timing: ReplayTiming::Immediate,
+        })
+        .item_cache(Some(cache.clone()))
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        assert_eq!(client.item(5).await.unwrap().id, 5);
+    }
+    // Missing items are not cached.
+    for _ in 0..2 {
+        assert!(client.item(4).await.is_err());
+    }
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (1, 3));
+}
@@ -0,0 +1,103 @@
+//! Global change feed.
+//!
+//! A single supervised subscription to the `updates` end point is shared by
+//! any number of listeners. Listeners receive every batch of changed items
+//! and profiles and pick out the ids they care about.
+use super::{ApiClient, Subscription, SubscriptionStatus, SupervisorConfig};
+use crate::types::{Updates, UpdatesEventData};
+use futures::Stream;
+use log::{info, warn};
+use std::sync::Arc;
+use tokio::{
+    sync::{broadcast, watch},
+    task::AbortHandle,
+};
+
+/// Shared subscription to the updates end point.
+pub struct ChangeFeed {
+    sender: broadcast::Sender<Arc<Updates>>,
+    status: watch::Receiver<SubscriptionStatus>,
+    abort_handles: [AbortHandle; 2],
+}
+
+impl ChangeFeed {
+    /// Start the change feed. Listeners that fall more than `capacity`
+    /// batches behind skip the oldest batches.
+    pub fn start(client: &Arc<ApiClient>, config: SupervisorConfig, capacity: usize) -> Self {
+        Self::from_subscription(client.subscribe_updates(config), capacity)
+    }
+
+    /// Share the batches received on an updates subscription.
+    fn from_subscription(subscription: Subscription<UpdatesEventData>, capacity: usize) -> Self {
+        let Subscription {
+            mut receiver,
+            status,
+            handle,
+        } = subscription;
+        let (sender, _) = broadcast::channel(capacity.max(1));
+
+        let forward_sender = sender.clone();
+        let forward = tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                // Having no listeners is not an error, there may be some later.
+                let _ = forward_sender.send(Arc::new(event.data));
+            }
+            info!("Change feed has ended");
+        });
+
+        Self {
+            sender,
+            status,
+            abort_handles: [handle.abort_handle(), forward.abort_handle()],
+        }
+    }
+
+    /// Listen to batches of changes.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Updates>> {
+        self.sender.subscribe()
+    }
+
+    /// Connection state of the underlying subscription.
+    pub fn status(&self) -> watch::Receiver<SubscriptionStatus> {
+        self.status.clone()
+    }
+
+    /// Stream of item ids from `ids` that have changed. Each batch is a
+    /// snapshot of recently changed items, so an item that keeps changing
+    /// stays in it and is yielded for every batch. Listeners compare the
+    /// fetched item with the one they have to skip repeats.
+    pub fn changed_items(&self, ids: Vec<u64>) -> impl Stream<Item = u64> {
+        let mut receiver = self.subscribe();
+        async_stream::stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(updates) => {
+                        for id in updates.items.iter().filter(|id| ids.contains(id)) {
+                            yield *id;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Change feed listener skipped {skipped} updates");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    /// Stop the change feed.
+    pub fn abort(&self) {
+        for handle in &self.abort_handles {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for ChangeFeed {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+#[cfg(test)]
+mod change_feed_tests;
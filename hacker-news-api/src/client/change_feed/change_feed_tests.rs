@@ -0,0 +1,106 @@
+use super::ChangeFeed;
+use crate::{
+    client::{Subscription, SubscriptionStatus},
+    types::{Updates, UpdatesEventData},
+};
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::{
+    sync::{mpsc, watch},
+    time::timeout,
+};
+
+/// A change feed fed by the returned sender.
+fn feed(capacity: usize) -> (mpsc::Sender<UpdatesEventData>, ChangeFeed) {
+    let (tx, receiver) = mpsc::channel(100);
+    let (_, status) = watch::channel(SubscriptionStatus::Live);
+    let subscription = Subscription {
+        receiver,
+        status,
+        handle: tokio::spawn(std::future::pending()),
+    };
+    (tx, ChangeFeed::from_subscription(subscription, capacity))
+}
+
+fn updates(items: &[u64]) -> UpdatesEventData {
+    UpdatesEventData {
+        path: "/".to_owned(),
+        data: Updates {
+            items: items.to_vec(),
+            profiles: Vec::new(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn batches_reach_every_listener() {
+    let (tx, feed) = feed(8);
+    let mut first = feed.subscribe();
+    let mut second = feed.subscribe();
+
+    tx.send(updates(&[1, 2])).await.unwrap();
+    tx.send(updates(&[3])).await.unwrap();
+
+    for receiver in [&mut first, &mut second] {
+        assert_eq!(receiver.recv().await.unwrap().items, [1, 2]);
+        assert_eq!(receiver.recv().await.unwrap().items, [3]);
+    }
+    feed.abort();
+}
+
+#[tokio::test]
+async fn changed_items_picks_out_ids() {
+    let (tx, feed) = feed(8);
+    let changes = feed.changed_items(vec![2, 4]);
+
+    tx.send(updates(&[1, 2, 3, 4])).await.unwrap();
+    tx.send(updates(&[5])).await.unwrap();
+    tx.send(updates(&[4, 6])).await.unwrap();
+
+    let changes = timeout(Duration::from_secs(5), changes.take(3).collect::<Vec<_>>())
+        .await
+        .unwrap();
+    assert_eq!(changes, [2, 4, 4]);
+}
+
+#[tokio::test]
+async fn changed_items_repeats_ids_still_listed() {
+    let (tx, feed) = feed(8);
+    let changes = feed.changed_items(vec![1, 2]);
+
+    tx.send(updates(&[1])).await.unwrap();
+    // Still in the snapshot, it may have changed again.
+    tx.send(updates(&[1, 2])).await.unwrap();
+    tx.send(updates(&[2, 1])).await.unwrap();
+    tx.send(updates(&[3])).await.unwrap();
+    tx.send(updates(&[1])).await.unwrap();
+
+    let changes = timeout(Duration::from_secs(5), changes.take(6).collect::<Vec<_>>())
+        .await
+        .unwrap();
+    assert_eq!(changes, [1, 1, 2, 2, 1, 1]);
+}
+
+#[tokio::test]
+async fn dropped_feed_stops_its_tasks() {
+    let (tx, feed) = feed(8);
+
+    drop(feed);
+    // The forwarding task held the receiving end.
+    timeout(Duration::from_secs(5), tx.closed()).await.unwrap();
+}
+
+#[tokio::test]
+async fn lagging_listener_skips_oldest_batches() {
+    let (tx, feed) = feed(2);
+    let changes = feed.changed_items(vec![1, 2, 3]);
+
+    tx.send(updates(&[1])).await.unwrap();
+    tx.send(updates(&[2])).await.unwrap();
+    tx.send(updates(&[3])).await.unwrap();
+
+    let changes = timeout(Duration::from_secs(5), changes.take(2).collect::<Vec<_>>())
+        .await
+        .unwrap();
+    assert_eq!(changes, [2, 3]);
+}
@@ -0,0 +1,236 @@
+//! Comment tree fetching.
+//!
+//! Trees are fetched breadth first one level at a time. Each level is
+//! fetched with the client's concurrency cap so wide threads don't open a
+//! request per comment all at once.
+use super::ApiClient;
+use crate::{ApiResult, Item, ItemResult};
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use log::warn;
+use std::{collections::HashMap, pin::pin};
+
+/// Limits for a comment tree fetch.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TreeOptions {
+    /// Deepest level to fetch. The root is at depth 0 and its kids at 1.
+    pub max_depth: Option<usize>,
+    /// Maximum number of items to fetch including the root.
+    pub max_nodes: Option<usize>,
+}
+
+/// An item and its place in a comment tree. Deleted and dead items are kept
+/// so their replies are still reachable.
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub item: Item,
+    /// Parent in the tree, `None` for the root.
+    pub parent: Option<u64>,
+    /// Distance from the root.
+    pub depth: usize,
+    /// Position amongst its siblings.
+    pub rank: usize,
+}
+
+/// A fetched comment tree.
+#[derive(Debug, Clone)]
+pub struct CommentTree {
+    /// Nodes in breadth first order starting with the root.
+    nodes: Vec<TreeNode>,
+    /// Position of each id in `nodes`.
+    index: HashMap<u64, usize>,
+    /// Number of descendants that failed to fetch.
+    failed: usize,
+}
+
+impl CommentTree {
+    fn new(nodes: Vec<TreeNode>, failed: usize) -> Self {
+        let index = nodes
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (node.item.id, position))
+            .collect();
+        Self {
+            nodes,
+            index,
+            failed,
+        }
+    }
+
+    /// The root item.
+    pub fn root(&self) -> &TreeNode {
+        &self.nodes[0]
+    }
+
+    /// A node by item id.
+    pub fn get(&self, id: u64) -> Option<&TreeNode> {
+        self.index.get(&id).map(|position| &self.nodes[*position])
+    }
+
+    /// The parent of a node.
+    pub fn parent(&self, id: u64) -> Option<&TreeNode> {
+        self.get(id)?.parent.and_then(|parent| self.get(parent))
+    }
+
+    /// Fetched children of a node in display order. Children beyond the
+    /// depth or node limits and missing items are left out.
+    pub fn children(&self, id: u64) -> impl Iterator<Item = &TreeNode> {
+        self.get(id)
+            .map(|node| node.item.kids.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|kid| self.get(*kid))
+    }
+
+    /// Nodes in breadth first order starting with the root.
+    pub fn nodes(&self) -> &[TreeNode] {
+        &self.nodes
+    }
+
+    /// Nodes in depth first order with each node followed by its replies,
+    /// the order comments are displayed in.
+    pub fn depth_first(&self) -> impl Iterator<Item = &TreeNode> {
+        let mut stack = vec![self.root()];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let children = self.children(node.item.id).collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Nodes in breadth first order.
+    pub fn into_nodes(self) -> Vec<TreeNode> {
+        self.nodes
+    }
+
+    /// Number of descendants that failed to fetch. They are left out of the
+    /// tree along with their replies.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+}
+
+impl ApiClient {
+    /// Fetch an item and its descendants within the limits of `options`. A
+    /// failure to fetch the root is an error. Descendants that fail to fetch
+    /// are counted in [`CommentTree::failed`] and the rest of the tree is
+    /// returned.
+    pub async fn comment_tree(&self, root_id: u64, options: TreeOptions) -> ApiResult<CommentTree> {
+        let mut stream = pin!(self.comment_tree_stream(root_id, options));
+        let mut nodes = Vec::new();
+        let mut failed = 0;
+        while let Some(node) = stream.next().await {
+            match node {
+                Ok(node) => nodes.push(node),
+                // The root failed to fetch.
+                Err(err) if nodes.is_empty() => return Err(err),
+                Err(err) => {
+                    warn!("Failed to fetch a comment of {root_id}: {err}");
+                    failed += 1;
+                }
+            }
+        }
+        Ok(CommentTree::new(nodes, failed))
+    }
+
+    /// Stream an item and its descendants breadth first as they arrive. A
+    /// failure to fetch the root ends the stream. Failures to fetch a
+    /// descendant are yielded and the stream carries on without it.
+    pub fn comment_tree_stream(
+        &self,
+        root_id: u64,
+        options: TreeOptions,
+    ) -> impl Stream<Item = ApiResult<TreeNode>> + use<'_> {
+        async_stream::stream! {
+            let root = match self.item(root_id).await {
+                Ok(root) => root,
+                Err(err) => {
+                    yield Err(err);
+                    return;
+                }
+            };
+            let kids = root.kids.clone();
+            yield Ok(TreeNode {
+                item: root,
+                parent: None,
+                depth: 0,
+                rank: 0,
+            });
+
+            let options = TreeOptions {
+                max_depth: options.max_depth,
+                max_nodes: options.max_nodes.map(|max| max.saturating_sub(1)),
+            };
+            let mut descendants = pin!(self.descendants_stream(root_id, kids, options));
+            while let Some(node) = descendants.next().await {
+                yield node;
+            }
+        }
+    }
+
+    /// Stream the descendants of an item that has already been fetched,
+    /// breadth first, starting with its `kids` at depth 1. `max_nodes` counts
+    /// descendants only.
+    pub fn descendants_stream(
+        &self,
+        parent_id: u64,
+        kids: Vec<u64>,
+        options: TreeOptions,
+    ) -> impl Stream<Item = ApiResult<TreeNode>> + use<'_> {
+        async_stream::stream! {
+            let mut remaining = options.max_nodes.unwrap_or(usize::MAX);
+            let mut level = kids
+                .into_iter()
+                .enumerate()
+                .map(|(rank, id)| (parent_id, rank, id))
+                .collect::<Vec<_>>();
+            let mut depth = 1;
+
+            while !level.is_empty()
+                && remaining > 0
+                && options.max_depth.is_none_or(|max_depth| depth <= max_depth)
+            {
+                level.truncate(remaining);
+                let ids = level.iter().map(|(_, _, id)| *id).collect::<Vec<_>>();
+                let mut results = pin!(self
+                    .item_results(&ids)
+                    .into_stream()
+                    .zip(stream::iter(level)));
+                let mut next_level = Vec::new();
+
+                while let Some((result, (parent, rank, id))) = results.next().await {
+                    let item = match result.map(ItemResult::into_item) {
+                        Ok(Some(item)) => item,
+                        Ok(None) => {
+                            warn!("Comment {id} is missing");
+                            continue;
+                        }
+                        Err(err) => {
+                            yield Err(err);
+                            continue;
+                        }
+                    };
+                    next_level.extend(
+                        item.kids
+                            .iter()
+                            .enumerate()
+                            .map(|(rank, kid)| (item.id, rank, *kid)),
+                    );
+                    remaining -= 1;
+                    yield Ok(TreeNode {
+                        item,
+                        parent: Some(parent),
+                        depth,
+                        rank,
+                    });
+                }
+
+                level = next_level;
+                depth += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod comment_tree_tests;
@@ -0,0 +1,164 @@
+use super::TreeOptions;
+use crate::{ApiClient, ReplayTiming, Transport, RECORDING_FILE};
+use futures::TryStreamExt as _;
+use std::path::PathBuf;
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/client/comment_tree/fixtures")
+}
+
+/// Replays a story with a deleted comment and a missing comment:
+///
+/// ```text
+/// 1
+/// ├── 2
+/// │   ├── 5
+/// │   │   └── 8
+/// │   └── 6
+/// ├── 3 (deleted)
+/// │   └── 7
+/// └── 4 (missing)
+/// ```
+fn client() -> ApiClient {
+    ApiClient::builder()
+        .transport(Transport::Replay {
+            dir: fixtures(),
+            timing: ReplayTiming::Immediate,
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn whole_tree() {
+    let tree = client()
+        .comment_tree(1, TreeOptions::default())
+        .await
+        .unwrap();
+
+    let ids = tree
+        .nodes()
+        .iter()
+        .map(|node| node.item.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3, 5, 6, 7, 8]);
+
+    let ids = tree
+        .depth_first()
+        .map(|node| node.item.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 5, 8, 6, 3, 7]);
+
+    let node = tree.get(8).unwrap();
+    assert_eq!((node.parent, node.depth, node.rank), (Some(5), 3, 0));
+    assert_eq!(tree.parent(6).unwrap().item.id, 2);
+    assert_eq!(tree.get(6).unwrap().rank, 1);
+    assert!(tree.get(3).unwrap().item.deleted);
+
+    let children = tree
+        .children(1)
+        .map(|node| node.item.id)
+        .collect::<Vec<_>>();
+    assert_eq!(children, [2, 3]);
+}
+
+#[tokio::test]
+async fn limits() {
+    let client = client();
+
+    let tree = client
+        .comment_tree(
+            1,
+            TreeOptions {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let ids = tree
+        .nodes()
+        .iter()
+        .map(|node| node.item.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3]);
+    assert_eq!(tree.children(2).count(), 0);
+
+    let tree = client
+        .comment_tree(
+            1,
+            TreeOptions {
+                max_nodes: Some(4),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let ids = tree
+        .nodes()
+        .iter()
+        .map(|node| node.item.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3, 5]);
+}
+
+#[tokio::test]
+async fn descendants_of_fetched_item() {
+    let nodes = client()
+        .descendants_stream(2, vec![5, 6], TreeOptions::default())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let ids = nodes
+        .iter()
+        .map(|node| (node.item.id, node.depth))
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [(5, 1), (6, 1), (8, 2)]);
+}
+
+#[tokio::test]
+async fn failed_descendants_are_counted() {
+    // Comment 5 isn't recorded so fetching it fails.
+    let dir = std::env::temp_dir().join(format!("hn-comment-tree-failed-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let recording = std::fs::read_to_string(fixtures().join(RECORDING_FILE)).unwrap();
+    let recording = recording
+        .lines()
+        .filter(|line| !line.contains("\"item/5.json\""))
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(dir.join(RECORDING_FILE), recording).unwrap();
+    let client = ApiClient::builder()
+        .transport(Transport::Replay {
+            dir: dir.clone(),
+            timing: ReplayTiming::Immediate,
+        })
+        .build()
+        .unwrap();
+
+    let tree = client
+        .comment_tree(1, TreeOptions::default())
+        .await
+        .unwrap();
+    // The replies of the failed comment are left out with it.
+    let ids = tree
+        .nodes()
+        .iter()
+        .map(|node| node.item.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3, 6, 7]);
+    assert_eq!(tree.failed(), 1);
+
+    let tree = client.item_tree(1).await.unwrap();
+    let ids = tree.items.iter().map(|item| item.id).collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 6, 3, 7]);
+    assert_eq!(tree.failed, 1);
+
+    // The root failing is an error.
+    assert!(client
+        .comment_tree(9, TreeOptions::default())
+        .await
+        .is_err());
+    let _ = std::fs::remove_dir_all(dir);
+}
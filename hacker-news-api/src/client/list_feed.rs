@@ -0,0 +1,235 @@
+//! Shared article list deltas.
+//!
+//! Article list events carry the whole list of ids. A [`ListFeed`] diffs
+//! each list against the previous one and broadcasts the ids that were
+//! added, removed and moved. Every delta has a sequence number so listeners
+//! can tell when they have skipped deltas and resync from [`ListDelta::ids`].
+use super::{ApiClient, Subscription, SubscriptionStatus, SupervisorConfig};
+use crate::{ArticleType, StoriesEventData};
+use log::info;
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    sync::{broadcast, watch},
+    task::AbortHandle,
+};
+
+/// An id and its zero based position in a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RankedId {
+    pub id: u64,
+    pub rank: usize,
+}
+
+/// An id that changed position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovedId {
+    pub id: u64,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Changes between two versions of an article list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListDelta {
+    /// Starts at 0 and increments by one for every delta of a feed.
+    pub seq: u64,
+    pub article_type: ArticleType,
+    /// Ids that are new to the list with their rank.
+    pub added: Vec<RankedId>,
+    /// Ids no longer in the list with their previous rank.
+    pub removed: Vec<RankedId>,
+    /// Ids in both lists with a different rank.
+    pub moved: Vec<MovedId>,
+    /// The whole list after this delta.
+    pub ids: Arc<[u64]>,
+}
+
+impl ListDelta {
+    /// Diff `current` against `previous`. The first delta of a feed has an
+    /// empty `previous` so every id is added.
+    pub fn diff(
+        seq: u64,
+        article_type: ArticleType,
+        previous: &[u64],
+        current: Arc<[u64]>,
+    ) -> Self {
+        let previous_ranks = ranks(previous);
+        let current_ranks = ranks(&current);
+
+        let mut added = Vec::new();
+        let mut moved = Vec::new();
+        for (rank, id) in current.iter().copied().enumerate() {
+            match previous_ranks.get(&id) {
+                None => added.push(RankedId { id, rank }),
+                Some(&from) if from != rank => moved.push(MovedId { id, from, to: rank }),
+                Some(_) => (),
+            }
+        }
+
+        let removed = previous
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, id)| !current_ranks.contains_key(id))
+            .map(|(rank, id)| RankedId { id, rank })
+            .collect();
+
+        Self {
+            seq,
+            article_type,
+            added,
+            removed,
+            moved,
+            ids: current,
+        }
+    }
+
+    /// No ids were added, removed or moved.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+
+    /// How far `id` moved up the list, negative when it moved down. `0` when
+    /// it was added or did not move.
+    pub fn rank_change(&self, id: u64) -> i64 {
+        self.moved
+            .iter()
+            .find(|moved| moved.id == id)
+            .map(|moved| moved.from as i64 - moved.to as i64)
+            .unwrap_or_default()
+    }
+}
+
+fn ranks(ids: &[u64]) -> HashMap<u64, usize> {
+    ids.iter()
+        .copied()
+        .enumerate()
+        .map(|(rank, id)| (id, rank))
+        .collect()
+}
+
+/// Shared subscription to the deltas of an article list. The subscription
+/// is stopped when the feed is dropped.
+pub struct ListFeed {
+    article_type: ArticleType,
+    sender: broadcast::Sender<Arc<ListDelta>>,
+    status: watch::Receiver<SubscriptionStatus>,
+    abort_handles: [AbortHandle; 2],
+}
+
+impl ListFeed {
+    /// Start the list feed. Only the first `limit` ids of the list are
+    /// diffed when set. Listeners that fall more than `capacity` deltas
+    /// behind skip the oldest deltas.
+    pub fn start(
+        client: &Arc<ApiClient>,
+        article_type: ArticleType,
+        limit: Option<usize>,
+        config: SupervisorConfig,
+        capacity: usize,
+    ) -> Self {
+        let subscription = client.subscribe_article_list(article_type, config);
+        Self::from_subscription(article_type, limit, subscription, capacity)
+    }
+
+    /// Diff the lists received on an article list subscription.
+    fn from_subscription(
+        article_type: ArticleType,
+        limit: Option<usize>,
+        subscription: Subscription<StoriesEventData>,
+        capacity: usize,
+    ) -> Self {
+        let Subscription {
+            mut receiver,
+            status,
+            handle,
+        } = subscription;
+        let (sender, _) = broadcast::channel(capacity.max(1));
+
+        let forward_sender = sender.clone();
+        let mut forward_status = status.clone();
+        let forward = tokio::spawn(async move {
+            let mut previous: Option<Arc<[u64]>> = None;
+            let mut seq = 0;
+            // The first list after a reconnect is always sent, even when it is
+            // unchanged, so listeners know they are in sync again.
+            let mut resync = true;
+
+            loop {
+                let mut event = tokio::select! {
+                    biased;
+                    Ok(()) = forward_status.changed() => {
+                        if *forward_status.borrow_and_update() != SubscriptionStatus::Live {
+                            resync = true;
+                        }
+                        continue;
+                    }
+                    event = receiver.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                };
+
+                if let Some(limit) = limit {
+                    event.data.truncate(limit);
+                }
+                let current = Arc::<[u64]>::from(event.data);
+                let delta = ListDelta::diff(
+                    seq,
+                    article_type,
+                    previous.as_deref().unwrap_or_default(),
+                    current.clone(),
+                );
+                if !resync && delta.is_empty() {
+                    continue;
+                }
+                resync = false;
+                previous = Some(current);
+                seq += 1;
+                // Having no listeners is not an error, there may be some later.
+                let _ = forward_sender.send(Arc::new(delta));
+            }
+            info!("{article_type} list feed has ended");
+        });
+
+        Self {
+            article_type,
+            sender,
+            status,
+            abort_handles: [handle.abort_handle(), forward.abort_handle()],
+        }
+    }
+
+    /// The article list this feed is diffing.
+    pub fn article_type(&self) -> ArticleType {
+        self.article_type
+    }
+
+    /// Listen to deltas. A listener that joins late receives only the deltas
+    /// sent after it subscribed and should start from the next delta's
+    /// [`ListDelta::ids`].
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ListDelta>> {
+        self.sender.subscribe()
+    }
+
+    /// Connection state of the underlying subscription.
+    pub fn status(&self) -> watch::Receiver<SubscriptionStatus> {
+        self.status.clone()
+    }
+
+    /// Stop the list feed.
+    pub fn abort(&self) {
+        for handle in &self.abort_handles {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for ListFeed {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+#[cfg(test)]
+mod list_feed_tests;
@@ -0,0 +1,209 @@
+use super::{ListDelta, ListFeed, MovedId, RankedId};
+use crate::{
+    client::{Subscription, SubscriptionStatus},
+    ArticleType, StoriesEventData,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{broadcast, mpsc, watch},
+    time::timeout,
+};
+
+/// A list feed fed by the returned senders.
+fn feed(
+    limit: Option<usize>,
+) -> (
+    mpsc::Sender<StoriesEventData>,
+    watch::Sender<SubscriptionStatus>,
+    ListFeed,
+) {
+    let (tx, receiver) = mpsc::channel(100);
+    let (status_tx, status) = watch::channel(SubscriptionStatus::Connecting);
+    let subscription = Subscription {
+        receiver,
+        status,
+        handle: tokio::spawn(std::future::pending()),
+    };
+    let feed = ListFeed::from_subscription(ArticleType::Top, limit, subscription, 8);
+    (tx, status_tx, feed)
+}
+
+fn list(ids: &[u64]) -> StoriesEventData {
+    StoriesEventData {
+        path: "/".to_owned(),
+        data: ids.to_vec(),
+    }
+}
+
+async fn next(receiver: &mut broadcast::Receiver<Arc<ListDelta>>) -> Arc<ListDelta> {
+    timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .expect("no delta in time")
+        .unwrap()
+}
+
+/// Go through a reconnect like the supervisor, giving the feed time to see
+/// each status.
+async fn reconnect(status: &watch::Sender<SubscriptionStatus>) {
+    for next in [
+        SubscriptionStatus::Reconnecting {
+            attempt: 1,
+            next_in: Duration::from_millis(10),
+        },
+        SubscriptionStatus::Connecting,
+        SubscriptionStatus::Live,
+    ] {
+        status.send_replace(next);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[test]
+fn first_delta_adds_everything() {
+    let delta = ListDelta::diff(0, ArticleType::Top, &[], Arc::from([3, 1, 2]));
+
+    assert_eq!(
+        delta.added,
+        vec![
+            RankedId { id: 3, rank: 0 },
+            RankedId { id: 1, rank: 1 },
+            RankedId { id: 2, rank: 2 },
+        ]
+    );
+    assert!(delta.removed.is_empty());
+    assert!(delta.moved.is_empty());
+}
+
+#[test]
+fn added_removed_and_moved() {
+    let delta = ListDelta::diff(4, ArticleType::New, &[1, 2, 3, 4], Arc::from([5, 3, 1, 4]));
+
+    assert_eq!(delta.seq, 4);
+    assert_eq!(delta.added, vec![RankedId { id: 5, rank: 0 }]);
+    assert_eq!(delta.removed, vec![RankedId { id: 2, rank: 1 }]);
+    assert_eq!(
+        delta.moved,
+        vec![
+            MovedId {
+                id: 3,
+                from: 2,
+                to: 1
+            },
+            MovedId {
+                id: 1,
+                from: 0,
+                to: 2
+            },
+        ]
+    );
+    assert_eq!(delta.rank_change(3), 1);
+    assert_eq!(delta.rank_change(1), -2);
+    assert_eq!(delta.rank_change(4), 0);
+    assert_eq!(&*delta.ids, &[5, 3, 1, 4]);
+}
+
+#[test]
+fn unchanged_list_is_empty() {
+    let delta = ListDelta::diff(1, ArticleType::Best, &[1, 2], Arc::from([1, 2]));
+    assert!(delta.is_empty());
+}
+
+#[test]
+fn removed_only() {
+    let delta = ListDelta::diff(2, ArticleType::Top, &[1, 2, 3], Arc::from([1, 2]));
+
+    assert!(delta.added.is_empty());
+    assert_eq!(delta.removed, vec![RankedId { id: 3, rank: 2 }]);
+    assert!(delta.moved.is_empty());
+    assert!(!delta.is_empty());
+}
+
+#[test]
+fn swapped_ids_both_move() {
+    let delta = ListDelta::diff(1, ArticleType::Top, &[1, 2], Arc::from([2, 1]));
+
+    assert_eq!(
+        delta.moved,
+        vec![
+            MovedId {
+                id: 2,
+                from: 1,
+                to: 0
+            },
+            MovedId {
+                id: 1,
+                from: 0,
+                to: 1
+            },
+        ]
+    );
+    assert_eq!(delta.rank_change(2), 1);
+}
+
+#[tokio::test]
+async fn deltas_are_numbered_in_order() {
+    let (tx, status, feed) = feed(None);
+    let mut deltas = feed.subscribe();
+    status.send_replace(SubscriptionStatus::Live);
+
+    tx.send(list(&[1, 2])).await.unwrap();
+    tx.send(list(&[2, 1, 3])).await.unwrap();
+    // Unchanged lists are not sent and do not use up a number.
+    tx.send(list(&[2, 1, 3])).await.unwrap();
+    tx.send(list(&[3])).await.unwrap();
+
+    let first = next(&mut deltas).await;
+    assert_eq!(first.seq, 0);
+    assert_eq!(first.added.len(), 2);
+
+    let second = next(&mut deltas).await;
+    assert_eq!(second.seq, 1);
+    assert_eq!(second.added, vec![RankedId { id: 3, rank: 2 }]);
+    assert_eq!(second.moved.len(), 2);
+
+    let third = next(&mut deltas).await;
+    assert_eq!(third.seq, 2);
+    assert_eq!(third.removed.len(), 2);
+    assert_eq!(&*third.ids, &[3]);
+}
+
+#[tokio::test]
+async fn unchanged_list_is_sent_after_reconnect() {
+    let (tx, status, feed) = feed(None);
+    let mut deltas = feed.subscribe();
+    status.send_replace(SubscriptionStatus::Live);
+
+    tx.send(list(&[1, 2])).await.unwrap();
+    assert_eq!(next(&mut deltas).await.seq, 0);
+
+    reconnect(&status).await;
+    tx.send(list(&[1, 2])).await.unwrap();
+    let resync = next(&mut deltas).await;
+    assert_eq!(resync.seq, 1);
+    assert!(resync.is_empty());
+    assert_eq!(&*resync.ids, &[1, 2]);
+
+    // Back to skipping unchanged lists until the next reconnect.
+    tx.send(list(&[1, 2])).await.unwrap();
+    tx.send(list(&[2])).await.unwrap();
+    let changed = next(&mut deltas).await;
+    assert_eq!(changed.seq, 2);
+    assert_eq!(changed.removed, vec![RankedId { id: 1, rank: 0 }]);
+}
+
+#[tokio::test]
+async fn lists_are_limited() {
+    let (tx, status, feed) = feed(Some(2));
+    let mut deltas = feed.subscribe();
+    status.send_replace(SubscriptionStatus::Live);
+
+    tx.send(list(&[1, 2, 3])).await.unwrap();
+    assert_eq!(&*next(&mut deltas).await.ids, &[1, 2]);
+
+    // Changes past the limit are not seen.
+    tx.send(list(&[1, 2, 4])).await.unwrap();
+    tx.send(list(&[2, 1, 4])).await.unwrap();
+    let delta = next(&mut deltas).await;
+    assert_eq!(delta.seq, 1);
+    assert_eq!(&*delta.ids, &[2, 1]);
+}
@@ -0,0 +1,255 @@
+//! In process request metrics.
+//!
+//! The client counts requests, response bytes and latency per endpoint,
+//! failed attempts per error kind and open event streams. A
+//! [`MetricsSnapshot`] can be taken at any time for display.
+use crate::ApiError;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// Upper bounds of the latency histogram buckets in milliseconds. Slower
+/// requests are counted in a final overflow bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Group of API paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Endpoint {
+    Item,
+    User,
+    /// One of the article lists such as `topstories`.
+    List,
+    MaxItem,
+    Updates,
+    Other,
+}
+
+impl Endpoint {
+    /// Endpoint of a path relative to the base url such as `item/1.json`.
+    pub(crate) fn from_path(path: &str) -> Self {
+        let path = path.split('?').next().unwrap_or_default();
+        match path.split_once('/') {
+            Some(("item", _)) => Self::Item,
+            Some(("user", _)) => Self::User,
+            Some(_) => Self::Other,
+            None => match path.strip_suffix(".json") {
+                Some("maxitem") => Self::MaxItem,
+                Some("updates") => Self::Updates,
+                Some(list) if list.ends_with("stories") => Self::List,
+                _ => Self::Other,
+            },
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Endpoint::Item => "item",
+            Endpoint::User => "user",
+            Endpoint::List => "list",
+            Endpoint::MaxItem => "maxitem",
+            Endpoint::Updates => "updates",
+            Endpoint::Other => "other",
+        }
+    }
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Request latency counts per bucket of [`LATENCY_BUCKETS_MS`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    /// Number of requests in each bucket followed by the overflow bucket.
+    pub counts: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    /// Total latency of all requests.
+    pub sum: Duration,
+}
+
+impl Histogram {
+    fn record(&mut self, latency: Duration) {
+        let millis = latency.as_millis();
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= u128::from(*bound))
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.sum += latency;
+    }
+
+    fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts) {
+            *count += other;
+        }
+        self.sum += other.sum;
+    }
+
+    /// Number of recorded requests.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Mean latency.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count())
+            .ok()
+            .filter(|count| *count > 0)?;
+        Some(self.sum / count)
+    }
+
+    /// Upper bound of the bucket holding the `quantile` such as `0.95`.
+    /// `None` when there are no requests or the quantile is in the overflow
+    /// bucket.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((count as f64 * quantile).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (count, bound) in self.counts.iter().zip(LATENCY_BUCKETS_MS) {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_millis(bound));
+            }
+        }
+        None
+    }
+}
+
+/// Metrics of an endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointMetrics {
+    /// Completed requests, including those with an error status.
+    pub requests: u64,
+    /// Response body bytes.
+    pub bytes: u64,
+    pub latency: Histogram,
+}
+
+/// Metrics of a client at a point in time.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub endpoints: BTreeMap<Endpoint, EndpointMetrics>,
+    /// Failed request attempts by [`ApiError::kind`], including those that
+    /// were retried.
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Event streams opened since the client was created.
+    pub streams_opened: u64,
+    /// Event streams currently open.
+    pub open_streams: u64,
+}
+
+impl MetricsSnapshot {
+    /// Completed requests over all endpoints.
+    pub fn requests(&self) -> u64 {
+        self.endpoints
+            .values()
+            .map(|metrics| metrics.requests)
+            .sum()
+    }
+
+    /// Response bytes over all endpoints.
+    pub fn bytes(&self) -> u64 {
+        self.endpoints.values().map(|metrics| metrics.bytes).sum()
+    }
+
+    /// Failed request attempts over all kinds.
+    pub fn error_count(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    /// Latency over all endpoints.
+    pub fn latency(&self) -> Histogram {
+        self.endpoints
+            .values()
+            .fold(Histogram::default(), |mut latency, metrics| {
+                latency.merge(&metrics.latency);
+                latency
+            })
+    }
+}
+
+/// A one line summary for status bars.
+impl Display for MetricsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let latency = self.latency();
+        let millis = |quantile| match latency.quantile(quantile) {
+            Some(bound) => format!("{}ms", bound.as_millis()),
+            None if latency.count() == 0 => "-".to_owned(),
+            None => format!(">{}ms", LATENCY_BUCKETS_MS[LATENCY_BUCKETS_MS.len() - 1]),
+        };
+
+        write!(
+            f,
+            "{} requests, {:.1} MB, p50 {} p95 {}, {} errors, {} streams",
+            self.requests(),
+            self.bytes() as f64 / 1_000_000.,
+            millis(0.5),
+            millis(0.95),
+            self.error_count(),
+            self.open_streams
+        )
+    }
+}
+
+/// Metrics collected by a client.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    endpoints: Mutex<BTreeMap<Endpoint, EndpointMetrics>>,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+    streams_opened: AtomicU64,
+    open_streams: AtomicU64,
+}
+
+impl Metrics {
+    /// Count a completed request.
+    pub fn request(&self, path: &str, latency: Duration, bytes: usize) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let metrics = endpoints.entry(Endpoint::from_path(path)).or_default();
+        metrics.requests += 1;
+        metrics.bytes += bytes as u64;
+        metrics.latency.record(latency);
+    }
+
+    /// Count a failed request attempt.
+    pub fn error(&self, err: &ApiError) {
+        *self.errors.lock().unwrap().entry(err.kind()).or_default() += 1;
+    }
+
+    /// Count an opened event stream until the returned guard is dropped.
+    pub fn stream_opened(self: &Arc<Self>) -> StreamGuard {
+        self.streams_opened.fetch_add(1, Ordering::Relaxed);
+        self.open_streams.fetch_add(1, Ordering::Relaxed);
+        StreamGuard(self.clone())
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            endpoints: self.endpoints.lock().unwrap().clone(),
+            errors: self.errors.lock().unwrap().clone(),
+            streams_opened: self.streams_opened.load(Ordering::Relaxed),
+            open_streams: self.open_streams.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts an event stream as open while it is alive.
+pub(crate) struct StreamGuard(Arc<Metrics>);
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.0.open_streams.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod metrics_tests;
@@ -0,0 +1,68 @@
+use super::{Endpoint, Histogram, Metrics};
+use crate::{ApiClient, ApiError, ReplayTiming, Transport};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+#[test]
+fn endpoints_from_paths() {
+    assert_eq!(Endpoint::from_path("item/1.json"), Endpoint::Item);
+    assert_eq!(
+        Endpoint::from_path("user/pg.json?print=pretty"),
+        Endpoint::User
+    );
+    assert_eq!(Endpoint::from_path("topstories.json"), Endpoint::List);
+    assert_eq!(Endpoint::from_path("maxitem.json"), Endpoint::MaxItem);
+    assert_eq!(Endpoint::from_path("updates.json"), Endpoint::Updates);
+    assert_eq!(Endpoint::from_path("v1/other.json"), Endpoint::Other);
+}
+
+#[test]
+fn histogram_quantiles() {
+    let mut histogram = Histogram::default();
+    assert_eq!(histogram.quantile(0.5), None);
+
+    for millis in [1, 2, 3, 40, 60_000] {
+        histogram.record(Duration::from_millis(millis));
+    }
+    assert_eq!(histogram.count(), 5);
+    assert_eq!(histogram.quantile(0.5), Some(Duration::from_millis(5)));
+    assert_eq!(histogram.quantile(0.8), Some(Duration::from_millis(50)));
+    assert_eq!(histogram.quantile(1.0), None);
+    assert_eq!(histogram.mean(), Some(Duration::from_micros(12_009_200)));
+}
+
+#[test]
+fn streams_and_errors() {
+    let metrics = Arc::new(Metrics::default());
+    let first = metrics.stream_opened();
+    let second = metrics.stream_opened();
+    drop(first);
+    metrics.error(&ApiError::ChannelClosed);
+    metrics.request("item/1.json", Duration::from_millis(20), 100);
+
+    let snapshot = metrics.snapshot();
+    assert_eq!((snapshot.streams_opened, snapshot.open_streams), (2, 1));
+    assert_eq!(snapshot.errors.get("channel_closed"), Some(&1));
+    assert_eq!(snapshot.bytes(), 100);
+
+    drop(second);
+    assert_eq!(metrics.snapshot().open_streams, 0);
+}
+
+#[tokio::test]
+async fn client_counts_failed_attempts() {
+    let client = ApiClient::builder()
+        .transport(Transport::Replay {
+            dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/client/comment_tree/fixtures"),
+            timing: ReplayTiming::Immediate,
+        })
+        .build()
+        .unwrap();
+
+    assert!(client.item(4).await.is_err());
+    assert!(client.item(100).await.is_err());
+
+    let metrics = client.metrics();
+    assert_eq!(metrics.errors.get("not_found"), Some(&1));
+    assert_eq!(metrics.errors.get("not_recorded"), Some(&1));
+    assert_eq!(metrics.error_count(), 2);
+}
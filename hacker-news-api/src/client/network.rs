@@ -0,0 +1,113 @@
+//! Proxy, certificate and user agent settings shared by the API clients.
+use crate::{ApiError, ApiResult};
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use std::{convert::Infallible, path::PathBuf, str::FromStr};
+
+/// Environment variables read by [`ProxySettings::Environment`] in order of
+/// precedence.
+const PROXY_ENV: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// How the client connects through a proxy.
+#[derive(Debug, Clone, Default)]
+pub enum ProxySettings {
+    /// Use the proxy from the `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY`
+    /// environment variables honouring `NO_PROXY`.
+    #[default]
+    Environment,
+    /// Connect directly and ignore the environment.
+    Direct,
+    /// Send every request through the proxy at `url`. Supports `http`,
+    /// `https` and `socks5` proxies and credentials in the url.
+    Url {
+        url: String,
+        /// Comma separated hosts, domains and ip ranges to connect to
+        /// directly, in the format of `NO_PROXY`.
+        no_proxy: Option<String>,
+    },
+}
+
+impl ProxySettings {
+    /// Whether requests may go through a proxy.
+    pub(crate) fn is_proxied(&self) -> bool {
+        match self {
+            Self::Environment => PROXY_ENV
+                .iter()
+                .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty())),
+            Self::Direct => false,
+            Self::Url { .. } => true,
+        }
+    }
+}
+
+/// Parses `direct` or `none` as [`ProxySettings::Direct`], `env` as
+/// [`ProxySettings::Environment`] and anything else as a proxy url.
+impl FromStr for ProxySettings {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "direct" | "none" => Self::Direct,
+            "env" => Self::Environment,
+            url => Self::Url {
+                url: url.to_owned(),
+                no_proxy: None,
+            },
+        })
+    }
+}
+
+/// Network policy for the firebase and algolia clients.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkSettings {
+    /// Proxy to connect through.
+    pub proxy: ProxySettings,
+    /// PEM files with root certificates to trust in addition to the
+    /// built in roots, such as the certificate of a TLS intercepting gateway.
+    pub ca_certificates: Vec<PathBuf>,
+    /// User agent sent with every request.
+    pub user_agent: Option<String>,
+}
+
+impl NetworkSettings {
+    /// Apply the settings to a reqwest client builder.
+    pub(crate) fn apply(&self, mut builder: ClientBuilder) -> ApiResult<ClientBuilder> {
+        builder = match &self.proxy {
+            // reqwest reads the environment by default.
+            ProxySettings::Environment => builder,
+            ProxySettings::Direct => builder.no_proxy(),
+            ProxySettings::Url { url, no_proxy } => {
+                let proxy = Proxy::all(url)
+                    .map_err(|err| ApiError::Config(format!("Invalid proxy {url}: {err}")))?
+                    .no_proxy(no_proxy.as_deref().and_then(NoProxy::from_string));
+                builder.proxy(proxy)
+            }
+        };
+
+        for path in &self.ca_certificates {
+            let pem = std::fs::read(path).map_err(|err| {
+                ApiError::Config(format!("Failed to read certificate {path:?}: {err}"))
+            })?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .map_err(|err| ApiError::Config(format!("Invalid certificate {path:?}: {err}")))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod network_tests;
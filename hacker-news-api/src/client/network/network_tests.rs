@@ -0,0 +1,93 @@
+use super::{NetworkSettings, ProxySettings};
+use crate::{ApiClient, ApiError};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::mpsc,
+};
+
+/// Act as an http proxy answering every request with the same item and
+/// forward the request heads.
+async fn proxy_server() -> (String, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let _ = tx.send(String::from_utf8_lossy(&request).into_owned());
+
+            let body = r#"{"id":1,"type":"story","by":"a","time":1}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (format!("http://{address}"), rx)
+}
+
+#[tokio::test]
+async fn requests_go_through_proxy() {
+    let (proxy, mut requests) = proxy_server().await;
+    // The host doesn't resolve so the request only succeeds through the
+    // proxy.
+    let client = ApiClient::builder()
+        .base_url("http://hacker-news.invalid/v0")
+        .network(NetworkSettings {
+            proxy: ProxySettings::Url {
+                url: proxy,
+                no_proxy: None,
+            },
+            user_agent: Some("hn-test/1.0".to_owned()),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+
+    let item = client.item(1).await.unwrap();
+    assert_eq!(item.id, 1);
+
+    let request = requests.recv().await.unwrap();
+    assert!(
+        request.starts_with("GET http://hacker-news.invalid/v0/item/1.json "),
+        "{request}"
+    );
+    assert!(
+        request.to_lowercase().contains("user-agent: hn-test/1.0"),
+        "{request}"
+    );
+}
+
+#[test]
+fn invalid_settings_are_config_errors() {
+    let result = ApiClient::builder()
+        .network(NetworkSettings {
+            proxy: ProxySettings::Url {
+                url: "not a url".to_owned(),
+                no_proxy: None,
+            },
+            ..Default::default()
+        })
+        .build();
+    assert!(matches!(result, Err(ApiError::Config(_))));
+
+    let result = ApiClient::builder()
+        .network(NetworkSettings {
+            proxy: ProxySettings::Direct,
+            ca_certificates: vec!["does-not-exist.pem".into()],
+            ..Default::default()
+        })
+        .build();
+    assert!(matches!(result, Err(ApiError::Config(_))));
+}
@@ -0,0 +1,59 @@
+//! User profiles and their submissions.
+//!
+//! A profile lists the ids of everything the user submitted, newest first,
+//! which can run into the tens of thousands. Submissions are fetched a page
+//! at a time with the client's concurrency cap.
+use super::ApiClient;
+use crate::{ApiResult, Item, User};
+use futures::TryStreamExt as _;
+
+/// A page of a user's stories, comments and polls.
+#[derive(Debug, Clone)]
+pub struct SubmissionsPage {
+    /// Fetched submissions newest first. Deleted, dead and missing items are
+    /// left out so a page can hold fewer items than were requested.
+    pub items: Vec<Item>,
+    /// Position of the page in the user's submissions.
+    pub offset: usize,
+    /// Number of ids the page covers.
+    pub len: usize,
+    /// Number of submissions of the user.
+    pub total: usize,
+}
+
+impl SubmissionsPage {
+    /// Offset of the next page if there is one.
+    pub fn next_offset(&self) -> Option<usize> {
+        let next = self.offset + self.len;
+        (next < self.total).then_some(next)
+    }
+
+    /// Offset of the previous page of `page_size` if there is one.
+    pub fn previous_offset(&self, page_size: usize) -> Option<usize> {
+        (self.offset > 0).then(|| self.offset.saturating_sub(page_size))
+    }
+}
+
+impl ApiClient {
+    /// Fetch `page_size` of the user's submissions starting at `offset`.
+    pub async fn user_submissions(
+        &self,
+        user: &User,
+        offset: usize,
+        page_size: usize,
+    ) -> ApiResult<SubmissionsPage> {
+        let offset = offset.min(user.submitted.len());
+        let ids = &user.submitted[offset..user.submitted.len().min(offset + page_size)];
+        let items = self.items(ids).try_collect().await?;
+
+        Ok(SubmissionsPage {
+            items,
+            offset,
+            len: ids.len(),
+            total: user.submitted.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod profile_tests;
@@ -0,0 +1,44 @@
+use crate::{ApiClient, ReplayTiming, Transport, User};
+use std::path::PathBuf;
+
+fn user() -> User {
+    User {
+        id: "b".to_owned(),
+        about: Some("<p>About".to_owned()),
+        created: 1_000,
+        karma: 10,
+        submitted: vec![8, 4, 3, 2, 5],
+    }
+}
+
+#[tokio::test]
+async fn pages_skip_deleted_and_missing() {
+    let client = ApiClient::builder()
+        .transport(Transport::Replay {
+            dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/client/comment_tree/fixtures"),
+            timing: ReplayTiming::Immediate,
+        })
+        .build()
+        .unwrap();
+    let user = user();
+
+    let first = client.user_submissions(&user, 0, 3).await.unwrap();
+    assert_eq!(
+        first.items.iter().map(|item| item.id).collect::<Vec<_>>(),
+        [8]
+    );
+    assert_eq!(first.next_offset(), Some(3));
+    assert_eq!(first.previous_offset(3), None);
+
+    let last = client.user_submissions(&user, 3, 3).await.unwrap();
+    assert_eq!(
+        last.items.iter().map(|item| item.id).collect::<Vec<_>>(),
+        [2, 5]
+    );
+    assert_eq!((last.len, last.total), (2, 5));
+    assert_eq!(last.next_offset(), None);
+    assert_eq!(last.previous_offset(3), Some(0));
+
+    let beyond = client.user_submissions(&user, 10, 3).await.unwrap();
+    assert!(beyond.items.is_empty());
+}
@@ -0,0 +1,298 @@
+//! Record and replay API traffic.
+//!
+//! Recording appends every REST response and every server side event chunk
+//! to [`RECORDING_FILE`] in a fixture directory. Replay serves them back in
+//! the recorded order without a network, with the original or compressed
+//! timing. Paths are stored relative to the base url so a recording can be
+//! replayed against any base url.
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use bytes::Bytes;
+use futures::{future, stream, Stream, StreamExt as _};
+use log::warn;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// File in the fixture directory holding the recording.
+pub const RECORDING_FILE: &str = "recording.jsonl";
+
+/// Where the client gets its responses from.
+#[derive(Debug, Clone, Default)]
+pub enum Transport {
+    /// Make requests to the API.
+    #[default]
+    Live,
+    /// Make requests to the API and record them to the directory.
+    Record(PathBuf),
+    /// Serve responses recorded to the directory.
+    Replay { dir: PathBuf, timing: ReplayTiming },
+}
+
+/// Pace of replayed responses and events.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ReplayTiming {
+    /// Wait as long as the recorded response or event took.
+    #[default]
+    Original,
+    /// Divide recorded delays by the factor.
+    Faster(f64),
+    /// Serve everything without delay.
+    Immediate,
+}
+
+impl ReplayTiming {
+    fn scale(&self, delay: Duration) -> Duration {
+        match self {
+            Self::Original => delay,
+            Self::Faster(factor) if *factor > 0. => delay.div_f64(*factor),
+            Self::Faster(_) | Self::Immediate => Duration::ZERO,
+        }
+    }
+}
+
+/// A line of the recording.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry {
+    Response {
+        path: String,
+        status: u16,
+        elapsed_ms: u64,
+        body: Payload,
+    },
+    Event {
+        path: String,
+        /// Event stream connection in the order they were opened.
+        stream: u64,
+        /// Time since the connection was opened.
+        offset_ms: u64,
+        chunk: Payload,
+    },
+}
+
+/// Recorded bytes. Kept as text when they are valid UTF-8 so recordings stay
+/// readable, otherwise base64 encoded, such as a chunk that splits a
+/// multi-byte character.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Payload {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl Payload {
+    fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_owned()),
+            Err(_) => Self::Binary {
+                base64: STANDARD.encode(bytes),
+            },
+        }
+    }
+
+    fn into_bytes(self) -> io::Result<Bytes> {
+        match self {
+            Self::Text(text) => Ok(Bytes::from(text)),
+            Self::Binary { base64 } => STANDARD
+                .decode(base64)
+                .map(Bytes::from)
+                .map_err(io::Error::other),
+        }
+    }
+}
+
+/// Record or replay state of a client.
+pub(crate) enum Tape {
+    Record(Arc<Recorder>),
+    Replay(Replayer),
+}
+
+impl Tape {
+    /// Open the tape for the transport. `None` for live requests.
+    pub fn open(transport: &Transport) -> io::Result<Option<Self>> {
+        Ok(match transport {
+            Transport::Live => None,
+            Transport::Record(dir) => Some(Self::Record(Arc::new(Recorder::open(dir)?))),
+            Transport::Replay { dir, timing } => Some(Self::Replay(Replayer::load(dir, *timing)?)),
+        })
+    }
+}
+
+/// Appends responses and events to a recording.
+pub(crate) struct Recorder {
+    file: Mutex<File>,
+    streams: AtomicU64,
+}
+
+impl Recorder {
+    fn open(dir: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(RECORDING_FILE))?;
+        Ok(Self {
+            file: Mutex::new(file),
+            streams: AtomicU64::new(0),
+        })
+    }
+
+    /// Record a response body.
+    pub fn response(&self, path: &str, status: StatusCode, elapsed: Duration, body: &[u8]) {
+        self.write(&Entry::Response {
+            path: path.to_owned(),
+            status: status.as_u16(),
+            elapsed_ms: elapsed.as_millis() as u64,
+            body: Payload::new(body),
+        });
+    }
+
+    /// Number for a new event stream connection.
+    pub fn next_stream(&self) -> u64 {
+        self.streams.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Record a chunk of an event stream.
+    pub fn event(&self, path: &str, stream: u64, offset: Duration, chunk: &[u8]) {
+        self.write(&Entry::Event {
+            path: path.to_owned(),
+            stream,
+            offset_ms: offset.as_millis() as u64,
+            chunk: Payload::new(chunk),
+        });
+    }
+
+    /// Failing to record is logged and does not fail the request.
+    fn write(&self, entry: &Entry) {
+        let result = serde_json::to_vec(entry)
+            .map_err(io::Error::other)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                self.file.lock().unwrap().write_all(&line)
+            });
+        if let Err(err) = result {
+            warn!("Failed to record response: {err}");
+        }
+    }
+}
+
+/// A recorded response.
+#[derive(Clone)]
+pub(crate) struct RecordedResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+    elapsed: Duration,
+}
+
+/// Chunks of a recorded event stream connection with their offset.
+type Connection = Vec<(Duration, Bytes)>;
+
+/// Serves a recording.
+pub(crate) struct Replayer {
+    timing: ReplayTiming,
+    responses: Mutex<HashMap<String, VecDeque<RecordedResponse>>>,
+    streams: Mutex<HashMap<String, VecDeque<Connection>>>,
+}
+
+impl Replayer {
+    fn load(dir: &Path, timing: ReplayTiming) -> io::Result<Self> {
+        let file = File::open(dir.join(RECORDING_FILE))?;
+        let mut responses = HashMap::<_, VecDeque<_>>::new();
+        let mut connections = BTreeMap::<u64, (String, Connection)>::new();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line).map_err(io::Error::other)? {
+                Entry::Response {
+                    path,
+                    status,
+                    elapsed_ms,
+                    body,
+                } => responses
+                    .entry(path)
+                    .or_default()
+                    .push_back(RecordedResponse {
+                        status: StatusCode::from_u16(status).map_err(io::Error::other)?,
+                        body: body.into_bytes()?,
+                        elapsed: Duration::from_millis(elapsed_ms),
+                    }),
+                Entry::Event {
+                    path,
+                    stream,
+                    offset_ms,
+                    chunk,
+                } => connections
+                    .entry(stream)
+                    .or_insert_with(|| (path, Vec::new()))
+                    .1
+                    .push((Duration::from_millis(offset_ms), chunk.into_bytes()?)),
+            }
+        }
+
+        let mut streams = HashMap::<_, VecDeque<_>>::new();
+        for (path, chunks) in connections.into_values() {
+            streams.entry(path).or_default().push_back(chunks);
+        }
+
+        Ok(Self {
+            timing,
+            responses: Mutex::new(responses),
+            streams: Mutex::new(streams),
+        })
+    }
+
+    /// The next recorded response for the path. Responses are served in the
+    /// recorded order and the last one is repeated.
+    pub async fn response(&self, path: &str) -> Option<RecordedResponse> {
+        let response = {
+            let mut responses = self.responses.lock().unwrap();
+            let recorded = responses.get_mut(path)?;
+            if recorded.len() > 1 {
+                recorded.pop_front()
+            } else {
+                recorded.front().cloned()
+            }
+        }?;
+        tokio::time::sleep(self.timing.scale(response.elapsed)).await;
+        Some(response)
+    }
+
+    /// Chunks of the next recorded connection to the path. The stream stays
+    /// open once the chunks run out like an idle connection would.
+    pub fn stream(&self, path: &str) -> impl Stream<Item = Bytes> + Send + 'static {
+        let chunks = self
+            .streams
+            .lock()
+            .unwrap()
+            .get_mut(path)
+            .and_then(VecDeque::pop_front)
+            .unwrap_or_default();
+        let timing = self.timing;
+
+        stream::unfold(
+            (chunks.into_iter(), Duration::ZERO),
+            move |(mut chunks, last_offset)| async move {
+                let (offset, chunk) = chunks.next()?;
+                tokio::time::sleep(timing.scale(offset.saturating_sub(last_offset))).await;
+                Some((chunk, (chunks, offset)))
+            },
+        )
+        .chain(stream::once(future::pending()))
+    }
+}
+
+#[cfg(test)]
+mod recording_tests;
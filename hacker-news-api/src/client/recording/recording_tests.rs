@@ -0,0 +1,200 @@
+use super::{Recorder, ReplayTiming, Replayer, Transport, RECORDING_FILE};
+use crate::{ApiClient, ApiError, ArticleType, DnsResolution, RetryPolicy};
+use futures::StreamExt;
+use reqwest::StatusCode;
+use std::{path::PathBuf, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::mpsc,
+};
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/client/recording/fixtures")
+}
+
+fn replay_client(dir: PathBuf) -> ApiClient {
+    ApiClient::builder()
+        .transport(Transport::Replay {
+            dir,
+            timing: ReplayTiming::Immediate,
+        })
+        .build()
+        .unwrap()
+}
+
+/// Serve an item and a single event on a local port and return the base url.
+async fn live_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+            let response = match path {
+                "/item/7.json" => {
+                    let body = r#"{"id":7,"type":"comment","by":"dang","text":"Hi","time":1}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                }
+                "/newstories.json" => "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\nevent: put\ndata: {\"path\":\"/\",\"data\":[9,8]}\n\n".to_owned(),
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_owned(),
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    format!("http://{address}")
+}
+
+#[tokio::test]
+async fn replay_responses_in_order() {
+    let client = replay_client(fixtures());
+
+    let scores = [
+        client.item(1).await.unwrap().score,
+        client.item(1).await.unwrap().score,
+        // The last response is repeated.
+        client.item(1).await.unwrap().score,
+    ];
+    assert_eq!(scores, [1, 2, 2]);
+
+    assert!(matches!(
+        client.item(2).await,
+        Err(ApiError::NotFound { id: Some(2), .. })
+    ));
+    assert!(matches!(
+        client.item(3).await,
+        Err(ApiError::NotRecorded { .. })
+    ));
+}
+
+#[tokio::test]
+async fn replay_event_stream() {
+    let client = replay_client(fixtures());
+    let (sender, mut receiver) = mpsc::channel(10);
+
+    let stream = tokio::spawn(async move {
+        client
+            .articles_list_stream(ArticleType::Top, sender)
+            .await
+            .map(|_| ())
+    });
+
+    assert_eq!(receiver.recv().await.unwrap().data, [1, 2, 3]);
+    assert_eq!(receiver.recv().await.unwrap().data, [1, 4, 3]);
+    // A replayed stream stays open like an idle connection.
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), receiver.recv())
+            .await
+            .is_err()
+    );
+    stream.abort();
+}
+
+#[tokio::test]
+async fn record_then_replay() {
+    let dir = std::env::temp_dir().join(format!("hn-recording-{}", std::process::id()));
+    let _ = std::fs::remove_file(dir.join(RECORDING_FILE));
+
+    let recording = ApiClient::builder()
+        .base_url(live_server().await)
+        .dns_resolution(DnsResolution::System)
+        .retry(RetryPolicy::none())
+        .transport(Transport::Record(dir.clone()))
+        .build()
+        .unwrap();
+    assert_eq!(recording.item(7).await.unwrap().by, "dang");
+    let (sender, mut receiver) = mpsc::channel(10);
+    recording
+        .articles_list_stream(ArticleType::New, sender)
+        .await
+        .unwrap();
+    assert_eq!(receiver.recv().await.unwrap().data, [9, 8]);
+
+    let replay = replay_client(dir.clone());
+    assert_eq!(replay.item(7).await.unwrap().by, "dang");
+    let (sender, mut receiver) = mpsc::channel(10);
+    let stream = tokio::spawn(async move {
+        replay
+            .articles_list_stream(ArticleType::New, sender)
+            .await
+            .map(|_| ())
+    });
+    assert_eq!(receiver.recv().await.unwrap().data, [9, 8]);
+    stream.abort();
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn raw_bytes_are_kept() {
+    let dir = std::env::temp_dir().join(format!("hn-recording-bytes-{}", std::process::id()));
+    let _ = std::fs::remove_file(dir.join(RECORDING_FILE));
+
+    // The two bytes of `é` split across chunks.
+    let chunks: [&[u8]; 2] = [b"event: put\ndata: \"caf\xc3", b"\xa9\"\n\n"];
+    let body = b"\xff\xfe not utf-8";
+    let recorder = Recorder::open(&dir).unwrap();
+    recorder.response("item/1.json", StatusCode::OK, Duration::ZERO, body);
+    let stream = recorder.next_stream();
+    for chunk in chunks {
+        recorder.event("topstories.json", stream, Duration::ZERO, chunk);
+    }
+    recorder.response("item/2.json", StatusCode::OK, Duration::ZERO, b"null");
+
+    let recording = std::fs::read_to_string(dir.join(RECORDING_FILE)).unwrap();
+    assert!(recording.contains(r#""body":"null""#));
+
+    let replayer = Replayer::load(&dir, ReplayTiming::Immediate).unwrap();
+    assert_eq!(
+        &replayer.response("item/1.json").await.unwrap().body[..],
+        body
+    );
+    let replayed = replayer
+        .stream("topstories.json")
+        .take(2)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(replayed, chunks);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn replay_timing() {
+    let delay = Duration::from_secs(1);
+
+    assert_eq!(ReplayTiming::Original.scale(delay), delay);
+    assert_eq!(
+        ReplayTiming::Faster(10.).scale(delay),
+        Duration::from_millis(100)
+    );
+    assert_eq!(ReplayTiming::Immediate.scale(delay), Duration::ZERO);
+}
+
+#[test]
+fn replay_speed_must_be_positive() {
+    for speed in [0., -1., f64::NAN] {
+        let result = ApiClient::builder()
+            .transport(Transport::Replay {
+                dir: fixtures(),
+                timing: ReplayTiming::Faster(speed),
+            })
+            .build();
+        assert!(matches!(result, Err(ApiError::Config(_))), "{speed}");
+    }
+}
@@ -0,0 +1,222 @@
+//! Server side event (EventSource) decoding.
+//!
+//! The firebase event stream sends `put`, `patch`, `keep-alive`, `cancel`
+//! and `auth_revoked` events. Events can be split across network chunks or
+//! several events can arrive in a single chunk so the decoder buffers
+//! partial lines until an event is complete.
+use anyhow::{Context as _, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[cfg(test)]
+mod sse_tests;
+
+/// A complete event as sent on the wire.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RawEvent {
+    /// The event type. Defaults to `message` when not provided.
+    pub event: String,
+    /// The event data. Multiple `data` lines are joined with a newline.
+    pub data: String,
+}
+
+/// Incremental decoder that assembles [`RawEvent`]s from arbitrary chunks.
+#[derive(Debug, Default)]
+pub struct EventDecoder {
+    /// Bytes of an incomplete line.
+    buffer: Vec<u8>,
+    /// The last chunk ended with a carriage return so a following line feed
+    /// belongs to the same line ending.
+    pending_cr: bool,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl EventDecoder {
+    /// Feed a chunk of bytes and return all events completed by it.
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<RawEvent> {
+        let mut events = Vec::new();
+        let mut rest = chunk;
+
+        if self.pending_cr {
+            self.pending_cr = false;
+            if let Some(stripped) = rest.strip_prefix(b"\n") {
+                rest = stripped;
+            }
+        }
+
+        while let Some(pos) = rest.iter().position(|b| *b == b'\n' || *b == b'\r') {
+            self.buffer.extend_from_slice(&rest[..pos]);
+            let line = std::mem::take(&mut self.buffer);
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+
+            if rest[pos] == b'\r' {
+                match rest.get(pos + 1) {
+                    Some(b'\n') => rest = &rest[pos + 2..],
+                    Some(_) => rest = &rest[pos + 1..],
+                    None => {
+                        self.pending_cr = true;
+                        rest = &[];
+                    }
+                }
+            } else {
+                rest = &rest[pos + 1..];
+            }
+        }
+        self.buffer.extend_from_slice(rest);
+
+        events
+    }
+
+    /// Process a single line without the line terminator.
+    fn process_line(&mut self, line: &[u8]) -> Option<RawEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // Comment line.
+        if line.starts_with(b":") {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_owned()),
+            "data" => match self.data.as_mut() {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_owned()),
+            },
+            // id and retry are not used by firebase.
+            _ => (),
+        }
+        None
+    }
+
+    /// Dispatch the buffered event when a blank line is seen.
+    fn dispatch(&mut self) -> Option<RawEvent> {
+        let event = self.event.take();
+        let data = self.data.take()?;
+        Some(RawEvent {
+            event: event.unwrap_or_else(|| "message".to_owned()),
+            data,
+        })
+    }
+}
+
+/// Payload of a `patch` event. Each key of `data` replaces the child of the
+/// same name at `path`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatchData {
+    pub path: String,
+    pub data: Map<String, Value>,
+}
+
+impl PatchData {
+    /// Apply the patch to a value previously received from a `put` event.
+    pub fn apply<T>(&self, current: &T) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut value = serde_json::to_value(current)?;
+
+        let mut target = &mut value;
+        for segment in self.path.split('/').filter(|s| !s.is_empty()) {
+            target = child_mut(target, segment)
+                .with_context(|| format!("Invalid patch path {}", self.path))?;
+        }
+
+        for (key, patch_value) in &self.data {
+            match target {
+                Value::Object(map) => {
+                    map.insert(key.clone(), patch_value.clone());
+                }
+                _ => {
+                    *child_mut(target, key)
+                        .with_context(|| format!("Invalid patch key {key}"))? = patch_value.clone();
+                }
+            }
+        }
+
+        serde_json::from_value(value).context("Patched value is invalid")
+    }
+}
+
+/// Get a mutable reference to a child of an object or array. Arrays are
+/// extended with nulls when the index is past the end.
+fn child_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Object(map) => Some(map.entry(key).or_insert(Value::Null)),
+        Value::Array(items) => {
+            let index = key.parse::<usize>().ok()?;
+            if index >= items.len() {
+                items.resize(index + 1, Value::Null);
+            }
+            items.get_mut(index)
+        }
+        _ => None,
+    }
+}
+
+/// A typed firebase event.
+#[derive(Debug)]
+pub enum StreamEvent<EventData> {
+    /// A full replacement of the data at a path.
+    Put(EventData),
+    /// A partial update of the data at a path.
+    Patch(PatchData),
+    /// Sent periodically to keep the connection open.
+    KeepAlive,
+    /// The server has cancelled the subscription.
+    Cancel,
+    /// The credentials used for the subscription have expired.
+    AuthRevoked,
+}
+
+impl<EventData> StreamEvent<EventData>
+where
+    EventData: DeserializeOwned,
+{
+    /// Convert a raw event into a typed event. Unknown event types are
+    /// returned as `None`.
+    pub fn from_raw(raw: &RawEvent) -> Result<Option<Self>> {
+        let event = match raw.event.as_str() {
+            "put" => StreamEvent::Put(
+                serde_json::from_str(&raw.data)
+                    .with_context(|| format!("Failed to deserialize put payload: {}", raw.data))?,
+            ),
+            "patch" => {
+                StreamEvent::Patch(serde_json::from_str(&raw.data).with_context(|| {
+                    format!("Failed to deserialize patch payload: {}", raw.data)
+                })?)
+            }
+            "keep-alive" => StreamEvent::KeepAlive,
+            "cancel" => StreamEvent::Cancel,
+            "auth_revoked" => StreamEvent::AuthRevoked,
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
+
+/// How an event stream subscription ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEnd {
+    /// The server closed the connection.
+    Closed,
+    /// The server sent a `cancel` event.
+    Cancelled,
+    /// The server sent an `auth_revoked` event.
+    AuthRevoked,
+    /// No event or keep-alive was received in time.
+    Stale,
+}
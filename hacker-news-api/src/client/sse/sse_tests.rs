@@ -0,0 +1,95 @@
+use super::{EventDecoder, PatchData, RawEvent, StreamEvent};
+use crate::StoriesEventData;
+
+fn raw(event: &str, data: &str) -> RawEvent {
+    RawEvent {
+        event: event.to_owned(),
+        data: data.to_owned(),
+    }
+}
+
+#[test]
+fn single_event() {
+    let mut decoder = EventDecoder::default();
+    let events = decoder.decode(b"event: put\ndata: {\"path\":\"/\",\"data\":[1,2]}\n\n");
+
+    assert_eq!(events, vec![raw("put", r#"{"path":"/","data":[1,2]}"#)]);
+}
+
+#[test]
+fn event_split_across_chunks() {
+    let mut decoder = EventDecoder::default();
+
+    assert!(decoder.decode(b"event: p").is_empty());
+    assert!(decoder.decode(b"ut\ndata: {\"path\":\"/\",").is_empty());
+    assert!(decoder.decode(b"\"data\":[1]}\n").is_empty());
+    let events = decoder.decode(b"\n");
+
+    assert_eq!(events, vec![raw("put", r#"{"path":"/","data":[1]}"#)]);
+}
+
+#[test]
+fn multiple_events_in_chunk() {
+    let mut decoder = EventDecoder::default();
+    let events = decoder.decode(
+        b"event: keep-alive\ndata: null\n\nevent: put\ndata: 1\n\nevent: cancel\ndata: null\n\n",
+    );
+
+    assert_eq!(
+        events,
+        vec![
+            raw("keep-alive", "null"),
+            raw("put", "1"),
+            raw("cancel", "null")
+        ]
+    );
+}
+
+#[test]
+fn crlf_split_between_chunks() {
+    let mut decoder = EventDecoder::default();
+
+    assert!(decoder.decode(b"event: put\r").is_empty());
+    assert_eq!(decoder.decode(b"\ndata: 1\r\n\r"), vec![raw("put", "1")]);
+    // The line feed completes the previous line ending and is not a blank line.
+    assert!(decoder.decode(b"\n").is_empty());
+    assert_eq!(decoder.decode(b"data: 2\n\n"), vec![raw("message", "2")]);
+}
+
+#[test]
+fn comments_and_multi_line_data() {
+    let mut decoder = EventDecoder::default();
+    let events = decoder.decode(b": comment\ndata: a\ndata: b\n\n");
+
+    assert_eq!(events, vec![raw("message", "a\nb")]);
+}
+
+#[test]
+fn typed_events() {
+    let put = StreamEvent::<StoriesEventData>::from_raw(&raw("put", r#"{"path":"/","data":[3]}"#))
+        .unwrap();
+    assert!(matches!(put, Some(StreamEvent::Put(StoriesEventData { data, .. })) if data == [3]));
+
+    let keep_alive = StreamEvent::<StoriesEventData>::from_raw(&raw("keep-alive", "null")).unwrap();
+    assert!(matches!(keep_alive, Some(StreamEvent::KeepAlive)));
+
+    let revoked = StreamEvent::<StoriesEventData>::from_raw(&raw(
+        "auth_revoked",
+        "credential is no longer valid",
+    ))
+    .unwrap();
+    assert!(matches!(revoked, Some(StreamEvent::AuthRevoked)));
+
+    let unknown = StreamEvent::<StoriesEventData>::from_raw(&raw("other", "")).unwrap();
+    assert!(unknown.is_none());
+
+    assert!(StreamEvent::<StoriesEventData>::from_raw(&raw("put", "{")).is_err());
+}
+
+#[test]
+fn apply_patch() {
+    let patch: PatchData = serde_json::from_str(r#"{"path":"/","data":{"1":42,"3":7}}"#).unwrap();
+    let patched = patch.apply(&vec![1_u64, 2, 3]).unwrap();
+
+    assert_eq!(patched, vec![1, 42, 3, 7]);
+}
@@ -0,0 +1,262 @@
+//! Supervised event stream subscriptions.
+//!
+//! A supervisor keeps an event stream subscription alive by reconnecting
+//! with an exponential backoff when the stream is severed or goes stale.
+//! Connection state changes are published on a watch channel.
+use super::StreamEnd;
+use crate::ApiResult;
+use futures::Stream;
+use log::{info, warn};
+use std::{fmt::Display, future::Future, time::Duration};
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        watch,
+    },
+    task::{AbortHandle, JoinHandle},
+};
+
+/// Connection state of a supervised subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionStatus {
+    /// Opening the event stream.
+    Connecting,
+    /// The event stream is open and receiving events.
+    Live,
+    /// The event stream was lost and will be re-opened.
+    Reconnecting {
+        /// Reconnect attempt starting at 1.
+        attempt: u32,
+        /// Delay before the next attempt.
+        next_in: Duration,
+    },
+    /// The supervisor has stopped trying to reconnect.
+    GaveUp,
+}
+
+impl Display for SubscriptionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connecting => write!(f, "connecting"),
+            Self::Live => write!(f, "live"),
+            Self::Reconnecting { attempt, next_in } => {
+                write!(f, "reconnecting #{attempt} in {}s", next_in.as_secs())
+            }
+            Self::GaveUp => write!(f, "gave up"),
+        }
+    }
+}
+
+/// Supervisor settings.
+#[derive(Debug, Clone, Copy)]
+pub struct SupervisorConfig {
+    /// Delay before the first reconnect attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between reconnect attempts.
+    pub max_backoff: Duration,
+    /// Give up after this many consecutive failed attempts. `None` retries
+    /// forever.
+    pub max_attempts: Option<u32>,
+    /// Reconnect when no event or keep-alive was received within this time.
+    /// Firebase sends a keep-alive every 30 seconds.
+    pub keep_alive_timeout: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_mins(2),
+            max_attempts: Some(10),
+            keep_alive_timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+/// Options for a single event stream connection.
+#[derive(Default)]
+pub(crate) struct StreamOptions {
+    /// End the stream when no event is received within this time.
+    pub stale_after: Option<Duration>,
+    /// Set to [`SubscriptionStatus::Live`] when events are received.
+    pub status: Option<watch::Sender<SubscriptionStatus>>,
+}
+
+impl StreamOptions {
+    /// Record that an event was received.
+    pub fn received(&self) {
+        if let Some(status) = self.status.as_ref() {
+            status.send_if_modified(|status| {
+                let changed = *status != SubscriptionStatus::Live;
+                *status = SubscriptionStatus::Live;
+                changed
+            });
+        }
+    }
+}
+
+/// A supervised subscription. Events are received on `receiver` and the
+/// connection state on `status`. The subscription ends when it gives up,
+/// when it is aborted or when the receiver is dropped.
+pub struct Subscription<EventData> {
+    pub receiver: Receiver<EventData>,
+    pub status: watch::Receiver<SubscriptionStatus>,
+    pub handle: JoinHandle<()>,
+}
+
+impl<EventData> Subscription<EventData> {
+    /// Stop the subscription.
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Handle to stop the subscription.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.handle.abort_handle()
+    }
+}
+
+/// Stream of status changes starting with the current status. Ends when
+/// the subscription ends.
+pub fn status_stream(
+    mut status: watch::Receiver<SubscriptionStatus>,
+) -> impl Stream<Item = SubscriptionStatus> {
+    async_stream::stream! {
+        let current = *status.borrow_and_update();
+        yield current;
+        while status.changed().await.is_ok() {
+            let current = *status.borrow_and_update();
+            yield current;
+        }
+    }
+}
+
+/// Exponential backoff with jitter.
+struct Backoff {
+    config: SupervisorConfig,
+    attempt: u32,
+    rng: fastrand::Rng,
+}
+
+impl Backoff {
+    fn new(config: SupervisorConfig) -> Self {
+        Self::with_rng(config, fastrand::Rng::new())
+    }
+
+    /// Backoff drawing its jitter from `rng`, seeded for repeatable delays.
+    fn with_rng(config: SupervisorConfig, rng: fastrand::Rng) -> Self {
+        Self {
+            config,
+            attempt: 0,
+            rng,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// The next attempt number and delay, or `None` when out of attempts.
+    fn next(&mut self) -> Option<(u32, Duration)> {
+        if self
+            .config
+            .max_attempts
+            .is_some_and(|max| self.attempt >= max)
+        {
+            return None;
+        }
+        let delay = self
+            .config
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(self.attempt))
+            .min(self.config.max_backoff);
+        self.attempt += 1;
+
+        // Half of the delay is fixed and half is random so many clients do not
+        // reconnect at the same time.
+        let half = delay / 2;
+        Some((self.attempt, half + jitter(&mut self.rng, half)))
+    }
+}
+
+/// A random duration up to and including `max` in whole milliseconds.
+pub(crate) fn jitter(rng: &mut fastrand::Rng, max: Duration) -> Duration {
+    Duration::from_millis(rng.u64(..=max.as_millis() as u64))
+}
+
+/// Spawn a task that keeps a subscription alive by calling `connect` until it
+/// gives up.
+pub(crate) fn supervise<EventData, F, Fut>(
+    name: String,
+    config: SupervisorConfig,
+    connect: F,
+) -> Subscription<EventData>
+where
+    EventData: Send + 'static,
+    F: Fn(Sender<EventData>, StreamOptions) -> Fut + Send + 'static,
+    Fut: Future<Output = ApiResult<StreamEnd>> + Send,
+{
+    let (tx, rx) = mpsc::channel(100);
+    let (status_tx, status_rx) = watch::channel(SubscriptionStatus::Connecting);
+
+    let handle = tokio::spawn(async move {
+        let mut backoff = Backoff::new(config);
+
+        loop {
+            status_tx.send_replace(SubscriptionStatus::Connecting);
+            let options = StreamOptions {
+                stale_after: Some(config.keep_alive_timeout),
+                status: Some(status_tx.clone()),
+            };
+            let result = tokio::select! {
+                result = connect(tx.clone(), options) => result,
+                _ = tx.closed() => {
+                    info!("Subscription {name} receiver dropped");
+                    break;
+                }
+            };
+
+            if tx.is_closed() {
+                info!("Subscription {name} receiver dropped");
+                break;
+            }
+
+            if *status_tx.borrow() == SubscriptionStatus::Live {
+                backoff.reset();
+            }
+
+            match result {
+                Ok(StreamEnd::Closed) => info!("Subscription {name} closed"),
+                Ok(StreamEnd::Stale) => warn!("Subscription {name} is stale"),
+                Ok(end @ (StreamEnd::Cancelled | StreamEnd::AuthRevoked)) => {
+                    // The server will not accept this subscription again.
+                    warn!("Subscription {name} ended with {end:?}");
+                    break;
+                }
+                Err(err) => warn!("Subscription {name} severed {err}"),
+            }
+
+            match backoff.next() {
+                Some((attempt, next_in)) => {
+                    info!("Reconnecting subscription {name} attempt {attempt} in {next_in:?}");
+                    status_tx.send_replace(SubscriptionStatus::Reconnecting { attempt, next_in });
+                    tokio::select! {
+                        _ = tokio::time::sleep(next_in) => (),
+                        _ = tx.closed() => break,
+                    }
+                }
+                None => break,
+            }
+        }
+        status_tx.send_replace(SubscriptionStatus::GaveUp);
+    });
+
+    Subscription {
+        receiver: rx,
+        status: status_rx,
+        handle,
+    }
+}
+
+#[cfg(test)]
+mod subscription_tests;
@@ -0,0 +1,294 @@
+use super::{jitter, status_stream, supervise, Backoff, SubscriptionStatus, SupervisorConfig};
+use crate::{client::StreamEnd, ApiClient, ApiError, ApiResult};
+use futures::StreamExt;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::{mpsc, watch},
+    time::timeout,
+};
+
+fn config(max_attempts: Option<u32>) -> SupervisorConfig {
+    SupervisorConfig {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(1000),
+        max_attempts,
+        keep_alive_timeout: Duration::from_millis(300),
+    }
+}
+
+async fn wait_for(
+    status: &mut watch::Receiver<SubscriptionStatus>,
+    f: impl FnMut(&SubscriptionStatus) -> bool,
+) -> SubscriptionStatus {
+    *timeout(Duration::from_secs(5), status.wait_for(f))
+        .await
+        .expect("status did not change in time")
+        .unwrap()
+}
+
+#[test]
+fn backoff_grows_up_to_the_cap() {
+    let mut backoff = Backoff::with_rng(config(None), fastrand::Rng::with_seed(7));
+
+    for (attempt, full) in [100, 200, 400, 800, 1000, 1000, 1000]
+        .into_iter()
+        .enumerate()
+    {
+        let (number, delay) = backoff.next().unwrap();
+        let full = Duration::from_millis(full);
+        assert_eq!(number, attempt as u32 + 1);
+        assert!(
+            delay >= full / 2 && delay <= full,
+            "attempt {number} delay {delay:?} not within half of {full:?}"
+        );
+    }
+
+    backoff.reset();
+    let (number, delay) = backoff.next().unwrap();
+    assert_eq!(number, 1);
+    assert!(delay <= Duration::from_millis(100));
+}
+
+#[test]
+fn backoff_runs_out_of_attempts() {
+    let mut backoff = Backoff::new(config(Some(2)));
+
+    assert_eq!(backoff.next().map(|(attempt, _)| attempt), Some(1));
+    assert_eq!(backoff.next().map(|(attempt, _)| attempt), Some(2));
+    assert_eq!(backoff.next(), None);
+
+    backoff.reset();
+    assert_eq!(backoff.next().map(|(attempt, _)| attempt), Some(1));
+}
+
+#[test]
+fn seeded_backoff_is_repeatable() {
+    let delays = |seed| {
+        let mut backoff = Backoff::with_rng(config(None), fastrand::Rng::with_seed(seed));
+        (0..5)
+            .map(|_| backoff.next().unwrap().1)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(delays(3), delays(3));
+}
+
+#[test]
+fn jitter_bounds() {
+    let mut rng = fastrand::Rng::with_seed(11);
+    let max = Duration::from_millis(50);
+
+    let samples = (0..1000).map(|_| jitter(&mut rng, max)).collect::<Vec<_>>();
+    assert!(samples.iter().all(|sample| *sample <= max));
+    assert!(samples.contains(&Duration::ZERO));
+    assert!(samples.contains(&max));
+
+    assert_eq!(jitter(&mut rng, Duration::ZERO), Duration::ZERO);
+    assert_eq!(jitter(&mut rng, Duration::from_micros(900)), Duration::ZERO);
+}
+
+/// A step of a scripted connection.
+enum Step {
+    /// Receive an event.
+    Event,
+    /// End the connection.
+    End(ApiResult<StreamEnd>),
+}
+
+/// Supervise a connection that follows the steps sent on the returned
+/// channel.
+fn scripted(config: SupervisorConfig) -> (mpsc::UnboundedSender<Step>, super::Subscription<()>) {
+    let (steps_tx, steps_rx) = mpsc::unbounded_channel();
+    let steps_rx = Arc::new(tokio::sync::Mutex::new(steps_rx));
+    let subscription = supervise("test".to_owned(), config, move |sender, options| {
+        let steps_rx = steps_rx.clone();
+        async move {
+            let mut steps_rx = steps_rx.lock().await;
+            loop {
+                match steps_rx.recv().await {
+                    Some(Step::Event) => {
+                        options.received();
+                        sender.send(()).await.map_err(|_| ApiError::ChannelClosed)?;
+                    }
+                    Some(Step::End(result)) => return result,
+                    None => return Ok(StreamEnd::Cancelled),
+                }
+            }
+        }
+    });
+    (steps_tx, subscription)
+}
+
+#[tokio::test]
+async fn status_sequence() {
+    let (steps, mut subscription) = scripted(config(Some(2)));
+
+    let seen = Arc::new(Mutex::new(vec![*subscription.status.borrow()]));
+    let recorder = tokio::spawn({
+        let seen = seen.clone();
+        let status = subscription.status.clone();
+        async move {
+            let mut statuses = std::pin::pin!(status_stream(status));
+            while let Some(status) = statuses.next().await {
+                let mut seen = seen.lock().unwrap();
+                if seen.last() != Some(&status) {
+                    seen.push(status);
+                }
+            }
+        }
+    });
+
+    let mut status = subscription.status.clone();
+    steps.send(Step::Event).unwrap();
+    assert_eq!(subscription.receiver.recv().await, Some(()));
+    wait_for(&mut status, |s| *s == SubscriptionStatus::Live).await;
+
+    // The stream was live so the backoff starts over.
+    steps.send(Step::End(Ok(StreamEnd::Closed))).unwrap();
+    wait_for(&mut status, |s| {
+        matches!(s, SubscriptionStatus::Reconnecting { attempt: 1, .. })
+    })
+    .await;
+    wait_for(&mut status, |s| *s == SubscriptionStatus::Connecting).await;
+    steps.send(Step::End(Err(ApiError::ChannelClosed))).unwrap();
+    wait_for(&mut status, |s| {
+        matches!(s, SubscriptionStatus::Reconnecting { attempt: 2, .. })
+    })
+    .await;
+    wait_for(&mut status, |s| *s == SubscriptionStatus::Connecting).await;
+    steps.send(Step::End(Ok(StreamEnd::Stale))).unwrap();
+    wait_for(&mut status, |s| *s == SubscriptionStatus::GaveUp).await;
+
+    timeout(Duration::from_secs(5), subscription.handle)
+        .await
+        .unwrap()
+        .unwrap();
+    recorder.await.unwrap();
+
+    let seen = seen
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|status| match status {
+            SubscriptionStatus::Reconnecting { attempt, next_in } => {
+                assert!(*next_in >= Duration::from_millis(50));
+                format!("reconnecting #{attempt}")
+            }
+            status => status.to_string(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        seen,
+        [
+            "connecting",
+            "live",
+            "reconnecting #1",
+            "connecting",
+            "reconnecting #2",
+            "connecting",
+            "gave up"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let attempts = Arc::new(Mutex::new(0));
+    let subscription = supervise::<(), _, _>("test".to_owned(), config(Some(3)), {
+        let attempts = attempts.clone();
+        move |_sender, _options| {
+            *attempts.lock().unwrap() += 1;
+            async { Err(ApiError::ChannelClosed) }
+        }
+    });
+    let mut status = subscription.status.clone();
+
+    wait_for(&mut status, |s| *s == SubscriptionStatus::GaveUp).await;
+    // The first connection and one for each reconnect attempt.
+    assert_eq!(*attempts.lock().unwrap(), 4);
+}
+
+#[tokio::test]
+async fn cancelled_stream_is_not_reconnected() {
+    let (steps, subscription) = scripted(config(None));
+    let mut status = subscription.status.clone();
+
+    steps.send(Step::End(Ok(StreamEnd::AuthRevoked))).unwrap();
+    wait_for(&mut status, |s| *s == SubscriptionStatus::GaveUp).await;
+}
+
+/// Serve an event stream that sends a single item event and then goes
+/// silent. Every request is counted.
+async fn silent_server() -> (String, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let _ = tx.send(path.to_owned());
+
+                let event = "event: put\ndata: {\"path\":\"/\",\"data\":{\"id\":1,\"type\":\"story\",\"by\":\"a\",\"time\":1}}\n\n";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{event}\r\n",
+                    event.len()
+                );
+                if stream.write_all(response.as_bytes()).await.is_ok() {
+                    // Hold the connection open without sending keep-alives.
+                    let _ = stream.read(&mut buf).await;
+                }
+            });
+        }
+    });
+
+    (format!("http://{address}/v0"), rx)
+}
+
+#[tokio::test]
+async fn stale_stream_reconnects() {
+    let (base_url, mut requests) = silent_server().await;
+    let client = Arc::new(ApiClient::builder().base_url(base_url).build().unwrap());
+
+    let mut subscription = client.subscribe_item(1, config(None));
+    let mut status = subscription.status.clone();
+
+    let event = timeout(Duration::from_secs(5), subscription.receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.data.id, 1);
+    assert_eq!(requests.recv().await.as_deref(), Some("/v0/item/1.json"));
+
+    // No keep-alive within `keep_alive_timeout` after the event.
+    let reconnecting = wait_for(&mut status, |s| {
+        matches!(s, SubscriptionStatus::Reconnecting { .. })
+    })
+    .await;
+    assert!(matches!(
+        reconnecting,
+        SubscriptionStatus::Reconnecting { attempt: 1, .. }
+    ));
+    let second = timeout(Duration::from_secs(5), requests.recv())
+        .await
+        .unwrap();
+    assert_eq!(second.as_deref(), Some("/v0/item/1.json"));
+
+    subscription.abort();
+}
@@ -0,0 +1,157 @@
+//! Request throttling for bulk fetches.
+//!
+//! All non streaming requests share a concurrency cap and an optional token
+//! bucket rate limiter. Requests that fail with a transient error are retried
+//! with an exponential backoff.
+use super::subscription::jitter;
+use std::{sync::Mutex, time::Duration};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+
+/// Token bucket rate limit.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Tokens added to the bucket every second.
+    pub per_second: u32,
+    /// Maximum number of tokens in the bucket.
+    pub burst: u32,
+}
+
+/// Retry settings for requests that fail with a transient error.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first failed attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Do not retry.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before the retry `attempt` starting at 1, or `None` when out of
+    /// retries.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt == 0 || attempt > self.max_retries {
+            return None;
+        }
+        let delay = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        let half = delay / 2;
+        Some(half + jitter(&mut fastrand::Rng::new(), half))
+    }
+}
+
+/// Token bucket state.
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket rate limiter.
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: limit.burst.max(1) as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token or return how long to wait for the next one.
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let per_second = self.limit.per_second.max(1) as f64;
+        let burst = self.limit.burst.max(1) as f64;
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1. - bucket.tokens) / per_second))
+        }
+    }
+
+    /// Wait until a token is available.
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Concurrency cap and rate limiter shared by all requests of a client.
+pub(crate) struct Throttle {
+    max_concurrent: usize,
+    permits: Semaphore,
+    rate_limiter: Option<RateLimiter>,
+    pub retry: RetryPolicy,
+}
+
+impl Throttle {
+    pub fn new(max_concurrent: usize, rate_limit: Option<RateLimit>, retry: RetryPolicy) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            max_concurrent,
+            permits: Semaphore::new(max_concurrent),
+            rate_limiter: rate_limit.map(RateLimiter::new),
+            retry,
+        }
+    }
+
+    /// Maximum number of requests in flight.
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Wait for a request slot. The slot is released when the permit is
+    /// dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("Throttle semaphore is never closed");
+        if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+            rate_limiter.acquire().await;
+        }
+        permit
+    }
+}
+
+#[cfg(test)]
+mod throttle_tests;
@@ -0,0 +1,34 @@
+use super::{RateLimit, RateLimiter, RetryPolicy};
+use std::time::Duration;
+
+#[test]
+fn retry_delay_is_bounded() {
+    let policy = RetryPolicy {
+        max_retries: 4,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(300),
+    };
+
+    assert_eq!(policy.delay(0), None);
+    for attempt in 1..=4 {
+        let delay = policy.delay(attempt).unwrap();
+        let full = Duration::from_millis(100 * 2_u64.pow(attempt - 1)).min(policy.max_backoff);
+        assert!(delay >= full / 2 && delay <= full, "{attempt}: {delay:?}");
+    }
+    assert_eq!(policy.delay(5), None);
+    assert_eq!(RetryPolicy::none().delay(1), None);
+}
+
+#[test]
+fn rate_limiter_allows_burst_then_waits() {
+    let limiter = RateLimiter::new(RateLimit {
+        per_second: 10,
+        burst: 3,
+    });
+
+    for _ in 0..3 {
+        assert!(limiter.try_acquire().is_ok());
+    }
+    let wait = limiter.try_acquire().unwrap_err();
+    assert!(wait <= Duration::from_millis(100), "{wait:?}");
+}
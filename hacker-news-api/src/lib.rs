@@ -1,16 +1,137 @@
 //! A simple API client for the Hacker News firebase API.
+mod algolia;
 mod client;
+mod source;
 mod types;
+mod web;
 
-pub use crate::client::{subscribe_to_article_list, ApiClient};
+pub use crate::algolia::{AlgoliaClient, AlgoliaHit, AlgoliaItem, SearchPage};
+pub use crate::web::{Session, WebClient, WebError, WebResult};
+
+pub use crate::client::{
+    status_stream, subscribe_to_article_list, ApiClient, ApiClientBuilder, CacheOptions,
+    CacheStats, ChangeFeed, CommentTree, DnsResolution, Endpoint, EndpointMetrics, EventDecoder,
+    FreshnessPolicy, Histogram, ItemCache, ListDelta, ListFeed, MetricsSnapshot, MovedId,
+    NetworkSettings, PatchData, ProxySettings, RankedId, RateLimit, RawEvent, ReplayTiming,
+    Resolver, RetryPolicy, StreamEnd, StreamEvent, SubmissionsPage, Subscription,
+    SubscriptionStatus, SupervisorConfig, TlsSettings, Transport, TreeNode, TreeOptions,
+    LATENCY_BUCKETS_MS, RECORDING_FILE,
+};
+pub use source::{ItemSource, ItemTree};
 use thiserror::Error;
-pub use types::{ArticleType, Item, ItemEventData, ResultExt, StoriesEventData, User};
+pub use types::{
+    ArticleType, Item, ItemEventData, ItemResult, ResultExt, StoriesEventData, Updates,
+    UpdatesEventData, User,
+};
 
-#[derive(Debug, Error, Default)]
+/// Errors returned by the [`ApiClient`].
+#[derive(Debug, Error)]
 pub enum ApiError {
-    #[default]
-    #[error("Stream collection failed")]
-    Default,
-    #[error("Http error {0}")]
-    Http(#[from] reqwest::Error),
+    #[error("Not found: {url}")]
+    NotFound { id: Option<u64>, url: String },
+    #[error("Http status {status} for {url}")]
+    Status {
+        status: reqwest::StatusCode,
+        id: Option<u64>,
+        url: String,
+    },
+    #[error("Request timed out: {url}")]
+    Timeout { id: Option<u64>, url: String },
+    #[error("Failed to decode {url}: {source}")]
+    Decode {
+        id: Option<u64>,
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Http error for {url}: {source}")]
+    Http {
+        id: Option<u64>,
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Event receiver channel closed")]
+    ChannelClosed,
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+    #[error("No recorded response for {url}")]
+    NotRecorded { url: String },
+}
+
+impl ApiError {
+    /// Map a request error to the matching variant.
+    pub(crate) fn request(source: reqwest::Error, id: Option<u64>, url: &str) -> Self {
+        let url = url.to_owned();
+        if source.is_timeout() {
+            Self::Timeout { id, url }
+        } else if let Some(status) = source.status() {
+            Self::Status { status, id, url }
+        } else {
+            Self::Http { id, url, source }
+        }
+    }
+
+    /// The item id of the failed request if it was for an item.
+    pub fn id(&self) -> Option<u64> {
+        match self {
+            Self::NotFound { id, .. }
+            | Self::Status { id, .. }
+            | Self::Timeout { id, .. }
+            | Self::Decode { id, .. }
+            | Self::Http { id, .. } => *id,
+            Self::ChannelClosed | Self::Config(_) | Self::NotRecorded { .. } => None,
+        }
+    }
+
+    /// The url of the failed request.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::NotFound { url, .. }
+            | Self::Status { url, .. }
+            | Self::Timeout { url, .. }
+            | Self::Decode { url, .. }
+            | Self::Http { url, .. }
+            | Self::NotRecorded { url } => Some(url),
+            Self::ChannelClosed | Self::Config(_) => None,
+        }
+    }
+
+    /// Short name of the variant for metrics and logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "not_found",
+            Self::Status { .. } => "status",
+            Self::Timeout { .. } => "timeout",
+            Self::Decode { .. } => "decode",
+            Self::Http { .. } => "http",
+            Self::ChannelClosed => "channel_closed",
+            Self::Config(_) => "config",
+            Self::NotRecorded { .. } => "not_recorded",
+        }
+    }
+
+    /// The request may succeed if retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout { .. } | Self::Http { .. } => true,
+            Self::Status { status, .. } => {
+                status.is_server_error() || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Self::NotFound { .. }
+            | Self::Decode { .. }
+            | Self::ChannelClosed
+            | Self::Config(_)
+            | Self::NotRecorded { .. } => false,
+        }
+    }
 }
+
+impl<T> From<tokio::sync::mpsc::error::SendError<T>> for ApiError {
+    fn from(_: tokio::sync::mpsc::error::SendError<T>) -> Self {
+        Self::ChannelClosed
+    }
+}
+
+/// Result with an [`ApiError`].
+pub type ApiResult<T> = Result<T, ApiError>;
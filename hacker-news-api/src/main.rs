@@ -1,27 +1,632 @@
-// use hacker_news_api::subscribe_top_stories;
+//! `hn` command line client.
+//!
+//! Prints human readable tables by default or JSON with `--json` and
+//! `--jsonl` for use in shell pipelines. Votes, favorites, flags and replies
+//! go through the website with the session saved by `hn login`.
+use anyhow::Context as _;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use hacker_news_api::{
+    ApiClient, ArticleType, Item, MetricsSnapshot, NetworkSettings, ProxySettings, Session,
+    SubscriptionStatus, SupervisorConfig, User, WebClient,
+};
+use html_sanitizer::decode_entities;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Hacker News from the command line.
+#[derive(Parser)]
+#[command(name = "hn", version)]
+struct Cli {
+    #[command(flatten)]
+    output: OutputArgs,
+    /// Firebase API base url.
+    #[arg(long, global = true, env = "HACKER_NEWS_API_URL")]
+    base_url: Option<String>,
+    #[command(flatten)]
+    network: NetworkArgs,
+    #[command(flatten)]
+    web: WebArgs,
+    /// Print request metrics to stderr when the command ends.
+    #[arg(long, global = true)]
+    stats: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// Print JSON.
+    #[arg(long, global = true, conflicts_with = "jsonl")]
+    json: bool,
+    /// Print one JSON value per line.
+    #[arg(long, global = true)]
+    jsonl: bool,
+}
+
+#[derive(Args)]
+struct NetworkArgs {
+    /// Proxy url, `direct` to ignore the proxy environment variables or
+    /// `env` to use them.
+    #[arg(long, global = true, env = "HACKER_NEWS_PROXY")]
+    proxy: Option<ProxySettings>,
+    /// PEM file with extra root certificates to trust. May be repeated.
+    #[arg(long = "ca-cert", global = true)]
+    ca_certificates: Vec<PathBuf>,
+    /// User agent sent with every request.
+    #[arg(long, global = true, env = "HACKER_NEWS_USER_AGENT")]
+    user_agent: Option<String>,
+}
+
+#[derive(Args)]
+struct WebArgs {
+    /// Website base url for the login and action commands.
+    #[arg(long, global = true, env = "HACKER_NEWS_WEB_URL", default_value = WebClient::BASE_URL)]
+    web_url: String,
+    /// File with the saved login session. Defaults to `hn/session.json` in
+    /// the user config directory.
+    #[arg(long, global = true, env = "HACKER_NEWS_SESSION")]
+    session: Option<PathBuf>,
+}
+
+impl From<NetworkArgs> for NetworkSettings {
+    fn from(args: NetworkArgs) -> Self {
+        Self {
+            proxy: args.proxy.unwrap_or_default(),
+            ca_certificates: args.ca_certificates,
+            user_agent: args.user_agent,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Api(ApiCommand),
+    #[command(flatten)]
+    Web(WebCommand),
+}
+
+/// Reads through the firebase API.
+#[derive(Subcommand)]
+enum ApiCommand {
+    /// List the articles of a front page list.
+    List {
+        #[arg(value_enum, default_value_t = ListKind::Top)]
+        kind: ListKind,
+        /// Number of articles.
+        #[arg(long, short = 'n', default_value_t = 30)]
+        limit: usize,
+    },
+    /// Show an item.
+    Item {
+        id: u64,
+        /// Include all descendants.
+        #[arg(long)]
+        tree: bool,
+    },
+    /// Show a user profile.
+    User { handle: String },
+    /// Stream updates to an item until interrupted.
+    Watch { id: u64 },
+}
+
+/// Actions through the website as the logged in user.
+#[derive(Subcommand)]
+enum WebCommand {
+    /// Log in and save the session for the other website commands.
+    Login {
+        user: String,
+        /// Password, read from stdin when not given.
+        #[arg(long, env = "HACKER_NEWS_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// Remove the saved session.
+    Logout,
+    /// Upvote an item.
+    Vote {
+        id: u64,
+        /// Remove the vote instead.
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Add an item to your favorites.
+    Favorite {
+        id: u64,
+        /// Remove it from your favorites instead.
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Flag an item.
+    Flag { id: u64 },
+    /// Comment on a story or reply to a comment.
+    Reply { parent: u64, text: String },
+    /// Change the text of your comment or story within the edit window.
+    Edit { id: u64, text: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ListKind {
+    Top,
+    Best,
+    New,
+    Ask,
+    Show,
+    Job,
+}
+
+impl From<ListKind> for ArticleType {
+    fn from(kind: ListKind) -> Self {
+        match kind {
+            ListKind::Top => ArticleType::Top,
+            ListKind::Best => ArticleType::Best,
+            ListKind::New => ArticleType::New,
+            ListKind::Ask => ArticleType::Ask,
+            ListKind::Show => ArticleType::Show,
+            ListKind::Job => ArticleType::Job,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Json,
+    JsonLines,
+}
+
+impl From<&OutputArgs> for Format {
+    fn from(args: &OutputArgs) -> Self {
+        if args.json {
+            Format::Json
+        } else if args.jsonl {
+            Format::JsonLines
+        } else {
+            Format::Table
+        }
+    }
+}
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> anyhow::Result<()> {
-    // let (mut rx, _handle) = subscribe_top_stories();
+    let cli = Cli::parse();
+    match run(cli).await {
+        // The reader of a pipeline such as `head` went away.
+        Err(err) if is_broken_pipe(&err) => Ok(()),
+        result => result,
+    }
+}
+
+fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    let kind = err
+        .downcast_ref::<io::Error>()
+        .map(io::Error::kind)
+        .or_else(|| {
+            err.downcast_ref::<serde_json::Error>()
+                .and_then(serde_json::Error::io_error_kind)
+        });
+    kind == Some(io::ErrorKind::BrokenPipe)
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let format = Format::from(&cli.output);
+    let network = NetworkSettings::from(cli.network);
+    let command = match cli.command {
+        Command::Api(command) => command,
+        Command::Web(command) => return run_web_command(cli.web, &network, command).await,
+    };
+    let mut builder = ApiClient::builder().network(network);
+    if let Some(base_url) = cli.base_url {
+        builder = builder.base_url(base_url);
+    }
+    let client = Arc::new(builder.build()?);
 
-    // let mut old_keys = Vec::new();
+    let result = run_command(&client, command, format).await;
+    if cli.stats {
+        print_stats(&client.metrics())?;
+    }
+    result
+}
 
-    // while let Some(mut data) = rx.recv().await {
-    //     data.data.sort_unstable();
-    //     // println!("received data: {data:?}");
+async fn run_command(
+    client: &Arc<ApiClient>,
+    command: ApiCommand,
+    format: Format,
+) -> anyhow::Result<()> {
+    let mut out = io::stdout().lock();
 
-    //     let new_keys = data
-    //         .data
-    //         .iter()
-    //         .copied()
-    //         .take(50)
-    //         .filter(|id| old_keys.binary_search(id).is_err())
-    //         .collect::<Vec<_>>();
+    match command {
+        ApiCommand::List { kind, limit } => {
+            let items = client.articles(limit, kind.into()).await?;
+            print_items(&mut out, format, &items, |_| 0)?;
+        }
+        ApiCommand::Item { id, tree: false } => {
+            let item = client.item(id).await?;
+            print_items(&mut out, format, &[item], |_| 0)?;
+        }
+        ApiCommand::Item { id, tree: true } => {
+            let tree = client.item_tree(id).await?;
+            let depths = depths(&tree.items);
+            print_items(&mut out, format, &tree.items, |item| {
+                depths.get(&item.id).copied().unwrap_or_default()
+            })?;
+            if tree.failed > 0 {
+                eprintln!("{} comments failed to load", tree.failed);
+            }
+        }
+        ApiCommand::User { handle } => {
+            let user = client
+                .user(&handle)
+                .await
+                .with_context(|| format!("Failed to get user {handle}"))?;
+            print_user(&mut out, format, &user)?;
+        }
+        ApiCommand::Watch { id } => {
+            let mut subscription = client.subscribe_item(id, SupervisorConfig::default());
+            loop {
+                tokio::select! {
+                    event = subscription.receiver.recv() => {
+                        let Some(event) = event else { break };
+                        let item = event.data;
+                        if format == Format::Table {
+                            writeln!(
+                                out,
+                                "{} score {} comments {}",
+                                item.id,
+                                item.score,
+                                item.descendants.unwrap_or_default()
+                            )?;
+                        } else {
+                            print_items(&mut out, format.streaming(), &[item], |_| 0)?;
+                        }
+                        out.flush()?;
+                    }
+                    changed = subscription.status.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        let status = *subscription.status.borrow_and_update();
+                        eprintln!("{status}");
+                        if status == SubscriptionStatus::GaveUp {
+                            anyhow::bail!("Lost the event stream for item {id}");
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-    //     println!("new keys total {} {new_keys:?}", new_keys.len());
+    Ok(())
+}
 
-    //     old_keys = data.data;
-    // }
+async fn run_web_command(
+    args: WebArgs,
+    network: &NetworkSettings,
+    command: WebCommand,
+) -> anyhow::Result<()> {
+    let session_path = match args.session {
+        Some(path) => path,
+        None => default_session_path()
+            .context("No user config directory for the session, use --session")?,
+    };
+    let mut client = WebClient::with_network(args.web_url, network)?;
+    if let Some(session) = load_session(&session_path)? {
+        client = client.with_session(session);
+    }
 
+    match command {
+        WebCommand::Login { user, password } => {
+            let password = match password {
+                Some(password) => password,
+                None => read_password()?,
+            };
+            let session = client.login(&user, &password).await?;
+            save_session(&session_path, session)?;
+            eprintln!("Logged in as {user}");
+        }
+        WebCommand::Logout => {
+            remove_session(&session_path)?;
+            eprintln!("Logged out");
+        }
+        WebCommand::Vote { id, undo: false } => client.upvote(id).await?,
+        WebCommand::Vote { id, undo: true } => client.unvote(id).await?,
+        WebCommand::Favorite { id, undo: false } => client.favorite(id).await?,
+        WebCommand::Favorite { id, undo: true } => client.unfavorite(id).await?,
+        WebCommand::Flag { id } => client.flag(id).await?,
+        WebCommand::Reply { parent, text } => client.reply(parent, &text).await?,
+        WebCommand::Edit { id, text } => client.edit(id, &text).await?,
+    }
     Ok(())
 }
+
+/// `hn/session.json` in the user config directory.
+fn default_session_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config_dir.join("hn").join("session.json"))
+}
+
+/// The saved session if there is one.
+fn load_session(path: &Path) -> anyhow::Result<Option<Session>> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(
+            serde_json::from_slice(&content)
+                .with_context(|| format!("Invalid session in {path:?}, log in again"))?,
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("Failed to read {path:?}")),
+    }
+}
+
+/// Save the session so only the user can read it since it is a credential.
+/// The file is created with those permissions rather than changed after
+/// the session is written.
+fn save_session(path: &Path, session: &Session) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // A new file so the permissions of an older one aren't kept.
+    remove_session(path)?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {path:?}"))?;
+    serde_json::to_writer(&mut file, session)?;
+    Ok(())
+}
+
+fn remove_session(path: &Path) -> anyhow::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("Failed to remove {path:?}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A line from stdin without its line ending.
+fn read_password() -> io::Result<String> {
+    eprint!("Password: ");
+    let mut password = String::new();
+    io::stdin().read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+fn print_stats(metrics: &MetricsSnapshot) -> io::Result<()> {
+    let mut err = io::stderr().lock();
+    writeln!(err, "{metrics}")?;
+
+    let millis = |latency: Option<Duration>| {
+        latency
+            .map(|latency| format!("{}ms", latency.as_millis()))
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    let rows = metrics
+        .endpoints
+        .iter()
+        .map(|(endpoint, metrics)| {
+            vec![
+                endpoint.to_string(),
+                metrics.requests.to_string(),
+                metrics.bytes.to_string(),
+                millis(metrics.latency.mean()),
+                millis(metrics.latency.quantile(0.95)),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(
+        &mut err,
+        &["ENDPOINT", "REQUESTS", "BYTES", "MEAN", "P95"],
+        &rows,
+    )?;
+    for (kind, count) in &metrics.errors {
+        writeln!(err, "{kind} errors: {count}")?;
+    }
+    if metrics.streams_opened > 0 {
+        writeln!(err, "event streams opened: {}", metrics.streams_opened)?;
+    }
+    Ok(())
+}
+
+impl Format {
+    /// A stream of values can't be a single JSON document.
+    fn streaming(self) -> Self {
+        match self {
+            Format::Json => Format::JsonLines,
+            format => format,
+        }
+    }
+}
+
+/// Print items as a table or JSON. `depth` indents tree items.
+fn print_items(
+    out: &mut impl Write,
+    format: Format,
+    items: &[Item],
+    depth: impl Fn(&Item) -> usize,
+) -> anyhow::Result<()> {
+    match format {
+        Format::Json => print_json(out, &items)?,
+        Format::JsonLines => {
+            for item in items {
+                serde_json::to_writer(&mut *out, item)?;
+                writeln!(out)?;
+            }
+        }
+        Format::Table => {
+            let rows = items
+                .iter()
+                .map(|item| {
+                    let summary = item
+                        .title
+                        .as_deref()
+                        .or(item.text.as_deref())
+                        .map(plain_text)
+                        .unwrap_or_else(|| {
+                            if item.deleted {
+                                "[deleted]".to_owned()
+                            } else {
+                                String::new()
+                            }
+                        });
+                    vec![
+                        item.id.to_string(),
+                        item.ty.clone(),
+                        item.score.to_string(),
+                        item.descendants
+                            .map(|count| count.to_string())
+                            .unwrap_or_default(),
+                        item.by.clone(),
+                        age(item.time),
+                        format!("{}{summary}", "  ".repeat(depth(item))),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            print_table(
+                out,
+                &["ID", "TYPE", "SCORE", "COMMENTS", "BY", "AGE", "TITLE"],
+                &rows,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn print_user(out: &mut impl Write, format: Format, user: &User) -> anyhow::Result<()> {
+    match format {
+        Format::Json => print_json(out, user)?,
+        Format::JsonLines => {
+            serde_json::to_writer(&mut *out, user)?;
+            writeln!(out)?;
+        }
+        Format::Table => {
+            let about = user.about.as_deref().map(plain_text).unwrap_or_default();
+            let rows = [
+                ("user", user.id.clone()),
+                ("karma", user.karma.to_string()),
+                ("created", age(user.created)),
+                ("submitted", user.submitted.len().to_string()),
+                ("about", about),
+            ];
+            for (name, value) in rows {
+                writeln!(out, "{name:<10} {value}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_json(out: &mut impl Write, value: &impl Serialize) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Print left aligned columns. The last column is not padded.
+fn print_table(out: &mut impl Write, headers: &[&str], rows: &[Vec<String>]) -> io::Result<()> {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    let mut print_row = |cells: &mut dyn Iterator<Item = &str>| -> io::Result<()> {
+        let mut line = String::new();
+        for (index, cell) in cells.enumerate() {
+            if index + 1 == widths.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{cell:<width$}  ", width = widths[index]));
+            }
+        }
+        writeln!(out, "{}", line.trim_end())
+    };
+
+    print_row(&mut headers.iter().copied())?;
+    for row in rows {
+        print_row(&mut row.iter().map(String::as_str))?;
+    }
+    Ok(())
+}
+
+/// Depth of each item in a tree where parents come before children.
+fn depths(items: &[Item]) -> HashMap<u64, usize> {
+    let mut depths = HashMap::with_capacity(items.len());
+    for item in items {
+        let depth = item
+            .parent
+            .and_then(|parent| depths.get(&parent))
+            .map(|depth| depth + 1)
+            .unwrap_or_default();
+        depths.insert(item.id, depth);
+    }
+    depths
+}
+
+/// Single line text with HTML tags removed and common entities decoded.
+fn plain_text(html: &str) -> String {
+    const MAX_CHARS: usize = 100;
+
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+
+    let text = decode_entities(&text);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_CHARS {
+        let truncated = text.chars().take(MAX_CHARS - 1).collect::<String>();
+        format!("{truncated}…")
+    } else {
+        text
+    }
+}
+
+/// Short age label such as `5m` or `3d`.
+fn age(time: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default();
+    let seconds = now.saturating_sub(time);
+
+    match seconds {
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s if s < 60 * 60 * 24 => format!("{}h", s / (60 * 60)),
+        s if s < 60 * 60 * 24 * 365 => format!("{}d", s / (60 * 60 * 24)),
+        s => format!("{}y", s / (60 * 60 * 24 * 365)),
+    }
+}
+
+#[cfg(test)]
+mod main_tests;
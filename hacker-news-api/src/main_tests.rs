@@ -0,0 +1,83 @@
+use super::{load_session, plain_text, remove_session, save_session};
+use hacker_news_api::Session;
+
+#[test]
+fn entities_are_decoded() {
+    assert_eq!(
+        plain_text("&quot;quoted&quot; it&#x27;s a&#x2F;b &lt;tag&gt; &amp;"),
+        r#""quoted" it's a/b <tag> &"#
+    );
+    // Decoded once so escaped entities stay entities.
+    assert_eq!(plain_text("&amp;lt;"), "&lt;");
+}
+
+#[test]
+fn paragraphs_become_spaces() {
+    assert_eq!(
+        plain_text("First line.<p>Second   line.\n<p>Third."),
+        "First line. Second line. Third."
+    );
+}
+
+#[test]
+fn links_keep_their_text() {
+    assert_eq!(
+        plain_text(
+            r#"See <a href="https:&#x2F;&#x2F;example.com&#x2F;a?b=1&amp;c=2" rel="nofollow">example.com&#x2F;a</a>."#
+        ),
+        "See example.com/a ."
+    );
+}
+
+#[test]
+fn nested_markup() {
+    assert_eq!(
+        plain_text("<i>very</i> <b>bold</b><pre><code>  let x = 1;\n</code></pre>"),
+        "very bold let x = 1;"
+    );
+}
+
+#[test]
+fn malformed_tags() {
+    // An unclosed tag swallows the rest of the text.
+    assert_eq!(plain_text("before <i unclosed"), "before");
+    // A stray closing bracket is dropped.
+    assert_eq!(plain_text("a > b"), "a b");
+    assert_eq!(plain_text("<<b>>text"), "text");
+}
+
+#[test]
+fn long_text_is_truncated() {
+    let text = plain_text(&"word ".repeat(50));
+
+    assert_eq!(text.chars().count(), 100);
+    assert!(text.ends_with('…'));
+    assert_eq!(plain_text("short"), "short");
+}
+
+#[test]
+fn session_is_saved_for_the_user_only() {
+    let dir = std::env::temp_dir().join(format!("hn-session-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("hn").join("session.json");
+    assert!(load_session(&path).unwrap().is_none());
+
+    let session: Session =
+        serde_json::from_value(serde_json::json!({"user": "pg", "cookie": "pg&secret"})).unwrap();
+    save_session(&path, &session).unwrap();
+    // Saving again replaces the session.
+    save_session(&path, &session).unwrap();
+    assert_eq!(load_session(&path).unwrap().unwrap().user, "pg");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    remove_session(&path).unwrap();
+    remove_session(&path).unwrap();
+    assert!(load_session(&path).unwrap().is_none());
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,25 @@
+//! Common interface for item backends.
+use crate::{ApiResult, Item};
+use futures::future::BoxFuture;
+
+/// An item with its descendants fetched by an [`ItemSource`].
+#[derive(Debug, Clone, Default)]
+pub struct ItemTree {
+    /// The item first and every parent before its children.
+    pub items: Vec<Item>,
+    /// Number of descendants that failed to fetch. They are left out of
+    /// `items` along with their replies.
+    pub failed: usize,
+}
+
+/// A backend that can fetch hacker news items.
+pub trait ItemSource: Send + Sync {
+    /// Name of the backend for logging.
+    fn name(&self) -> &'static str;
+
+    /// Fetch an item with all of its descendants. The item is first and every
+    /// parent comes before its children, which follow the order of its
+    /// `kids`. That is display order for the firebase API but not for
+    /// Algolia, which returns replies in its own order.
+    fn item_tree(&self, id: u64) -> BoxFuture<'_, ApiResult<ItemTree>>;
+}
@@ -9,7 +9,7 @@ use std::{
 /// Hacker news item.
 ///
 /// [`https://github.com/HackerNews/API`]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Item {
     /// The item's unique id.
     pub id: u64,
@@ -24,6 +24,7 @@ pub struct Item {
     #[serde(default)]
     pub score: u64,
     /// Creation date of the item, in Unix Time.
+    #[serde(default)]
     pub time: u64,
     #[serde(default)]
     /// The username of the item's author.
@@ -41,6 +42,71 @@ pub struct Item {
     pub parent: Option<u64>,
     /// In the case of stories or polls, the total comment count.
     pub descendants: Option<u64>,
+    #[serde(default)]
+    /// A list of related pollopts, in display order.
+    pub parts: Vec<u64>,
+    /// The pollopt's associated poll.
+    pub poll: Option<u64>,
+}
+
+/// An item fetch result that keeps deleted, dead and missing items so the
+/// shape of a comment thread is not lost.
+#[derive(Debug, Clone)]
+pub enum ItemResult {
+    /// A live item.
+    Present(Item),
+    /// A deleted item. Only the id, parent, kids and time are available.
+    Deleted(Item),
+    /// An item that was killed by flags or moderators.
+    Dead(Item),
+    /// The API returned `null` for the item id.
+    Missing(u64),
+}
+
+impl ItemResult {
+    /// The item id.
+    pub fn id(&self) -> u64 {
+        match self {
+            Self::Present(item) | Self::Deleted(item) | Self::Dead(item) => item.id,
+            Self::Missing(id) => *id,
+        }
+    }
+
+    /// The item if it is not missing.
+    pub fn item(&self) -> Option<&Item> {
+        match self {
+            Self::Present(item) | Self::Deleted(item) | Self::Dead(item) => Some(item),
+            Self::Missing(_) => None,
+        }
+    }
+
+    /// Convert into the item if it is not missing.
+    pub fn into_item(self) -> Option<Item> {
+        match self {
+            Self::Present(item) | Self::Deleted(item) | Self::Dead(item) => Some(item),
+            Self::Missing(_) => None,
+        }
+    }
+
+    /// Convert into the item if it is present.
+    pub fn into_present(self) -> Option<Item> {
+        match self {
+            Self::Present(item) => Some(item),
+            _ => None,
+        }
+    }
+}
+
+impl From<Item> for ItemResult {
+    fn from(item: Item) -> Self {
+        if item.deleted {
+            Self::Deleted(item)
+        } else if item.dead {
+            Self::Dead(item)
+        } else {
+            Self::Present(item)
+        }
+    }
 }
 
 /// Hacker news user.
@@ -55,18 +121,34 @@ pub struct User {
 }
 
 /// An event-source for hacker-news.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct StoriesEventData {
     pub path: String,
     pub data: Vec<u64>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct ItemEventData {
     pub path: String,
     pub data: Item,
 }
 
+/// Recently changed items and profiles.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Updates {
+    #[serde(default)]
+    pub items: Vec<u64>,
+    #[serde(default)]
+    pub profiles: Vec<String>,
+}
+
+/// An event-source for the updates end point.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdatesEventData {
+    pub path: String,
+    pub data: Updates,
+}
+
 /// Extension trait for the Result type to add logging capabilities.
 pub trait ResultExt<T, E> {
     /// If the result is [`Err`] then log the error.
@@ -107,6 +189,16 @@ pub enum ArticleType {
 }
 
 impl ArticleType {
+    /// All categories.
+    pub const ALL: [ArticleType; 6] = [
+        ArticleType::New,
+        ArticleType::Best,
+        ArticleType::Top,
+        ArticleType::Ask,
+        ArticleType::Show,
+        ArticleType::Job,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ArticleType::New => "New",
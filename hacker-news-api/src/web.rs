@@ -0,0 +1,347 @@
+//! Authenticated actions through the Hacker News website.
+//!
+//! The firebase API is read only. Votes, favorites, flags and comments go
+//! through the same HTML pages and forms a browser uses. Each action loads
+//! the page with the link or form for the item and follows it with the
+//! per user `auth` or `hmac` token found there, so actions only work while
+//! the site offers them, such as editing within the edit window.
+use crate::{ApiError, NetworkSettings};
+use html_sanitizer::decode_entities;
+use log::info;
+use reqwest::{header, redirect, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, time::Duration};
+use thiserror::Error;
+
+/// Errors returned by the [`WebClient`].
+#[derive(Debug, Error)]
+pub enum WebError {
+    /// The request itself failed.
+    #[error(transparent)]
+    Api(#[from] ApiError),
+    #[error("Not logged in")]
+    NotLoggedIn,
+    #[error("Failed to log in as {user}")]
+    LoginFailed { user: String },
+    #[error("Can't {action} item {id}")]
+    ActionUnavailable { action: &'static str, id: u64 },
+    #[error("Rejected by {url}: {message}")]
+    Rejected { url: String, message: String },
+}
+
+/// Result with a [`WebError`].
+pub type WebResult<T> = Result<T, WebError>;
+
+/// A logged in user. The session can be saved and restored to stay logged
+/// in across restarts.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// User handle.
+    pub user: String,
+    /// Value of the `user` cookie set by the login form.
+    cookie: String,
+}
+
+/// The cookie is a credential and is left out.
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("user", &self.user)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Hacker News website client.
+pub struct WebClient {
+    client: reqwest::Client,
+    base_url: String,
+    session: Option<Session>,
+}
+
+impl WebClient {
+    /// Base url of the Hacker News website.
+    pub const BASE_URL: &'static str = "https://news.ycombinator.com";
+
+    /// Create a new client for the Hacker News website.
+    pub fn new() -> WebResult<Self> {
+        Self::with_network(Self::BASE_URL, &NetworkSettings::default())
+    }
+
+    /// Create a new client for a different base url such as a local
+    /// fixture server with the proxy, certificates and user agent of
+    /// `network`.
+    pub fn with_network(base_url: impl Into<String>, network: &NetworkSettings) -> WebResult<Self> {
+        let base_url = base_url.into().trim_end_matches('/').to_owned();
+        let builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(30))
+            .gzip(true)
+            .use_rustls_tls()
+            // Successful form posts redirect. The redirected page isn't
+            // needed and the login cookie is only on the redirect.
+            .redirect(redirect::Policy::none());
+        let client = network
+            .apply(builder)?
+            .build()
+            .map_err(|err| ApiError::Config(format!("Failed to build the http client: {err}")))?;
+
+        Ok(Self {
+            client,
+            base_url,
+            session: None,
+        })
+    }
+
+    /// Restore a saved session.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// The logged in user.
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    /// Log in with the login form. The returned session should be saved to
+    /// stay logged in.
+    pub async fn login(&mut self, user: &str, password: &str) -> WebResult<&Session> {
+        let url = self.url("login");
+        let response = self
+            .client
+            .post(&url)
+            .form(&[("acct", user), ("pw", password), ("goto", "news")])
+            .send()
+            .await
+            .map_err(|err| ApiError::request(err, None, &url))?;
+
+        // A failed login shows the login page again without a cookie.
+        let cookie = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split(';').next()?.strip_prefix("user="))
+            .find(|cookie| !cookie.is_empty())
+            .ok_or_else(|| WebError::LoginFailed {
+                user: user.to_owned(),
+            })?;
+
+        info!("Logged in as {user}");
+        Ok(self.session.insert(Session {
+            user: user.to_owned(),
+            cookie: cookie.to_owned(),
+        }))
+    }
+
+    /// Forget the session.
+    pub fn logout(&mut self) {
+        self.session = None;
+    }
+
+    /// Upvote an item.
+    pub async fn upvote(&self, id: u64) -> WebResult<()> {
+        self.follow_link("upvote", id, &format!("vote?id={id}&how=up&"))
+            .await
+    }
+
+    /// Remove a vote within the unvote window.
+    pub async fn unvote(&self, id: u64) -> WebResult<()> {
+        self.follow_link("unvote", id, &format!("vote?id={id}&how=un&"))
+            .await
+    }
+
+    /// Add an item to the user's favorites.
+    pub async fn favorite(&self, id: u64) -> WebResult<()> {
+        self.follow_link("favorite", id, &format!("fave?id={id}&auth="))
+            .await
+    }
+
+    /// Remove an item from the user's favorites.
+    pub async fn unfavorite(&self, id: u64) -> WebResult<()> {
+        self.follow_link("unfavorite", id, &format!("fave?id={id}&un=t&"))
+            .await
+    }
+
+    /// Flag an item.
+    pub async fn flag(&self, id: u64) -> WebResult<()> {
+        self.follow_link("flag", id, &format!("flag?id={id}&auth="))
+            .await
+    }
+
+    /// Post a comment on a story or a reply to a comment.
+    pub async fn reply(&self, parent: u64, text: &str) -> WebResult<()> {
+        let page = self
+            .page(&format!("reply?id={parent}&goto=item%3Fid%3D{parent}"))
+            .await?;
+        let mut fields = form_fields(&page, "comment").ok_or(WebError::ActionUnavailable {
+            action: "reply",
+            id: parent,
+        })?;
+        fields.insert("text".to_owned(), text.to_owned());
+        self.submit("comment", &fields).await
+    }
+
+    /// Change the text of the user's own comment or story within the edit
+    /// window.
+    pub async fn edit(&self, id: u64, text: &str) -> WebResult<()> {
+        let page = self.page(&format!("edit?id={id}")).await?;
+        let mut fields = form_fields(&page, "xedit")
+            .ok_or(WebError::ActionUnavailable { action: "edit", id })?;
+        fields.insert("text".to_owned(), text.to_owned());
+        self.submit("xedit", &fields).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{path}", self.base_url)
+    }
+
+    fn cookie(&self) -> WebResult<String> {
+        let session = self.session.as_ref().ok_or(WebError::NotLoggedIn)?;
+        Ok(format!("user={}", session.cookie))
+    }
+
+    /// Load a page as the logged in user.
+    async fn page(&self, path: &str) -> WebResult<String> {
+        let url = self.url(path);
+        let response = self
+            .client
+            .get(&url)
+            .header(header::COOKIE, self.cookie()?)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ApiError::request(err, None, &url))?;
+        Ok(response
+            .text()
+            .await
+            .map_err(|err| ApiError::request(err, None, &url))?)
+    }
+
+    /// Find the link starting with `prefix` on the item page and follow it.
+    async fn follow_link(&self, action: &'static str, id: u64, prefix: &str) -> WebResult<()> {
+        let page = self.page(&format!("item?id={id}")).await?;
+        let link = links(&page)
+            .find(|link| link.starts_with(prefix))
+            .ok_or(WebError::ActionUnavailable { action, id })?;
+
+        let url = self.url(&link);
+        let response = self
+            .client
+            .get(&url)
+            .header(header::COOKIE, self.cookie()?)
+            .send()
+            .await
+            .map_err(|err| ApiError::request(err, Some(id), &url))?;
+        check_action(response, &url).await
+    }
+
+    /// Post a form as the logged in user.
+    async fn submit(&self, action: &str, fields: &HashMap<String, String>) -> WebResult<()> {
+        let url = self.url(action);
+        let response = self
+            .client
+            .post(&url)
+            .header(header::COOKIE, self.cookie()?)
+            .form(fields)
+            .send()
+            .await
+            .map_err(|err| ApiError::request(err, None, &url))?;
+        check_action(response, &url).await
+    }
+}
+
+/// Actions redirect when they succeed. Otherwise the site responds with a
+/// page explaining why, such as posting too fast.
+async fn check_action(response: reqwest::Response, url: &str) -> WebResult<()> {
+    let status = response.status();
+    if status.is_redirection() {
+        return Ok(());
+    }
+    if status != StatusCode::OK {
+        return Err(ApiError::Status {
+            status,
+            id: None,
+            url: url.to_owned(),
+        }
+        .into());
+    }
+
+    let page = response
+        .text()
+        .await
+        .map_err(|err| ApiError::request(err, None, url))?;
+    Err(WebError::Rejected {
+        url: url.to_owned(),
+        message: text_content(&page),
+    })
+}
+
+/// Decoded values of all `href` attributes.
+fn links(page: &str) -> impl Iterator<Item = String> + '_ {
+    page.match_indices("href=")
+        .filter_map(|(index, _)| quoted(&page[index + "href=".len()..]))
+        .map(decode_entities)
+}
+
+/// Names and values of the inputs of the form posting to `action`.
+fn form_fields(page: &str, action: &str) -> Option<HashMap<String, String>> {
+    let form = page
+        .match_indices("<form")
+        .map(|(index, _)| &page[index..])
+        .map(|form| &form[..form.find("</form>").unwrap_or(form.len())])
+        .find(|form| {
+            let tag = &form[..form.find('>').unwrap_or(form.len())];
+            attribute(tag, "action").as_deref() == Some(action)
+        })?;
+
+    Some(
+        form.match_indices("<input")
+            .map(|(index, _)| &form[index..])
+            .map(|input| &input[..input.find('>').unwrap_or(input.len())])
+            .filter_map(|input| Some((attribute(input, "name")?, attribute(input, "value")?)))
+            .collect(),
+    )
+}
+
+/// Value of an attribute in a tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=");
+    let index = tag.find(&pattern)? + pattern.len();
+    let rest = &tag[index..];
+    let value = quoted(rest).unwrap_or_else(|| {
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '>')
+            .unwrap_or(rest.len());
+        &rest[..end]
+    });
+    Some(decode_entities(value))
+}
+
+/// The text between the quotes at the start of `value`.
+fn quoted(value: &str) -> Option<&str> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+/// Text of a page without tags and with whitespace collapsed.
+fn text_content(page: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in page.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    decode_entities(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod web_tests;
@@ -0,0 +1,215 @@
+use super::{form_fields, links, WebClient, WebError};
+use crate::{NetworkSettings, ProxySettings};
+use std::sync::{Arc, Mutex};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// A request received by the stand-in server.
+#[derive(Debug, Clone)]
+struct Request {
+    /// Method and path such as `GET /item?id=1`.
+    line: String,
+    cookie: Option<String>,
+    body: String,
+}
+
+/// Serve the captured pages like news.ycombinator.com and keep the
+/// requests.
+async fn site() -> (WebClient, Arc<Mutex<Vec<Request>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let received = requests.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut data = Vec::new();
+            let mut buf = [0; 4096];
+            let head_end = loop {
+                if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break data.len(),
+                    Ok(n) => data.extend_from_slice(&buf[..n]),
+                }
+            };
+            let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+            let header = |name: &str| {
+                head.lines()
+                    .filter_map(|line| line.split_once(": "))
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.to_owned())
+            };
+            let length = header("content-length")
+                .and_then(|length| length.parse().ok())
+                .unwrap_or(0);
+            while data.len() < head_end + length {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => data.extend_from_slice(&buf[..n]),
+                }
+            }
+
+            let request = Request {
+                line: head.split(" HTTP/").next().unwrap_or_default().to_owned(),
+                cookie: header("cookie"),
+                body: String::from_utf8_lossy(&data[head_end..]).into_owned(),
+            };
+            received.lock().unwrap().push(request.clone());
+
+            let (path, query) = request
+                .line
+                .split_once(' ')
+                .map(|(_, target)| target.split_once('?').unwrap_or((target, "")))
+                .unwrap_or_default();
+            let page = |page: &str| ("200 OK", Vec::new(), page.to_owned());
+            let redirect = || ("302 Found", Vec::new(), String::new());
+            let (status, headers, body) = match path {
+                "/login" if request.body.contains("pw=secret") => (
+                    "302 Found",
+                    vec!["Set-Cookie: user=alice&s3ss10n; Path=/; Secure; HttpOnly".to_owned()],
+                    String::new(),
+                ),
+                "/login" => page(include_str!("fixtures/login.html")),
+                "/item" => page(include_str!("fixtures/item.html")),
+                "/reply" => page(include_str!("fixtures/reply.html")),
+                "/edit" if query == "id=2" => page(include_str!("fixtures/edit.html")),
+                "/edit" => page(include_str!("fixtures/unavailable.html")),
+                "/comment" if request.body.contains("fast") => {
+                    page(include_str!("fixtures/rejected.html"))
+                }
+                "/vote" | "/fave" | "/flag" | "/comment" | "/xedit" => redirect(),
+                _ => ("404 Not Found", Vec::new(), String::new()),
+            };
+            let headers = headers
+                .iter()
+                .map(|header| format!("{header}\r\n"))
+                .collect::<String>();
+            let response = format!(
+                "HTTP/1.1 {status}\r\nLocation: item?id=1\r\n{headers}Content-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    let network = NetworkSettings {
+        proxy: ProxySettings::Direct,
+        ..Default::default()
+    };
+    let client = WebClient::with_network(format!("http://{address}"), &network).unwrap();
+    (client, requests)
+}
+
+#[test]
+fn tokens_are_found() {
+    let page = include_str!("fixtures/item.html");
+    let vote = links(page)
+        .find(|link| link.starts_with("vote?id=1&how=up&"))
+        .unwrap();
+    assert_eq!(vote, "vote?id=1&how=up&auth=9f2a1b7c&goto=item%3Fid%3D1");
+
+    let fields = form_fields(page, "comment").unwrap();
+    assert_eq!(fields["parent"], "1");
+    assert_eq!(fields["goto"], "item?id=1");
+    assert_eq!(fields["hmac"], "c0ffee11");
+    assert!(form_fields(page, "xedit").is_none());
+}
+
+#[tokio::test]
+async fn login_and_vote() {
+    let (mut client, requests) = site().await;
+
+    assert!(matches!(client.upvote(1).await, Err(WebError::NotLoggedIn)));
+    assert!(matches!(
+        client.login("alice", "wrong").await,
+        Err(WebError::LoginFailed { .. })
+    ));
+    let session = client.login("alice", "secret").await.unwrap().clone();
+    assert_eq!(session.user, "alice");
+    assert!(!format!("{session:?}").contains("s3ss10n"));
+
+    // A restored session works the same in a new client.
+    let (client, requests_after) = site().await;
+    let client = client.with_session(session);
+    client.upvote(1).await.unwrap();
+    client.unvote(3).await.unwrap();
+    client.favorite(1).await.unwrap();
+    client.flag(1).await.unwrap();
+    assert!(matches!(
+        client.unvote(2).await,
+        Err(WebError::ActionUnavailable {
+            action: "unvote",
+            id: 2
+        })
+    ));
+
+    let login = requests.lock().unwrap().last().cloned().unwrap();
+    assert_eq!(login.line, "POST /login");
+    assert!(login.body.contains("acct=alice"), "{}", login.body);
+
+    let requests = requests_after.lock().unwrap();
+    let actions = requests
+        .iter()
+        .filter(|request| !request.line.starts_with("GET /item"))
+        .map(|request| request.line.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        [
+            "GET /vote?id=1&how=up&auth=9f2a1b7c&goto=item%3Fid%3D1",
+            "GET /vote?id=3&how=un&auth=1a2b3c4d&goto=item%3Fid%3D1",
+            "GET /fave?id=1&auth=9f2a1b7c",
+            "GET /flag?id=1&auth=9f2a1b7c&goto=item%3Fid%3D1",
+        ]
+    );
+    assert!(requests
+        .iter()
+        .all(|request| request.cookie.as_deref() == Some("user=alice&s3ss10n")));
+}
+
+#[tokio::test]
+async fn reply_and_edit() {
+    let (mut client, requests) = site().await;
+    client.login("alice", "secret").await.unwrap();
+
+    client.reply(2, "Thanks").await.unwrap();
+    let err = client.reply(2, "too fast").await.unwrap_err();
+    assert!(
+        matches!(&err, WebError::Rejected { message, .. } if message.contains("posting too fast")),
+        "{err}"
+    );
+
+    client.edit(2, "A changed comment").await.unwrap();
+    assert!(matches!(
+        client.edit(3, "Not mine").await,
+        Err(WebError::ActionUnavailable {
+            action: "edit",
+            id: 3
+        })
+    ));
+
+    let requests = requests.lock().unwrap();
+    let posts = requests
+        .iter()
+        .filter(|request| {
+            request.line.starts_with("POST /comment") || request.line == "POST /xedit"
+        })
+        .map(|request| request.body.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(posts.len(), 3);
+    for field in [
+        "parent=2",
+        "hmac=be7a11ed",
+        "goto=item%3Fid%3D1%232",
+        "text=Thanks",
+    ] {
+        assert!(posts[0].contains(field), "{field} in {}", posts[0]);
+    }
+    for field in ["id=2", "hmac=ed17ed17", "text=A+changed+comment"] {
+        assert!(posts[2].contains(field), "{field} in {}", posts[2]);
+    }
+}
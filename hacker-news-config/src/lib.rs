@@ -1,31 +1,99 @@
 //! Configuration for client apps.
 use std::{
     path::PathBuf,
-    sync::{Arc, RwLock},
+    sync::{Arc, OnceLock, RwLock},
 };
 
 use anyhow::Context as _;
 use app_dirs2::{AppDataType, AppInfo, get_app_dir, get_app_root};
 use flexi_logger::{Age, Cleanup, Criterion, FileSpec, Naming, opt_format};
-use hacker_news_api::ArticleType;
-use hacker_news_search::{IndexStats, SearchContext};
-use log::info;
+use hacker_news_api::{
+    AlgoliaClient, ApiClientBuilder, ArticleType, CacheOptions, DnsResolution, ItemCache,
+    NetworkSettings, ProxySettings, ReplayTiming, Transport,
+};
+use hacker_news_search::{IndexStats, RebuildOptions, SearchContext};
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
-#[cfg(target_family = "unix")]
-pub mod limits;
-
 /// Saved viewing state of the index
 pub const INDEX_CONFIG: &str = "index_config.data";
 
+/// Saved network settings.
+pub const NETWORK_CONFIG: &str = "network_config.data";
+
 /// Index configuration.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct IndexConfig {
     pub index_stats: Vec<IndexStats>,
     pub viewing_count: usize,
     pub viewing_type: ArticleType,
+    /// Show the body of dead comments.
+    #[serde(default)]
+    pub show_dead: bool,
+    /// Limits used when rebuilding a category index.
+    #[serde(default)]
+    pub rebuild_options: RebuildOptions,
+}
+
+/// Network settings shared by all apps. Each field is overridden by its
+/// environment variable, see [`network_settings`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct NetworkConfig {
+    /// Proxy url, `direct` to ignore the standard proxy variables or `env`
+    /// to use them.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Comma separated hosts that bypass `proxy`.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// PEM files of extra root certificates to trust.
+    #[serde(default)]
+    pub ca_certificates: Vec<PathBuf>,
+    /// User agent sent with every request.
+    #[serde(default)]
+    pub user_agent: Option<String>,
 }
 
+/// Environment variable overriding the API base url. Useful for pointing
+/// the apps at a mirror or a local fake server.
+pub const API_URL_ENV: &str = "HACKER_NEWS_API_URL";
+
+/// Environment variable selecting the backend for comment trees when
+/// rebuilding the index. Set to `algolia` to use the Hacker News Search API.
+pub const ITEM_SOURCE_ENV: &str = "HACKER_NEWS_ITEM_SOURCE";
+
+/// Environment variable with a directory to record all API traffic to.
+pub const RECORD_ENV: &str = "HACKER_NEWS_RECORD";
+
+/// Environment variable with a directory of recorded API traffic to replay
+/// instead of using the network.
+pub const REPLAY_ENV: &str = "HACKER_NEWS_REPLAY";
+
+/// Environment variable with the replay speed. `1` keeps the recorded
+/// timing, `10` is ten times faster and `0` replays without delay. Negative
+/// speeds fail to build the client.
+pub const REPLAY_SPEED_ENV: &str = "HACKER_NEWS_REPLAY_SPEED";
+
+/// Environment variable with the proxy url for all API traffic. Set to
+/// `direct` to ignore the standard `HTTPS_PROXY`, `HTTP_PROXY` and
+/// `ALL_PROXY` variables, which are used when this is not set.
+pub const PROXY_ENV: &str = "HACKER_NEWS_PROXY";
+
+/// Environment variable with comma separated hosts that bypass the proxy
+/// set with [`PROXY_ENV`].
+pub const NO_PROXY_ENV: &str = "HACKER_NEWS_NO_PROXY";
+
+/// Environment variable with PEM files of extra root certificates to trust,
+/// separated like `PATH`.
+pub const CA_CERTS_ENV: &str = "HACKER_NEWS_CA_CERTS";
+
+/// Environment variable with the user agent sent with every request.
+pub const USER_AGENT_ENV: &str = "HACKER_NEWS_USER_AGENT";
+
+/// Environment variable to turn off the item cache shared by the apps when
+/// set to `off`.
+pub const CACHE_ENV: &str = "HACKER_NEWS_CACHE";
+
 /// Application information.
 pub const APP_INFO: AppInfo = AppInfo {
     name: "Hacker News",
@@ -84,6 +152,164 @@ pub fn init_logger(base_name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Api client builder shared by all client apps. The base url can be
+/// overridden with the [`API_URL_ENV`] environment variable, traffic
+/// recorded or replayed with [`RECORD_ENV`] and [`REPLAY_ENV`] and the
+/// network policy set as described in [`network_settings`]. Items are
+/// cached as described in [`item_cache`].
+pub fn api_client_builder() -> ApiClientBuilder {
+    let builder = match std::env::var(API_URL_ENV) {
+        Ok(base_url) => {
+            info!("Using api base url {base_url}");
+            ApiClientBuilder::new()
+                .base_url(base_url)
+                .dns_resolution(DnsResolution::System)
+        }
+        Err(_) => ApiClientBuilder::new(),
+    };
+    let transport = transport();
+    // Replayed traffic is served as recorded.
+    let cache = match transport {
+        Transport::Replay { .. } => None,
+        _ => item_cache(),
+    };
+    builder
+        .transport(transport)
+        .network(network_settings())
+        .item_cache(cache)
+}
+
+/// Item cache in the user cache directory shared by all apps, unless turned
+/// off with the [`CACHE_ENV`] environment variable. The cache is opened
+/// once per process.
+pub fn item_cache() -> Option<Arc<ItemCache>> {
+    static CACHE: OnceLock<Option<Arc<ItemCache>>> = OnceLock::new();
+
+    CACHE
+        .get_or_init(|| {
+            if std::env::var(CACHE_ENV).is_ok_and(|cache| cache == "off") {
+                info!("Item cache is off");
+                return None;
+            }
+            let cache = get_app_dir(AppDataType::UserCache, &APP_INFO, "item-cache")
+                .context("No cache directory")
+                .and_then(|dir| Ok(ItemCache::open(dir, CacheOptions::default())?));
+            match cache {
+                Ok(cache) => Some(Arc::new(cache)),
+                Err(err) => {
+                    warn!("Failed to open item cache: {err}");
+                    None
+                }
+            }
+        })
+        .clone()
+}
+
+/// Limits for rebuilding an index saved in the [`IndexConfig`], or the
+/// defaults when nothing was saved.
+pub fn rebuild_options() -> RebuildOptions {
+    saved_config::<IndexConfig>(INDEX_CONFIG).rebuild_options
+}
+
+/// Saved configuration in `file_name`, or the default when it can't be
+/// loaded. Only a missing file is expected, so other errors are logged.
+fn saved_config<C>(file_name: &str) -> C
+where
+    C: for<'a> Deserialize<'a> + Default,
+{
+    load_config(file_name).unwrap_or_else(|err: anyhow::Error| {
+        if err
+            .downcast_ref::<std::io::Error>()
+            .is_none_or(|err| err.kind() != std::io::ErrorKind::NotFound)
+        {
+            warn!("Failed to load {file_name}: {err}");
+        }
+        C::default()
+    })
+}
+
+/// Proxy, extra root certificates and user agent from the saved
+/// [`NetworkConfig`]. The [`PROXY_ENV`], [`NO_PROXY_ENV`], [`CA_CERTS_ENV`]
+/// and [`USER_AGENT_ENV`] environment variables override the saved values.
+pub fn network_settings() -> NetworkSettings {
+    merge_network_settings(saved_config(NETWORK_CONFIG), |name| std::env::var_os(name))
+}
+
+/// Override the fields of `config` with the environment variables returned
+/// by `var`.
+fn merge_network_settings(
+    config: NetworkConfig,
+    var: impl Fn(&str) -> Option<std::ffi::OsString>,
+) -> NetworkSettings {
+    let string_var = |name| var(name).and_then(|value| value.into_string().ok());
+    let proxy = match string_var(PROXY_ENV).or(config.proxy) {
+        Some(proxy) => {
+            let Ok(proxy) = proxy.parse();
+            match proxy {
+                // The url may hold credentials so it isn't logged.
+                ProxySettings::Url { url, .. } => {
+                    info!("Using the configured proxy");
+                    ProxySettings::Url {
+                        url,
+                        no_proxy: string_var(NO_PROXY_ENV).or(config.no_proxy),
+                    }
+                }
+                proxy => proxy,
+            }
+        }
+        None => ProxySettings::Environment,
+    };
+
+    NetworkSettings {
+        proxy,
+        ca_certificates: var(CA_CERTS_ENV)
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or(config.ca_certificates),
+        user_agent: string_var(USER_AGENT_ENV).or(config.user_agent),
+    }
+}
+
+fn transport() -> Transport {
+    if let Some(dir) = std::env::var_os(REPLAY_ENV) {
+        let timing = match std::env::var(REPLAY_SPEED_ENV).map(|speed| speed.parse::<f64>()) {
+            Ok(Ok(0.)) => ReplayTiming::Immediate,
+            Ok(Ok(speed)) => ReplayTiming::Faster(speed),
+            Ok(Err(err)) => {
+                warn!("Invalid {REPLAY_SPEED_ENV}: {err}");
+                ReplayTiming::Original
+            }
+            Err(_) => ReplayTiming::Original,
+        };
+        info!("Replaying api traffic from {dir:?} with {timing:?} timing");
+        Transport::Replay {
+            dir: dir.into(),
+            timing,
+        }
+    } else if let Some(dir) = std::env::var_os(RECORD_ENV) {
+        info!("Recording api traffic to {dir:?}");
+        Transport::Record(dir.into())
+    } else {
+        Transport::Live
+    }
+}
+
+/// Install the comment tree backend selected with the [`ITEM_SOURCE_ENV`]
+/// environment variable.
+pub fn init_item_source() -> anyhow::Result<()> {
+    match std::env::var(ITEM_SOURCE_ENV).as_deref() {
+        Ok("algolia") => {
+            info!("Using algolia item source");
+            hacker_news_search::init_item_source(Arc::new(AlgoliaClient::with_network(
+                AlgoliaClient::API_END_POINT,
+                &network_settings(),
+            )?));
+        }
+        Ok("firebase") | Err(_) => (),
+        Ok(other) => anyhow::bail!("Unknown item source {other}"),
+    }
+    Ok(())
+}
+
 pub fn search_context() -> anyhow::Result<Arc<RwLock<SearchContext>>> {
     let index_dir = get_app_dir(
         app_dirs2::AppDataType::UserData,
@@ -100,3 +326,6 @@ pub fn search_context() -> anyhow::Result<Arc<RwLock<SearchContext>>> {
 
     Ok(search_context)
 }
+
+#[cfg(test)]
+mod lib_tests;
@@ -0,0 +1,93 @@
+use super::{
+    CA_CERTS_ENV, NO_PROXY_ENV, NetworkConfig, PROXY_ENV, USER_AGENT_ENV, merge_network_settings,
+};
+use hacker_news_api::{NetworkSettings, ProxySettings};
+use std::{collections::HashMap, ffi::OsString, path::PathBuf};
+
+fn merge(config: &NetworkConfig, env: &[(&str, &str)]) -> NetworkSettings {
+    let env = env
+        .iter()
+        .map(|(name, value)| (name.to_string(), OsString::from(value)))
+        .collect::<HashMap<_, _>>();
+    merge_network_settings(config.clone(), |name| env.get(name).cloned())
+}
+
+fn proxy_url(settings: &NetworkSettings) -> (&str, Option<&str>) {
+    match &settings.proxy {
+        ProxySettings::Url { url, no_proxy } => (url, no_proxy.as_deref()),
+        proxy => panic!("not a proxy url: {proxy:?}"),
+    }
+}
+
+#[test]
+fn defaults_use_the_environment_proxy() {
+    let settings = merge(&NetworkConfig::default(), &[]);
+    assert!(matches!(settings.proxy, ProxySettings::Environment));
+    assert!(settings.ca_certificates.is_empty());
+    assert_eq!(settings.user_agent, None);
+}
+
+#[test]
+fn saved_settings_are_used() {
+    let config = NetworkConfig {
+        proxy: Some("http://proxy.corp:3128".to_owned()),
+        no_proxy: Some("localhost".to_owned()),
+        ca_certificates: vec![PathBuf::from("/etc/corp-ca.pem")],
+        user_agent: Some("saved".to_owned()),
+    };
+
+    let settings = merge(&config, &[]);
+    assert_eq!(
+        proxy_url(&settings),
+        ("http://proxy.corp:3128", Some("localhost"))
+    );
+    assert_eq!(settings.ca_certificates, config.ca_certificates);
+    assert_eq!(settings.user_agent.as_deref(), Some("saved"));
+
+    let config = NetworkConfig {
+        proxy: Some("direct".to_owned()),
+        ..NetworkConfig::default()
+    };
+    assert!(matches!(merge(&config, &[]).proxy, ProxySettings::Direct));
+}
+
+#[test]
+fn environment_overrides_saved_settings() {
+    let config = NetworkConfig {
+        proxy: Some("http://proxy.corp:3128".to_owned()),
+        no_proxy: Some("localhost".to_owned()),
+        ca_certificates: vec![PathBuf::from("/etc/corp-ca.pem")],
+        user_agent: Some("saved".to_owned()),
+    };
+    let ca_certs = std::env::join_paths(["/tmp/a.pem", "/tmp/b.pem"]).unwrap();
+
+    let settings = merge(
+        &config,
+        &[
+            (PROXY_ENV, "socks5://127.0.0.1:1080"),
+            (NO_PROXY_ENV, "example.com"),
+            (CA_CERTS_ENV, ca_certs.to_str().unwrap()),
+            (USER_AGENT_ENV, "env"),
+        ],
+    );
+    assert_eq!(
+        proxy_url(&settings),
+        ("socks5://127.0.0.1:1080", Some("example.com"))
+    );
+    assert_eq!(
+        settings.ca_certificates,
+        [PathBuf::from("/tmp/a.pem"), PathBuf::from("/tmp/b.pem")]
+    );
+    assert_eq!(settings.user_agent.as_deref(), Some("env"));
+
+    // Each variable overrides only its own field.
+    let settings = merge(&config, &[(NO_PROXY_ENV, "example.com")]);
+    assert_eq!(
+        proxy_url(&settings),
+        ("http://proxy.corp:3128", Some("example.com"))
+    );
+    assert_eq!(settings.user_agent.as_deref(), Some("saved"));
+
+    let settings = merge(&config, &[(PROXY_ENV, "direct")]);
+    assert!(matches!(settings.proxy, ProxySettings::Direct));
+}
@@ -0,0 +1,142 @@
+//! Seed data for the fake server.
+use hacker_news_api::{ArticleType, Item, User};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+/// Items, users and article lists served by the fake server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dataset {
+    #[serde(default)]
+    pub items: Vec<Item>,
+    #[serde(default)]
+    pub users: Vec<User>,
+    /// Item ids of each article list in rank order.
+    #[serde(default)]
+    pub lists: HashMap<ArticleType, Vec<u64>>,
+}
+
+impl Dataset {
+    /// Load a dataset from a json file.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let content = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// A small dataset with a story and its comment thread, an ask, a poll
+    /// and a job.
+    pub fn sample() -> Self {
+        const TIME: u64 = 1_700_000_000;
+
+        let item = |id: u64, ty: &str, by: &str| Item {
+            id,
+            kids: Vec::new(),
+            text: None,
+            url: None,
+            title: None,
+            score: 0,
+            time: TIME + id * 60,
+            by: by.to_owned(),
+            dead: false,
+            deleted: false,
+            ty: ty.to_owned(),
+            parent: None,
+            descendants: None,
+            parts: Vec::new(),
+            poll: None,
+        };
+        let comment = |id: u64, parent: u64, by: &str, text: &str| Item {
+            parent: Some(parent),
+            text: Some(text.to_owned()),
+            ..item(id, "comment", by)
+        };
+
+        let items = vec![
+            Item {
+                title: Some("Show HN: A fake Hacker News server".to_owned()),
+                url: Some("https://example.com/fake".to_owned()),
+                score: 120,
+                kids: vec![2, 4],
+                descendants: Some(4),
+                ..item(1, "story", "alice")
+            },
+            Item {
+                kids: vec![3],
+                ..comment(
+                    2,
+                    1,
+                    "bob",
+                    "Nice, does it support <i>server side events</i>?",
+                )
+            },
+            comment(3, 2, "alice", "Yes, on every path."),
+            Item {
+                kids: vec![5],
+                ..comment(4, 1, "carol", "How do I seed it?")
+            },
+            comment(5, 4, "alice", "Load a dataset from a json file."),
+            Item {
+                title: Some("Ask HN: How do you test streaming clients?".to_owned()),
+                text: Some("Recorded fixtures or a fake server?".to_owned()),
+                score: 42,
+                descendants: Some(0),
+                ..item(6, "story", "dave")
+            },
+            Item {
+                title: Some("Poll: Favourite editor".to_owned()),
+                score: 30,
+                parts: vec![8, 9],
+                descendants: Some(0),
+                ..item(7, "poll", "erin")
+            },
+            Item {
+                text: Some("Helix".to_owned()),
+                score: 12,
+                poll: Some(7),
+                ..item(8, "pollopt", "erin")
+            },
+            Item {
+                text: Some("Zed".to_owned()),
+                score: 9,
+                poll: Some(7),
+                ..item(9, "pollopt", "erin")
+            },
+            Item {
+                title: Some("Fake Corp is hiring".to_owned()),
+                url: Some("https://example.com/jobs".to_owned()),
+                score: 1,
+                ..item(10, "job", "fakecorp")
+            },
+        ];
+
+        let users = ["alice", "bob", "carol", "dave", "erin", "fakecorp"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, id)| User {
+                id: id.to_owned(),
+                about: None,
+                created: TIME - 86_400 * 365,
+                karma: 100 * (index as u64 + 1),
+                submitted: items
+                    .iter()
+                    .filter(|item| item.by == id)
+                    .map(|item| item.id)
+                    .collect(),
+            })
+            .collect();
+
+        let lists = HashMap::from([
+            (ArticleType::Top, vec![1, 6, 7, 10]),
+            (ArticleType::Best, vec![1, 7]),
+            (ArticleType::New, vec![10, 7, 6, 1]),
+            (ArticleType::Ask, vec![6]),
+            (ArticleType::Show, vec![1]),
+            (ArticleType::Job, vec![10]),
+        ]);
+
+        Self {
+            items,
+            users,
+            lists,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! A local stand-in for the Hacker News firebase API.
+//!
+//! Serves the article lists, items, users, `maxitem` and `updates` from a
+//! seeded [`Dataset`], as plain json or as server side events. The data can
+//! be changed while clients are connected to exercise watch and reconnect
+//! logic without a network.
+use hacker_news_api::{ArticleType, Item, User};
+use state::State;
+use std::{io, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+mod dataset;
+mod server;
+mod state;
+
+pub use dataset::Dataset;
+
+/// Fake server settings.
+#[derive(Debug, Clone, Copy)]
+pub struct ServerOptions {
+    /// Address to listen on. Port 0 picks a free port.
+    pub address: SocketAddr,
+    /// Interval between keep-alive events on idle event streams.
+    pub keep_alive: Duration,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            address: SocketAddr::from(([127, 0, 0, 1], 0)),
+            keep_alive: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A running fake server. The server stops when dropped.
+pub struct FakeServer {
+    address: SocketAddr,
+    state: Arc<State>,
+    handle: JoinHandle<()>,
+}
+
+impl FakeServer {
+    /// Serve the dataset on a free local port.
+    pub async fn start(dataset: Dataset) -> io::Result<Self> {
+        Self::start_with(dataset, ServerOptions::default()).await
+    }
+
+    /// Serve the dataset with the options.
+    pub async fn start_with(dataset: Dataset, options: ServerOptions) -> io::Result<Self> {
+        let listener = TcpListener::bind(options.address).await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(State::new(dataset));
+        let handle = tokio::spawn(server::serve(listener, state.clone(), options.keep_alive));
+
+        Ok(Self {
+            address,
+            state,
+            handle,
+        })
+    }
+
+    /// Address the server is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Base url for an [`hacker_news_api::ApiClientBuilder`].
+    pub fn base_url(&self) -> String {
+        format!("http://{}/v0", self.address)
+    }
+
+    /// Current state of an item.
+    pub fn item(&self, id: u64) -> Option<Item> {
+        self.state.item(id)
+    }
+
+    /// Add or replace an item.
+    pub fn put_item(&self, item: Item) {
+        self.state.put_item(item);
+    }
+
+    /// Add a comment under `parent`, which may be a story or a comment.
+    /// Returns the new comment id or `None` when the parent doesn't exist.
+    pub fn add_comment(&self, parent: u64, by: &str, text: &str) -> Option<u64> {
+        self.state.add_comment(parent, by, text)
+    }
+
+    /// Set the score of an item. Returns `false` when the item doesn't
+    /// exist.
+    pub fn set_score(&self, id: u64, score: u64) -> bool {
+        self.state.update_item(id, |item| item.score = score)
+    }
+
+    /// Delete an item the way firebase does by clearing the content and
+    /// setting `deleted`. Returns `false` when the item doesn't exist.
+    pub fn delete_item(&self, id: u64) -> bool {
+        self.state.update_item(id, |item| {
+            item.deleted = true;
+            item.by.clear();
+            item.text = None;
+            item.title = None;
+            item.url = None;
+        })
+    }
+
+    /// Mark an item as killed by flags or moderators. Returns `false` when
+    /// the item doesn't exist.
+    pub fn kill_item(&self, id: u64) -> bool {
+        self.state.update_item(id, |item| item.dead = true)
+    }
+
+    /// Replace an article list.
+    pub fn set_list(&self, article_type: ArticleType, ids: Vec<u64>) {
+        self.state.set_list(article_type, ids);
+    }
+
+    /// Add or replace a user.
+    pub fn put_user(&self, user: User) {
+        self.state.put_user(user);
+    }
+
+    /// Close every open event stream so clients have to reconnect.
+    pub fn disconnect_streams(&self) {
+        self.state.disconnect();
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.state.disconnect();
+        self.handle.abort();
+    }
+}
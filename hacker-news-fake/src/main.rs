@@ -0,0 +1,112 @@
+//! Run the fake server and change its data with commands read from stdin.
+use anyhow::Context as _;
+use hacker_news_api::ArticleType;
+use hacker_news_fake::{Dataset, FakeServer, ServerOptions};
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const USAGE: &str = "Usage: hn-fake-server [--port PORT] [DATASET.json]";
+
+const COMMANDS: &str = "\
+Commands:
+  score <id> <score>
+  comment <parent> <by> <text>
+  delete <id>
+  kill <id>
+  list <top|best|new|ask|show|job> <id,id,...>
+  disconnect
+  help";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut options = ServerOptions::default();
+    let mut dataset = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let port = args.next().context(USAGE)?.parse().context(USAGE)?;
+                options.address = SocketAddr::from(([127, 0, 0, 1], port));
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}\n\n{COMMANDS}");
+                return Ok(());
+            }
+            path => dataset = Some(Dataset::load(path)?),
+        }
+    }
+
+    let server = FakeServer::start_with(dataset.unwrap_or_else(Dataset::sample), options).await?;
+    println!("Serving on {}", server.base_url());
+    println!("Set HACKER_NEWS_API_URL={} to use it", server.base_url());
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Err(err) = run_command(&server, &line) {
+            println!("{err}");
+        }
+    }
+    // Keep serving when stdin is closed such as when running detached.
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+fn run_command(server: &FakeServer, line: &str) -> anyhow::Result<()> {
+    let mut words = line.split_whitespace();
+    let Some(command) = words.next() else {
+        return Ok(());
+    };
+    let mut number =
+        || -> anyhow::Result<u64> { Ok(words.next().context("Missing number")?.parse()?) };
+
+    match command {
+        "score" => {
+            let id = number()?;
+            let score = number()?;
+            anyhow::ensure!(server.set_score(id, score), "No item {id}");
+        }
+        "comment" => {
+            let parent = number()?;
+            let by = words.next().context("Missing author")?;
+            let text = words.collect::<Vec<_>>().join(" ");
+            let id = server
+                .add_comment(parent, by, &text)
+                .with_context(|| format!("No item {parent}"))?;
+            println!("Added comment {id}");
+        }
+        "delete" => {
+            let id = number()?;
+            anyhow::ensure!(server.delete_item(id), "No item {id}");
+        }
+        "kill" => {
+            let id = number()?;
+            anyhow::ensure!(server.kill_item(id), "No item {id}");
+        }
+        "list" => {
+            let article_type = match words.next() {
+                Some("top") => ArticleType::Top,
+                Some("best") => ArticleType::Best,
+                Some("new") => ArticleType::New,
+                Some("ask") => ArticleType::Ask,
+                Some("show") => ArticleType::Show,
+                Some("job") => ArticleType::Job,
+                _ => anyhow::bail!("Missing or unknown list"),
+            };
+            let ids = words
+                .next()
+                .unwrap_or_default()
+                .split(',')
+                .filter(|id| !id.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<u64>, _>>()?;
+            server.set_list(article_type, ids);
+        }
+        "disconnect" => server.disconnect_streams(),
+        "help" => println!("{COMMANDS}"),
+        other => anyhow::bail!("Unknown command {other}, try help"),
+    }
+
+    Ok(())
+}
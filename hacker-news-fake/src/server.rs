@@ -0,0 +1,136 @@
+//! Minimal HTTP/1.1 server for the firebase API paths.
+//!
+//! Every response closes the connection. Requests that accept
+//! `text/event-stream` get a `put` event with the current data followed by
+//! a `put` for every change until the stream is disconnected.
+use crate::state::{Change, Resource, State};
+use log::{debug, warn};
+use std::{io, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::broadcast::error::RecvError,
+};
+
+/// Largest accepted request head.
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+
+/// A parsed request.
+struct Request {
+    path: String,
+    event_stream: bool,
+}
+
+/// Accept connections until the listener fails.
+pub(crate) async fn serve(listener: TcpListener, state: Arc<State>, keep_alive: Duration) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, address)) => {
+                debug!("Connection from {address}");
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle(stream, &state, keep_alive).await {
+                        debug!("Connection from {address} failed: {err}");
+                    }
+                });
+            }
+            Err(err) => {
+                warn!("Failed to accept connection: {err}");
+                break;
+            }
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream, state: &State, keep_alive: Duration) -> io::Result<()> {
+    let request = read_request(&mut stream).await?;
+    let Some(resource) = Resource::from_path(&request.path) else {
+        return write_response(&mut stream, "404 Not Found", r#"{"error":"Not Found"}"#).await;
+    };
+
+    if request.event_stream {
+        event_stream(stream, state, resource, keep_alive).await
+    } else {
+        write_response(&mut stream, "200 OK", &state.json(&resource)).await
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_SIZE {
+            return Err(io::Error::other("Request head too large"));
+        }
+        match stream.read(&mut buf).await? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => head.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let path = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| io::Error::other("Invalid request line"))?
+        .to_owned();
+    let event_stream = lines
+        .filter_map(|line| line.split_once(':'))
+        .any(|(name, value)| {
+            name.eq_ignore_ascii_case("accept") && value.contains("text/event-stream")
+        });
+
+    Ok(Request { path, event_stream })
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn event_stream(
+    mut stream: TcpStream,
+    state: &State,
+    resource: Resource,
+    keep_alive: Duration,
+) -> io::Result<()> {
+    // Subscribe before reading the data so no change is missed.
+    let mut changes = state.subscribe();
+
+    stream
+        .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n")
+        .await?;
+    write_put(&mut stream, &state.json(&resource)).await?;
+
+    loop {
+        tokio::select! {
+            change = changes.recv() => match change {
+                Ok(Change::Resource(changed)) if changed == resource => {
+                    write_put(&mut stream, &state.json(&resource)).await?;
+                }
+                Ok(Change::Resource(_)) => (),
+                // Send the current data in case a change was missed.
+                Err(RecvError::Lagged(_)) => write_put(&mut stream, &state.json(&resource)).await?,
+                Ok(Change::Disconnect) | Err(RecvError::Closed) => break,
+            },
+            _ = tokio::time::sleep(keep_alive) => {
+                stream.write_all(b"event: keep-alive\ndata: null\n\n").await?;
+            }
+        }
+    }
+
+    stream.shutdown().await
+}
+
+async fn write_put(stream: &mut TcpStream, data: &str) -> io::Result<()> {
+    let event = format!("event: put\ndata: {{\"path\":\"/\",\"data\":{data}}}\n\n");
+    stream.write_all(event.as_bytes()).await
+}
+
+#[cfg(test)]
+mod server_tests;
@@ -0,0 +1,130 @@
+use crate::{Dataset, FakeServer, state::Resource};
+use hacker_news_api::{ApiClient, ApiError, ArticleType, SubscriptionStatus, SupervisorConfig};
+use std::{sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+async fn start() -> (FakeServer, Arc<ApiClient>) {
+    let server = FakeServer::start(Dataset::sample()).await.unwrap();
+    let client = ApiClient::builder()
+        .base_url(server.base_url())
+        .build()
+        .unwrap();
+    (server, Arc::new(client))
+}
+
+fn fast_reconnect() -> SupervisorConfig {
+    SupervisorConfig {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn resource_paths() {
+    assert_eq!(
+        Resource::from_path("/v0/topstories.json"),
+        Some(Resource::List(ArticleType::Top))
+    );
+    assert_eq!(
+        Resource::from_path("/v0/item/42.json?print=pretty"),
+        Some(Resource::Item(42))
+    );
+    assert_eq!(
+        Resource::from_path("/user/pg.json"),
+        Some(Resource::User("pg".to_owned()))
+    );
+    assert_eq!(Resource::from_path("/v0/item/abc.json"), None);
+    assert_eq!(Resource::from_path("/v0/other.json"), None);
+}
+
+#[tokio::test]
+async fn serves_dataset() {
+    let (_server, client) = start().await;
+
+    let top = client.articles(10, ArticleType::Top).await.unwrap();
+    let ids = top.iter().map(|item| item.id).collect::<Vec<_>>();
+    assert_eq!(ids, [1, 6, 7, 10]);
+
+    assert_eq!(client.user("alice").await.unwrap().submitted, [1, 3, 5]);
+    assert_eq!(client.max_item().await.unwrap(), 10);
+    assert!(matches!(
+        client.item(99).await,
+        Err(ApiError::NotFound { id: Some(99), .. })
+    ));
+}
+
+#[tokio::test]
+async fn scripted_changes() {
+    let (server, client) = start().await;
+
+    let id = server.add_comment(3, "dave", "Late reply").unwrap();
+    assert_eq!(id, 11);
+    assert_eq!(client.item(3).await.unwrap().kids, [11]);
+    assert_eq!(client.item(1).await.unwrap().descendants, Some(5));
+
+    assert!(server.delete_item(5));
+    let deleted = client.item(5).await.unwrap();
+    assert!(deleted.deleted);
+    assert_eq!(deleted.text, None);
+
+    let updates = client.updates().await.unwrap();
+    assert_eq!(&updates.items[..4], [5, 1, 2, 3]);
+    assert_eq!(updates.profiles, ["dave"]);
+}
+
+#[tokio::test]
+async fn streams_changes() {
+    let (server, client) = start().await;
+    let mut subscription = client.subscribe_item(1, SupervisorConfig::default());
+
+    let first = subscription.receiver.recv().await.unwrap();
+    assert_eq!(first.data.score, 120);
+
+    server.set_score(1, 121);
+    let changed = subscription.receiver.recv().await.unwrap();
+    assert_eq!(changed.data.score, 121);
+
+    // Changes to other items are not sent.
+    server.set_score(6, 1);
+    assert!(
+        timeout(Duration::from_millis(50), subscription.receiver.recv())
+            .await
+            .is_err()
+    );
+    subscription.abort();
+}
+
+#[tokio::test]
+async fn reconnects_after_disconnect() {
+    let (server, client) = start().await;
+    let mut subscription = client.subscribe_article_list(ArticleType::New, fast_reconnect());
+
+    assert_eq!(
+        subscription.receiver.recv().await.unwrap().data,
+        [10, 7, 6, 1]
+    );
+
+    server.disconnect_streams();
+    subscription
+        .status
+        .wait_for(|status| matches!(status, SubscriptionStatus::Reconnecting { .. }))
+        .await
+        .unwrap();
+
+    // The reconnected stream starts with the current list.
+    server.set_list(ArticleType::New, vec![6, 1]);
+    let event = timeout(Duration::from_secs(5), async {
+        loop {
+            let event = subscription.receiver.recv().await.unwrap();
+            if event.data == [6, 1] {
+                break event;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(event.data, [6, 1]);
+    assert_eq!(*subscription.status.borrow(), SubscriptionStatus::Live);
+    subscription.abort();
+}
@@ -0,0 +1,243 @@
+//! Mutable server state shared by all connections.
+use crate::Dataset;
+use hacker_news_api::{ArticleType, Item, Updates, User};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::broadcast;
+
+/// Number of recently changed items and profiles kept for `updates.json`.
+const MAX_UPDATES: usize = 100;
+
+/// A resource served on a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Resource {
+    List(ArticleType),
+    Item(u64),
+    User(String),
+    MaxItem,
+    Updates,
+}
+
+impl Resource {
+    /// Resource for a request path such as `/v0/item/1.json`.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.split('?').next()?;
+        let path = path.strip_prefix("/v0").unwrap_or(path);
+        let path = path.trim_start_matches('/').strip_suffix(".json")?;
+
+        Some(match path {
+            "topstories" => Self::List(ArticleType::Top),
+            "beststories" => Self::List(ArticleType::Best),
+            "newstories" => Self::List(ArticleType::New),
+            "askstories" => Self::List(ArticleType::Ask),
+            "showstories" => Self::List(ArticleType::Show),
+            "jobstories" => Self::List(ArticleType::Job),
+            "maxitem" => Self::MaxItem,
+            "updates" => Self::Updates,
+            path => match path.split_once('/')? {
+                ("item", id) => Self::Item(id.parse().ok()?),
+                ("user", id) => Self::User(id.to_owned()),
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// A change to the served data.
+#[derive(Debug, Clone)]
+pub(crate) enum Change {
+    Resource(Resource),
+    /// Close all event streams.
+    Disconnect,
+}
+
+struct Data {
+    items: HashMap<u64, Item>,
+    users: HashMap<String, User>,
+    lists: HashMap<ArticleType, Vec<u64>>,
+    updates: Updates,
+}
+
+impl Data {
+    fn max_item(&self) -> u64 {
+        self.items.keys().max().copied().unwrap_or_default()
+    }
+}
+
+/// Server state.
+pub(crate) struct State {
+    data: Mutex<Data>,
+    changes: broadcast::Sender<Change>,
+}
+
+impl State {
+    pub fn new(dataset: Dataset) -> Self {
+        let Dataset {
+            items,
+            users,
+            lists,
+        } = dataset;
+        Self {
+            data: Mutex::new(Data {
+                items: items.into_iter().map(|item| (item.id, item)).collect(),
+                users: users
+                    .into_iter()
+                    .map(|user| (user.id.clone(), user))
+                    .collect(),
+                lists,
+                updates: Updates::default(),
+            }),
+            changes: broadcast::channel(64).0,
+        }
+    }
+
+    /// Listen to changes.
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.changes.subscribe()
+    }
+
+    /// Json for the resource. Missing items and users are `null` like
+    /// firebase.
+    pub fn json(&self, resource: &Resource) -> String {
+        let data = self.data.lock().unwrap();
+        let value = match resource {
+            Resource::List(article_type) => json!(data.lists.get(article_type)),
+            Resource::Item(id) => json!(data.items.get(id)),
+            Resource::User(id) => json!(data.users.get(id)),
+            Resource::MaxItem => json!(data.max_item()),
+            Resource::Updates => json!(data.updates),
+        };
+        value.to_string()
+    }
+
+    pub fn item(&self, id: u64) -> Option<Item> {
+        self.data.lock().unwrap().items.get(&id).cloned()
+    }
+
+    /// Change an item. Returns `false` when the item doesn't exist.
+    pub fn update_item(&self, id: u64, update: impl FnOnce(&mut Item)) -> bool {
+        let mut data = self.data.lock().unwrap();
+        let Some(item) = data.items.get_mut(&id) else {
+            return false;
+        };
+        update(item);
+        self.item_changed(&mut data, id);
+        true
+    }
+
+    /// Add or replace an item.
+    pub fn put_item(&self, item: Item) {
+        let mut data = self.data.lock().unwrap();
+        let id = item.id;
+        data.items.insert(id, item);
+        self.item_changed(&mut data, id);
+    }
+
+    /// Add a comment as the last kid of `parent` and count it in the
+    /// descendants of the story. Returns the new comment id.
+    pub fn add_comment(&self, parent: u64, by: &str, text: &str) -> Option<u64> {
+        let mut data = self.data.lock().unwrap();
+        if !data.items.contains_key(&parent) {
+            return None;
+        }
+        let id = data.max_item() + 1;
+
+        data.items.insert(
+            id,
+            Item {
+                id,
+                kids: Vec::new(),
+                text: Some(text.to_owned()),
+                url: None,
+                title: None,
+                score: 0,
+                time: now(),
+                by: by.to_owned(),
+                dead: false,
+                deleted: false,
+                ty: "comment".to_owned(),
+                parent: Some(parent),
+                descendants: None,
+                parts: Vec::new(),
+                poll: None,
+            },
+        );
+        self.item_changed(&mut data, id);
+
+        let mut ancestor = Some(parent);
+        while let Some(ancestor_id) = ancestor {
+            let Some(item) = data.items.get_mut(&ancestor_id) else {
+                break;
+            };
+            if ancestor_id == parent {
+                item.kids.push(id);
+            }
+            if let Some(descendants) = item.descendants.as_mut() {
+                *descendants += 1;
+            }
+            ancestor = item.parent;
+            self.item_changed(&mut data, ancestor_id);
+        }
+
+        if let Some(user) = data.users.get_mut(by) {
+            user.submitted.insert(0, id);
+            self.user_changed(&mut data, by.to_owned());
+        }
+
+        Some(id)
+    }
+
+    /// Replace an article list.
+    pub fn set_list(&self, article_type: ArticleType, ids: Vec<u64>) {
+        self.data.lock().unwrap().lists.insert(article_type, ids);
+        self.send(Change::Resource(Resource::List(article_type)));
+    }
+
+    /// Add or replace a user.
+    pub fn put_user(&self, user: User) {
+        let mut data = self.data.lock().unwrap();
+        let id = user.id.clone();
+        data.users.insert(id.clone(), user);
+        self.user_changed(&mut data, id);
+    }
+
+    pub fn disconnect(&self) {
+        self.send(Change::Disconnect);
+    }
+
+    fn item_changed(&self, data: &mut Data, id: u64) {
+        record_update(&mut data.updates.items, id);
+        self.send(Change::Resource(Resource::Item(id)));
+        self.send(Change::Resource(Resource::MaxItem));
+        self.send(Change::Resource(Resource::Updates));
+    }
+
+    fn user_changed(&self, data: &mut Data, id: String) {
+        record_update(&mut data.updates.profiles, id.clone());
+        self.send(Change::Resource(Resource::User(id)));
+        self.send(Change::Resource(Resource::Updates));
+    }
+
+    fn send(&self, change: Change) {
+        // No event streams are open.
+        let _ = self.changes.send(change);
+    }
+}
+
+/// Move `value` to the front of the recent updates.
+fn record_update<T: PartialEq>(updates: &mut Vec<T>, value: T) {
+    updates.retain(|existing| *existing != value);
+    updates.insert(0, value);
+    updates.truncate(MAX_UPDATES);
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
@@ -20,6 +20,8 @@ pub struct ArticleView {
     title: SharedString,
     /// The author of the article, formatted as "by {author}".
     pub author: SharedString,
+    /// The author's user handle.
+    by: SharedString,
     /// The number of comments on the article, if available.
     pub comment_count: Option<SharedString>,
     /// The URL of the article, if available.
@@ -46,6 +48,8 @@ pub struct ArticleView {
     pub id: u64,
     /// Article body.
     pub article_text: Option<Rc<ViewStyledText>>,
+    /// The IDs of the poll options when the article is a poll.
+    pub poll_option_ids: Arc<Vec<u64>>,
 }
 
 impl ArticleView {
@@ -84,6 +88,7 @@ impl ArticleView {
             Self {
                 title: item.title.unwrap_or_default().into(),
                 author: format!("by {}", item.by.clone()).into(),
+                by: item.by.clone().into(),
                 comment_count: item
                     .descendants
                     .filter(|&n| n > 0)
@@ -110,6 +115,7 @@ impl ArticleView {
                     .map(parse_layout)
                     .map(Into::into)
                     .map(Rc::new),
+                poll_option_ids: Arc::new(item.parts),
             }
         });
 
@@ -299,6 +305,8 @@ impl Render for ArticleView {
 
         let url = self.url.clone();
         let article_entity = cx.entity();
+        let content_entity = self.content_entity.clone();
+        let by = self.by.clone();
 
         let load_comments_cb = (self.article_text.is_some() && url.is_none())
             .then(|| self.fetch_comments_call_back(article_entity.clone()));
@@ -339,7 +347,19 @@ impl Render for ArticleView {
                     .p_1()
                     .italic()
                     .text_size(rems(0.75))
-                    .child(self.author.clone())
+                    .child(
+                        div()
+                            .id("author")
+                            .cursor_pointer()
+                            .rounded_md()
+                            .hover(hover_element(theme))
+                            .child(self.author.clone())
+                            .on_click(move |_event, _window, app| {
+                                content_entity.update(app, |_content_view, cx| {
+                                    cx.emit(ContentEvent::OpenProfile(by.clone()))
+                                });
+                            }),
+                    )
                     .child(self.age.clone())
                     .child(comments_col)
                     .gap_x(rems(0.1)),
@@ -2,6 +2,7 @@
 use crate::{
     article::ArticleView,
     common::{COMMENT_IMAGE, comment_entities, hover_element, parse_date},
+    content::ContentEvent,
     rich_text::{ParsedStyledText, TextLayout, parse_layout, rich_text_runs, url_ranges},
     theme::Theme,
 };
@@ -18,8 +19,12 @@ use std::{sync::Arc, time::Duration};
 pub struct CommentView {
     /// The comment text.
     text: SharedString,
-    /// The author of the article, formatted as "by {author}".
+    /// The author of the comment, formatted as "by: {author}".
     author: SharedString,
+    /// The author's user handle.
+    by: SharedString,
+    /// The comment id, formatted as "({id})".
+    id_label: SharedString,
     /// The entities representing the child comments for this comment.
     children: Vec<Entity<CommentView>>,
     /// The ids of child comments.
@@ -51,12 +56,19 @@ impl CommentView {
     /// * `item` - The Hacker News API item representing the comment.
     /// * `article_entity` - The entity representing the parent article view.
     pub fn new(cx: &mut AsyncApp, item: Item, article_entity: Entity<ArticleView>) -> Entity<Self> {
-        let ParsedStyledText { text, layout, urls } =
-            item.text.as_deref().map(parse_layout).unwrap_or_default();
+        let ParsedStyledText { text, layout, urls } = if item.deleted {
+            parse_layout("[deleted]")
+        } else if item.dead {
+            parse_layout("[dead]")
+        } else {
+            item.text.as_deref().map(parse_layout).unwrap_or_default()
+        };
 
         cx.new(|_cx| Self {
             text: text.into(),
-            author: format!("by: {} ({})", item.by, item.id).into(),
+            author: format!("by: {}", item.by).into(),
+            by: item.by.clone().into(),
+            id_label: format!("({})", item.id).into(),
             children: Vec::new(),
             comment_count: format!("{}", item.kids.len()).into(),
             comment_child_ids: Arc::new(item.kids),
@@ -115,6 +127,8 @@ impl CommentView {
         comment_entity: Entity<CommentView>,
     ) -> gpui::Div {
         let id = self.id;
+        let by = self.by.clone();
+        let article_entity = self.article_entity.clone();
 
         gpui::div()
             .flex()
@@ -127,10 +141,24 @@ impl CommentView {
             .text_size(rems(0.75))
             .child(
                 gpui::div()
-                    .id("comment_id")
+                    .id("comment_author")
                     .child(self.author.clone())
                     .cursor_pointer()
                     .rounded_md()
+                    .on_click(move |_event, _window, app| {
+                        let content_entity = article_entity.read(app).content_entity.clone();
+                        content_entity.update(app, |_content_view, cx| {
+                            cx.emit(ContentEvent::OpenProfile(by.clone()))
+                        });
+                    })
+                    .hover(hover_element(theme)),
+            )
+            .child(
+                gpui::div()
+                    .id("comment_id")
+                    .child(self.id_label.clone())
+                    .cursor_pointer()
+                    .rounded_md()
                     .on_click(move |_event, _window, cx| {
                         cx.open_url(&format!("https://news.ycombinator.com/item?id={id}"));
                     })
@@ -5,7 +5,8 @@ use gpui::{
     AppContext, AsyncApp, Entity, Fill, Image, StyleRefinement, Styled as _, http_client::Url,
     solid_background,
 };
-use log::error;
+use hacker_news_api::ItemResult;
+use log::{error, warn};
 use std::{
     borrow::Cow,
     sync::{Arc, LazyLock},
@@ -79,11 +80,16 @@ pub async fn comment_entities(
     });
     let client = app.read_global(|client: &ApiClientState, _| client.0.clone());
     let item_stream = client
-        .items(comment_ids)
+        .item_results(comment_ids)
         .into_stream()
         .filter_map(|comment_result| async move {
+            // Deleted and dead comments are kept so their replies can be reached.
             match comment_result {
-                Ok(comment) => Some(comment),
+                Ok(ItemResult::Missing(id)) => {
+                    warn!("Comment {id} is missing");
+                    None
+                }
+                Ok(comment) => comment.into_item(),
                 Err(err) => {
                     error!("Failed to fetch comment: {err}");
                     None
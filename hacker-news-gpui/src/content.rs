@@ -1,18 +1,18 @@
 //! Main content view
 use crate::{
     ArticleSelection, article::ArticleView, article_body::ArticleBodyView, comment::CommentView,
-    common::comment_entities, scrollbar::Scrollbar,
+    common::comment_entities, poll::PollView, profile::ProfileView, scrollbar::Scrollbar,
 };
 use background::{
-    BackGroundError, restart_background_task, start_background_article_list_subscription,
+    BackgroundEvent, restart_background_task, start_background_article_list_subscription,
     start_background_subscriptions,
 };
 use futures::channel;
 use gpui::{
-    App, AppContext, Entity, EventEmitter, FocusHandle, ListState, Pixels, ScrollHandle, Window,
-    prelude::*, px,
+    App, AppContext, Entity, EventEmitter, FocusHandle, ListState, Pixels, ScrollHandle,
+    SharedString, Window, prelude::*, px,
 };
-use hacker_news_api::{ArticleType, Item};
+use hacker_news_api::{ArticleType, SubscriptionStatus};
 use log::{error, info};
 use std::{collections::HashMap, f32};
 
@@ -25,9 +25,6 @@ pub struct ContentView {
     articles: Vec<Entity<ArticleView>>,
     /// State for scrolling and alignment of the article list.
     list_state: ListState,
-    /// Tracks the ranking of each article so that if it moves up
-    /// or down we can show by how much.
-    article_ranks: HashMap<u64, usize>,
     /// Tracks the number of comments for an article so that when it
     /// changes we can show a visual indicator.
     article_comment_counts: HashMap<u64, u64>,
@@ -36,7 +33,7 @@ pub struct ContentView {
     /// Handle to the background task that updates articles.
     background_task: Option<gpui::Task<()>>,
     /// Sender channel for pushing article updates from background to foreground.
-    article_sender: Option<channel::mpsc::Sender<Result<Vec<Item>, BackGroundError>>>,
+    article_sender: Option<channel::mpsc::Sender<BackgroundEvent>>,
     /// The number of times we have refresh due to an http server side event.
     background_refresh_count: usize,
     /// The entities representing the comments for this article.
@@ -65,8 +62,12 @@ pub struct ContentView {
     comments_scrollbar: Entity<Scrollbar>,
     /// Viewing Article text
     article_body_view: Option<Entity<ArticleBodyView>>,
+    /// Viewing poll options.
+    poll_view: Option<Entity<PollView>>,
     /// Viewing article id.
     pub viewing_article_id: Option<u64>,
+    /// Viewing user profile, shown in place of the comments.
+    profile_view: Option<Entity<ProfileView>>,
 }
 
 /// Events emitted by the ContentView to signal UI updates or errors.
@@ -81,8 +82,12 @@ pub enum ContentEvent {
     Terminated(ArticleType),
     /// Toggle online status
     OnlineToggle(bool),
+    /// Connection state of the article list subscription.
+    ConnectionStatus(SubscriptionStatus),
     /// Open Comments
     OpenComments(Entity<ArticleView>),
+    /// Open the profile of a user handle.
+    OpenProfile(SharedString),
 }
 
 impl EventEmitter<ContentEvent> for ContentView {}
@@ -129,9 +134,9 @@ impl ContentView {
                     let current_category = cx.global::<ArticleSelection>().viewing_article_type;
                     info!("Terminated: {terminated_category} current: {current_category}");
                     if terminated_category == &current_category {
-                        // We need to restart.
-                        info!("Restarting background subscription for {terminated_category}");
-                        restart_background_task(content_view, cx);
+                        // The subscription has given up reconnecting. Go offline so
+                        // it can be restarted with the online toggle.
+                        content_view.online = false;
                     }
                 }
                 ContentEvent::OpenComments(article_entity) => {
@@ -139,6 +144,7 @@ impl ContentView {
                     let id = article_entity.read(cx).id;
                     let article_entity = article_entity.clone();
                     let comment_ids = article_entity.read(cx).comment_ids.clone();
+                    let poll_option_ids = article_entity.read(cx).poll_option_ids.clone();
 
                     // Create the article body view.
                     content_view.article_body_view =
@@ -153,10 +159,13 @@ impl ContentView {
                             })
                         });
 
+                    content_view.poll_view = None;
+                    content_view.profile_view = None;
                     content_view.viewing_article_id = Some(id);
                     cx.notify();
 
                     cx.spawn(async move |content_entity, async_app| {
+                        let poll_view = PollView::fetch(async_app, &poll_option_ids).await;
                         let comment_entities =
                             comment_entities(async_app, article_entity.clone(), &comment_ids).await;
 
@@ -168,6 +177,7 @@ impl ContentView {
 
                             if let Err(err) = content_entity.update(app, |content_view, cx| {
                                 content_view.comment_entities = comment_entities;
+                                content_view.poll_view = poll_view;
                                 content_view.fetching_comments = false;
                                 cx.notify();
                             }) {
@@ -177,9 +187,18 @@ impl ContentView {
                     })
                     .detach();
                 }
+                ContentEvent::OpenProfile(handle) => {
+                    content_view.profile_view = Some(ProfileView::new(
+                        cx,
+                        handle.clone(),
+                        content_view.comments_scroll_handle.clone(),
+                    ));
+                    cx.notify();
+                }
                 ContentEvent::Error(_)
                 | ContentEvent::TotalArticles(_)
-                | ContentEvent::TotalRefreshes(_) => (),
+                | ContentEvent::TotalRefreshes(_)
+                | ContentEvent::ConnectionStatus(_) => (),
             })
             .detach();
 
@@ -225,10 +244,10 @@ impl ContentView {
 
             cx.observe_global::<ArticleSelection>(move |content_view, cx| {
                 let selection = *cx.global::<ArticleSelection>();
-                // Reset ranks when we change selection.
-                content_view.article_ranks.clear();
                 // Remove viewing article body.
                 content_view.article_body_view = None;
+                content_view.poll_view = None;
+                content_view.profile_view = None;
                 match content_view.article_sender.as_ref() {
                     Some(tx) => {
                         info!("Opening stream for {selection:?}");
@@ -251,7 +270,6 @@ impl ContentView {
             Self {
                 list_state,
                 articles: Default::default(),
-                article_ranks: Default::default(),
                 online: false,
                 background_task: None,
                 article_sender: None,
@@ -269,7 +287,9 @@ impl ContentView {
                 articles_scrollbar,
                 comments_scrollbar,
                 article_body_view: None,
+                poll_view: None,
                 viewing_article_id: None,
+                profile_view: None,
             }
         });
 
@@ -2,11 +2,14 @@
 use super::{ContentEvent, ContentView};
 use crate::{ApiClientState, ArticleSelection, article::ArticleView};
 use async_compat::Compat;
-use futures::{SinkExt, StreamExt, TryStreamExt as _, channel};
+use futures::{SinkExt, StreamExt, TryStreamExt as _, channel, stream};
 use gpui::{App, AppContext, Context, Entity};
-use hacker_news_api::{ArticleType, Item, subscribe_to_article_list};
-use log::{error, info};
-use std::collections::HashMap;
+use hacker_news_api::{
+    ArticleType, Item, ListDelta, ListFeed, SubscriptionStatus, SupervisorConfig, status_stream,
+};
+use log::{error, info, warn};
+use std::{collections::HashMap, pin::pin, sync::Arc};
+use tokio::sync::broadcast;
 
 /// Restarts the background task by dropping the current task and replacing it with a new one.
 pub(super) fn restart_background_task(
@@ -23,6 +26,28 @@ pub(super) fn restart_background_task(
     }
 }
 
+/// Messages sent from the background subscription to the foreground.
+pub(super) enum BackgroundEvent {
+    /// Updated articles or an error fetching them.
+    Articles(Result<ArticleUpdate, BackGroundError>),
+    /// The connection state of the article list subscription has changed.
+    Status(SubscriptionStatus),
+}
+
+/// Articles fetched for an article list delta.
+pub(super) struct ArticleUpdate {
+    /// Articles in list order.
+    items: Vec<Item>,
+    /// The list changes that triggered the fetch.
+    delta: Arc<ListDelta>,
+}
+
+/// Updates received from a list feed.
+enum SubscriptionUpdate {
+    Articles(Arc<ListDelta>),
+    Status(SubscriptionStatus),
+}
+
 #[derive(Debug, Clone)]
 /// Background event subscription errors.
 pub(super) enum BackGroundError {
@@ -58,7 +83,7 @@ pub(super) fn start_background_subscriptions(
     entity_content: &Entity<ContentView>,
 ) -> gpui::Task<()> {
     let entity_content = entity_content.clone();
-    let (tx, mut rx) = channel::mpsc::channel::<Result<Vec<Item>, BackGroundError>>(10);
+    let (tx, mut rx) = channel::mpsc::channel::<BackgroundEvent>(10);
 
     // Keep a reference to the send channel so we can restart the background
     // if we lose connection.
@@ -67,19 +92,23 @@ pub(super) fn start_background_subscriptions(
     });
 
     app.spawn(async move |app| {
-        while let Some(items) = rx.next().await {
+        while let Some(event) = rx.next().await {
+            let items = match event {
+                BackgroundEvent::Articles(items) => items,
+                BackgroundEvent::Status(status) => {
+                    app.update_entity(&entity_content, |_content_view, cx| {
+                        cx.emit(ContentEvent::ConnectionStatus(status));
+                        cx.notify();
+                    });
+                    continue;
+                }
+            };
             match items {
-                Ok(items) => {
+                Ok(ArticleUpdate { items, delta }) => {
                     let viewing_id = app.read_entity(&entity_content, |content_view, _cx| {
                         content_view.viewing_article_id
                     });
 
-                    let current_ranking_map = items
-                        .iter()
-                        .enumerate()
-                        .map(|(index, item)| (item.id, index))
-                        .collect::<HashMap<_, _>>();
-
                     let current_comment_counts = items
                         .iter()
                         .map(|item| (item.id, item.descendants.unwrap_or(0)))
@@ -91,14 +120,8 @@ pub(super) fn start_background_subscriptions(
                     // Create an ArticleView for each item.
                     let views = items
                         .into_iter()
-                        .enumerate()
-                        .map(|(index, article)| {
-                            let order_change = app.read_entity(&entity_content, |content, _app| {
-                                match content.article_ranks.get(&article.id) {
-                                    Some(rank) => (*rank as i64) - (index as i64),
-                                    None => 0,
-                                }
-                            });
+                        .map(|article| {
+                            let order_change = delta.rank_change(article.id);
 
                             let last_comment_count =
                                 app.read_entity(&entity_content, |content, _app| {
@@ -141,7 +164,6 @@ pub(super) fn start_background_subscriptions(
 
                         content.articles = views;
                         content.list_state.reset(content.articles.len());
-                        content.article_ranks = current_ranking_map;
                         content.article_comment_counts = current_comment_counts;
                         content.background_refresh_count += 1;
                         cx.emit(ContentEvent::TotalArticles(content.articles.len()));
@@ -191,7 +213,7 @@ pub(super) fn start_background_subscriptions(
 /// Returns a `gpui::Task<()>` representing the spawned background task.
 pub(super) fn start_background_article_list_subscription(
     app: &mut App,
-    mut tx: channel::mpsc::Sender<Result<Vec<Item>, BackGroundError>>,
+    mut tx: channel::mpsc::Sender<BackgroundEvent>,
 ) -> gpui::Task<()> {
     let ArticleSelection {
         viewing_article_type,
@@ -203,24 +225,50 @@ pub(super) fn start_background_article_list_subscription(
     let client = app.read_global(|client: &ApiClientState, _app| client.0.clone());
 
     app.background_executor().spawn(Compat::new(async move {
-        let (mut rx, handle) = subscribe_to_article_list(viewing_article_type);
-
-        while let Some(event) = rx.recv().await {
-            let article_ids = event
-                .data
-                .into_iter()
-                .take(viewing_article_total)
-                .collect::<Vec<_>>();
-
-            let result = client
-                .items(&article_ids)
-                .try_collect::<Vec<_>>()
-                .await
-                .map_err(|err| {
-                    BackGroundError::EventFailed(format!("Failed to fetch updated items: {err}"))
-                });
-
-            if let Err(err) = tx.send(result).await {
+        let feed = ListFeed::start(
+            &client,
+            viewing_article_type,
+            Some(viewing_article_total),
+            SupervisorConfig::default(),
+            8,
+        );
+
+        let articles = stream::unfold(feed.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(delta) => return Some((SubscriptionUpdate::Articles(delta), receiver)),
+                    // The next delta has the whole list.
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Skipped {skipped} article list deltas");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        let mut updates = pin!(stream::select(
+            articles,
+            status_stream(feed.status()).map(SubscriptionUpdate::Status),
+        ));
+
+        while let Some(update) = updates.next().await {
+            let event = match update {
+                SubscriptionUpdate::Articles(delta) => {
+                    let result = client
+                        .items(&delta.ids)
+                        .try_collect::<Vec<_>>()
+                        .await
+                        .map(|items| ArticleUpdate { items, delta })
+                        .map_err(|err| {
+                            BackGroundError::EventFailed(format!(
+                                "Failed to fetch updated items: {err}"
+                            ))
+                        });
+                    BackgroundEvent::Articles(result)
+                }
+                SubscriptionUpdate::Status(status) => BackgroundEvent::Status(status),
+            };
+
+            if let Err(err) = tx.send(event).await {
                 error!("UI foreground send channel is closed: {err}");
                 break;
             }
@@ -229,12 +277,14 @@ pub(super) fn start_background_article_list_subscription(
         log::warn!("Background events have terminated");
 
         if let Err(err) = tx
-            .send(Err(BackGroundError::Terminated(viewing_article_type)))
+            .send(BackgroundEvent::Articles(Err(BackGroundError::Terminated(
+                viewing_article_type,
+            ))))
             .await
         {
             error!("Failed to send error {err}");
         }
 
-        handle.abort();
+        feed.abort();
     }))
 }
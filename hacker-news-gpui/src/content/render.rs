@@ -193,9 +193,10 @@ impl Render for ContentView {
                                 self.render_comments(cx, theme, div)
                             }),
                     )
-                    .when(!self.comment_entities.is_empty(), |div| {
-                        div.child(self.comments_scrollbar.clone())
-                    }),
+                    .when(
+                        !self.comment_entities.is_empty() || self.profile_view.is_some(),
+                        |div| div.child(self.comments_scrollbar.clone()),
+                    ),
             )
     }
 }
@@ -221,9 +222,31 @@ impl ContentView {
         let comment_entities = self.comment_entities.clone();
         let content_entity = cx.entity();
 
+        // A profile is shown over the comments until it is closed.
+        if let Some(profile_view) = self.profile_view.clone() {
+            return el.child(
+                div()
+                    .bg(theme.bg())
+                    .rounded_tl_md()
+                    .pb_2()
+                    .child(close_bar(theme, "close-profile").on_click(
+                        move |_event, _window, app| {
+                            content_entity.update(app, |content_view, cx| {
+                                content_view.profile_view = None;
+                                cx.notify();
+                            });
+                        },
+                    ))
+                    .child(profile_view),
+            );
+        }
+
         // If we don't have either an article body or any comments to show then we have nothing
         // to render.
-        if self.article_body_view.is_none() && self.comment_entities.is_empty() {
+        if self.article_body_view.is_none()
+            && self.poll_view.is_none()
+            && self.comment_entities.is_empty()
+        {
             return el;
         }
 
@@ -233,30 +256,38 @@ impl ContentView {
                 .rounded_tl_md()
                 .pb_2()
                 .child(
-                    div()
-                        .flex()
-                        .flex_grow()
-                        .flex_row()
-                        .text_size(rems(0.75))
-                        .bg(theme.comment_border())
-                        .rounded_tl_md()
-                        .child(div().pl_1().child("[X]"))
-                        .cursor_pointer()
-                        .id("close-comments")
-                        .on_click(move |_event, _window, app| {
-                            // Clear any open comments for another article
-                            content_entity.update(app, |content_view, cx| {
-                                content_view.comment_entities.clear();
-                                content_view.viewing_article_id = None;
-                                content_view.article_body_view = None;
-                                cx.notify();
-                            });
-                        }),
+                    close_bar(theme, "close-comments").on_click(move |_event, _window, app| {
+                        // Clear any open comments for another article
+                        content_entity.update(app, |content_view, cx| {
+                            content_view.comment_entities.clear();
+                            content_view.viewing_article_id = None;
+                            content_view.article_body_view = None;
+                            content_view.poll_view = None;
+                            cx.notify();
+                        });
+                    }),
                 )
                 .when_some(self.article_body_view.as_ref(), |div, view_styled_text| {
                     div.child(view_styled_text.clone())
                 })
+                .when_some(self.poll_view.as_ref(), |div, poll_view| {
+                    div.child(poll_view.clone())
+                })
                 .children(comment_entities.clone()),
         )
     }
 }
+
+/// The bar above the comments column with a close button.
+fn close_bar(theme: Theme, id: &'static str) -> gpui::Stateful<gpui::Div> {
+    div()
+        .flex()
+        .flex_grow()
+        .flex_row()
+        .text_size(rems(0.75))
+        .bg(theme.comment_border())
+        .rounded_tl_md()
+        .child(div().pl_1().child("[X]"))
+        .cursor_pointer()
+        .id(id)
+}
@@ -1,13 +1,19 @@
 //! The footer view.
 use crate::{
-    ArticleSelection, UrlHover,
+    ArticleSelection, SearchContextState, UrlHover,
     content::{ContentEvent, ContentView},
     theme::Theme,
 };
+use async_compat::Compat;
 use chrono::Local;
+use futures::{StreamExt, channel::mpsc};
 use gpui::{
     App, Entity, ParentElement, Render, SharedString, Styled, Window, div, prelude::*, rems, rgb,
 };
+use hacker_news_api::SubscriptionStatus;
+use hacker_news_config::rebuild_options;
+use hacker_news_search::{IndexState, RebuildProgress, rebuild_index};
+use log::{error, info};
 use tooltip::Tooltip;
 
 mod tooltip;
@@ -22,10 +28,16 @@ pub struct FooterView {
     content_entity: Entity<ContentView>,
     /// Whether the stream is online or paused.
     online: bool,
+    /// Connection state of the article list subscription.
+    connection: SubscriptionStatus,
     /// The total number of refreshes resulting from a server side event, as a string.
     total_refreshes: SharedString,
     /// Any error message to display, if present.
     error: Option<SharedString>,
+    /// State of the local index when it was opened, `Ready` once rebuilt.
+    index_state: IndexState,
+    /// Stories indexed and the total while the index is rebuilt.
+    index_progress: Option<(usize, usize)>,
 }
 
 impl FooterView {
@@ -72,7 +84,10 @@ impl FooterView {
                     ContentEvent::Terminated(_) => {
                         footer.online = false;
                     }
-                    ContentEvent::OpenComments(_) => {}
+                    ContentEvent::ConnectionStatus(status) => {
+                        footer.connection = *status;
+                    }
+                    ContentEvent::OpenComments(_) | ContentEvent::OpenProfile(_) => {}
                 },
             )
             .detach();
@@ -83,15 +98,100 @@ impl FooterView {
             })
             .detach();
 
-            Self {
+            let index_state = cx
+                .try_global::<SearchContextState>()
+                .map(|SearchContextState(search_context)| {
+                    search_context.read().unwrap().index_state()
+                })
+                .unwrap_or(IndexState::Ready);
+
+            let mut footer = Self {
                 status_line: Default::default(),
                 url: None,
                 content_entity,
                 online: true,
+                connection: SubscriptionStatus::Connecting,
                 total_refreshes: Default::default(),
                 error: None,
+                index_state,
+                index_progress: None,
+            };
+            if index_state.needs_rebuild() {
+                footer.start_rebuild(cx);
+            }
+            footer
+        })
+    }
+
+    /// Rebuild the index of the viewed category after it was recreated
+    /// empty, showing the progress in the footer.
+    fn start_rebuild(&mut self, cx: &mut gpui::Context<Self>) {
+        let Some(SearchContextState(search_context)) = cx.try_global::<SearchContextState>() else {
+            return;
+        };
+        let search_context = search_context.clone();
+        let category = cx.global::<ArticleSelection>().viewing_article_type;
+        let (progress_tx, mut progress_rx) = mpsc::channel(100);
+
+        cx.spawn(async move |footer_entity, async_app| {
+            let rebuild = async_app
+                .background_executor()
+                .spawn(Compat::new(rebuild_index(
+                    search_context,
+                    category,
+                    rebuild_options(),
+                    progress_tx,
+                )));
+
+            // The progress channel closes once the stories are collected.
+            while let Some(progress) = progress_rx.next().await {
+                if footer_entity
+                    .update(async_app, |footer, cx| {
+                        footer.index_progress = match progress {
+                            RebuildProgress::Started(total) => Some((0, total)),
+                            RebuildProgress::StoryCompleted => footer
+                                .index_progress
+                                .map(|(completed, total)| (completed + 1, total)),
+                            RebuildProgress::Completed => None,
+                        };
+                        cx.notify();
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            let result = rebuild.await;
+
+            if let Err(err) = footer_entity.update(async_app, |footer, cx| {
+                footer.index_progress = None;
+                match result {
+                    Ok(stats) => {
+                        info!("Rebuilt index for {category}: {stats:?}");
+                        footer.index_state = IndexState::Ready;
+                    }
+                    Err(err) => {
+                        error!("Failed to rebuild index: {err}");
+                        footer.error = Some(format!("Failed to rebuild index: {err}").into());
+                    }
+                }
+                cx.notify();
+            }) {
+                error!("Footer view is gone: {err}");
             }
         })
+        .detach();
+    }
+
+    /// State of the local index, or `None` when it's up to date.
+    fn index_status(&self) -> Option<SharedString> {
+        match (self.index_progress, self.index_state) {
+            (Some((completed, total)), _) => {
+                Some(format!("Rebuilding index {completed}/{total}").into())
+            }
+            (None, IndexState::Ready) => None,
+            (None, state) => Some(state.to_string().into()),
+        }
     }
 }
 
@@ -104,6 +204,7 @@ impl Render for FooterView {
         let theme: Theme = window.appearance().into();
         let content_entity = self.content_entity.clone();
         let online = self.online;
+        let connection = self.connection;
 
         div()
             .bg(theme.surface())
@@ -129,6 +230,9 @@ impl Render for FooterView {
                             .flex()
                             .flex_row()
                             .gap_0p5()
+                            .when_some(self.index_status(), |el, status| {
+                                el.child(div().mr_1().child(status))
+                            })
                             .child(
                                 div()
                                     .id("toggle_online")
@@ -147,13 +251,21 @@ impl Render for FooterView {
                                             },
                                         )
                                     })
-                                    .when_else(
-                                        online,
-                                        |el| el.text_color(rgb(0x26a269)).child("[online]"),
-                                        |el| {
+                                    .map(|el| match connection {
+                                        _ if !online => {
                                             el.text_color(rgb(0xcc3300)).italic().child("[offline]")
-                                        },
-                                    ),
+                                        }
+                                        SubscriptionStatus::Live => {
+                                            el.text_color(rgb(0x26a269)).child("[online]")
+                                        }
+                                        SubscriptionStatus::GaveUp => {
+                                            el.text_color(rgb(0xcc3300)).italic().child("[offline]")
+                                        }
+                                        status => el
+                                            .text_color(rgb(0xe5a50a))
+                                            .italic()
+                                            .child(format!("[{status}]")),
+                                    }),
                             )
                             .child(div().mr_1().child(self.total_refreshes.clone())),
                     ),
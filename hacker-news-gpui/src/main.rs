@@ -9,10 +9,14 @@ use gpui::{
 };
 use gpui_platform::application;
 use hacker_news_api::{ApiClient, ArticleType};
-use hacker_news_config::{init_logger, load_config};
+use hacker_news_config::{api_client_builder, init_logger, load_config, search_context};
+use hacker_news_search::{SearchContext, init_api_client};
 use log::{error, info};
 use serde::{Deserialize, Serialize};
-use std::{ops::Deref, sync::Arc};
+use std::{
+    ops::Deref,
+    sync::{Arc, RwLock},
+};
 
 mod article;
 mod article_body;
@@ -21,6 +25,8 @@ mod common;
 mod content;
 mod footer;
 mod header;
+mod poll;
+mod profile;
 mod rich_text;
 mod scrollbar;
 mod theme;
@@ -52,6 +58,12 @@ pub struct ArticleSelection {
 
 impl Global for ArticleSelection {}
 
+/// The local search index shared with the other frontends. Profiles use it
+/// to mark submissions that are indexed.
+pub struct SearchContextState(Arc<RwLock<SearchContext>>);
+
+impl Global for SearchContextState {}
+
 /// Global state of url hover.
 pub struct UrlHover(pub Option<SharedString>);
 
@@ -173,8 +185,15 @@ fn main() {
     };
 
     application().run(move |app| {
-        let client = Arc::new(hacker_news_api::ApiClient::new().expect("No API Client"));
+        let client = init_api_client(api_client_builder().build().expect("No API Client"));
         app.set_global(ApiClientState(client));
+        match search_context() {
+            Ok(search_context) => {
+                info!("{}", search_context.read().unwrap().index_state());
+                app.set_global(SearchContextState(search_context));
+            }
+            Err(err) => error!("Failed to open search index: {err}"),
+        }
         app.set_global(ArticleSelection {
             viewing_article_type: ArticleType::Top,
             viewing_article_total: 50,
@@ -0,0 +1,124 @@
+//! View for poll options.
+use crate::{
+    ApiClientState,
+    rich_text::{ViewStyledText, parse_layout, rich_text_runs},
+    theme::Theme,
+};
+use futures::TryStreamExt as _;
+use gpui::{
+    AppContext as _, AsyncApp, Entity, Render, StyledText, div, prelude::*, px, relative, rems,
+};
+use hacker_news_api::Item;
+use log::error;
+
+/// A poll option with its share of the votes.
+struct PollOptionRow {
+    /// Option text.
+    text: ViewStyledText,
+    /// Votes, formatted as "{score} votes".
+    votes: String,
+    /// Votes relative to the option with the most votes.
+    relative_score: f32,
+}
+
+/// View for the options of a poll.
+pub struct PollView {
+    options: Vec<PollOptionRow>,
+}
+
+impl PollView {
+    /// Fetch the poll options and create the view. Returns `None` when there
+    /// are no options to show.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - The async application context used to create the entity.
+    /// * `option_ids` - The ids of the poll options in display order.
+    pub async fn fetch(app: &mut AsyncApp, option_ids: &[u64]) -> Option<Entity<Self>> {
+        if option_ids.is_empty() {
+            return None;
+        }
+        let client = app.read_global(|client: &ApiClientState, _| client.0.clone());
+        let items = async_compat::Compat::new(client.items(option_ids).try_collect::<Vec<Item>>())
+            .await
+            .inspect_err(|err| error!("Failed to fetch poll options: {err}"))
+            .ok()?;
+
+        let max_score = items
+            .iter()
+            .map(|item| item.score)
+            .max()
+            .unwrap_or_default();
+        let options = items
+            .into_iter()
+            .map(|item| PollOptionRow {
+                text: item
+                    .text
+                    .as_deref()
+                    .map(parse_layout)
+                    .unwrap_or_default()
+                    .into(),
+                votes: format!("{} votes", item.score),
+                relative_score: if max_score == 0 {
+                    0.
+                } else {
+                    item.score as f32 / max_score as f32
+                },
+            })
+            .collect();
+
+        Some(app.new(|_cx| Self { options }))
+    }
+}
+
+impl Render for PollView {
+    fn render(
+        &mut self,
+        window: &mut gpui::Window,
+        _cx: &mut gpui::Context<Self>,
+    ) -> impl gpui::IntoElement {
+        let theme: Theme = window.appearance().into();
+
+        div()
+            .mb_2()
+            .p_1()
+            .flex()
+            .flex_col()
+            .gap_y(px(5.0))
+            .bg(theme.article_text())
+            .border_1()
+            .border_color(theme.border())
+            .shadow_md()
+            .children(self.options.iter().map(|option| {
+                div()
+                    .flex()
+                    .flex_col()
+                    .child(
+                        StyledText::new(option.text.text.clone())
+                            .with_runs(rich_text_runs(theme, &option.text.layout).collect()),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_x(px(5.0))
+                            .child(
+                                div().w(relative(0.5)).child(
+                                    div()
+                                        .h(px(8.0))
+                                        .rounded_sm()
+                                        .bg(theme.button_active())
+                                        .w(relative(option.relative_score)),
+                                ),
+                            )
+                            .child(
+                                div()
+                                    .italic()
+                                    .text_size(rems(0.75))
+                                    .child(option.votes.clone()),
+                            ),
+                    )
+            }))
+    }
+}
@@ -0,0 +1,294 @@
+//! View for a user profile.
+use crate::{
+    SearchContextState,
+    common::{hover_element, parse_date},
+    rich_text::{ViewStyledText, parse_layout, rich_text_runs, url_ranges},
+    theme::Theme,
+};
+use gpui::{
+    App, AppContext as _, Context, Entity, InteractiveText, Render, ScrollHandle, SharedString,
+    StyledText, Window, div, point, prelude::*, px, rems,
+};
+use hacker_news_search::api::{PROFILE_PAGE_SIZE, UserProfile, user_profile};
+use log::error;
+use std::rc::Rc;
+
+/// A story or comment the user submitted.
+struct SubmissionRow {
+    id: u64,
+    /// Story title or comment text.
+    text: ViewStyledText,
+    /// Item type and age, formatted as "{type} {age}".
+    label: SharedString,
+    /// The item is in the local index.
+    indexed: bool,
+}
+
+/// View for a user's karma, about text and submissions.
+pub struct ProfileView {
+    /// User handle.
+    handle: SharedString,
+    /// Fetched profile with the viewing page of submissions.
+    profile: Option<UserProfile>,
+    /// Rendered about text.
+    about: Option<Rc<ViewStyledText>>,
+    /// Submissions on the viewing page.
+    submissions: Vec<SubmissionRow>,
+    /// A page is being fetched.
+    loading: bool,
+    /// Last fetch error.
+    error: Option<SharedString>,
+    /// Scroll handle of the column the profile is shown in.
+    scroll_handle: ScrollHandle,
+}
+
+impl ProfileView {
+    /// Create a profile view and fetch the first page of submissions.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - The application context used to create the entity.
+    /// * `handle` - The user handle.
+    /// * `scroll_handle` - Scroll handle reset when a page is shown.
+    pub fn new(app: &mut App, handle: SharedString, scroll_handle: ScrollHandle) -> Entity<Self> {
+        app.new(|cx| {
+            let mut view = Self {
+                handle,
+                profile: None,
+                about: None,
+                submissions: Vec::new(),
+                loading: false,
+                error: None,
+                scroll_handle,
+            };
+            view.fetch(0, cx);
+            view
+        })
+    }
+
+    /// Fetch the page of submissions at `offset`.
+    fn fetch(&mut self, offset: usize, cx: &mut Context<Self>) {
+        let Some(SearchContextState(search_context)) = cx.try_global::<SearchContextState>() else {
+            self.error = Some("Local index is not available".into());
+            return;
+        };
+        let search_context = search_context.clone();
+        let handle = self.handle.to_string();
+        self.loading = true;
+        cx.notify();
+
+        cx.spawn(async move |profile_entity, async_app| {
+            let result =
+                async_compat::Compat::new(user_profile(search_context, handle, offset)).await;
+
+            if let Err(err) = profile_entity.update(async_app, |profile_view, cx| {
+                profile_view.loading = false;
+                match result {
+                    Ok(profile) => profile_view.loaded(profile),
+                    Err(err) => {
+                        error!("Failed to fetch profile: {err}");
+                        profile_view.error = Some(err.to_string().into());
+                    }
+                }
+                cx.notify();
+            }) {
+                error!("Profile view is gone: {err}");
+            }
+        })
+        .detach();
+    }
+
+    /// Show a fetched page.
+    fn loaded(&mut self, profile: UserProfile) {
+        self.about = profile
+            .user
+            .about
+            .as_deref()
+            .filter(|about| !about.is_empty())
+            .map(parse_layout)
+            .map(Into::into)
+            .map(Rc::new);
+        self.submissions = profile
+            .submissions
+            .items
+            .iter()
+            .map(|item| SubmissionRow {
+                id: item.id,
+                text: match &item.title {
+                    Some(title) => parse_layout(title),
+                    None => item.text.as_deref().map(parse_layout).unwrap_or_default(),
+                }
+                .into(),
+                label: format!("{} {}", item.ty, parse_date(item.time).unwrap_or_default()).into(),
+                indexed: profile.is_indexed(item.id),
+            })
+            .collect();
+        self.profile = Some(profile);
+        self.error = None;
+        self.scroll_handle.set_offset(point(px(0.0), px(0.0)));
+    }
+
+    /// Renders the karma, account age and about text.
+    fn render_summary(&self, theme: Theme, profile: &UserProfile) -> gpui::Div {
+        div()
+            .flex()
+            .flex_col()
+            .gap_y(px(5.0))
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_x(px(10.0))
+                    .child(
+                        div()
+                            .font_weight(gpui::FontWeight::BOLD)
+                            .child(self.handle.clone()),
+                    )
+                    .child(format!("{} karma", profile.user.karma))
+                    .child(div().italic().child(format!(
+                        "joined {}",
+                        parse_date(profile.user.created).unwrap_or_default()
+                    )))
+                    .child(format!("{} submissions", profile.submissions.total)),
+            )
+            .when_some(self.about.clone(), |el, about| {
+                el.child(
+                    div()
+                        .p_1()
+                        .bg(theme.article_text())
+                        .border_1()
+                        .border_color(theme.border())
+                        .shadow_md()
+                        .child(
+                            InteractiveText::new(
+                                "profile_about",
+                                StyledText::new(about.text.clone())
+                                    .with_runs(rich_text_runs(theme, &about.layout).collect()),
+                            )
+                            .on_click(
+                                url_ranges(&about.layout),
+                                move |index, _window, app| {
+                                    if let Some(url) = about.urls.get(index) {
+                                        app.open_url(url);
+                                    }
+                                },
+                            ),
+                        ),
+                )
+            })
+    }
+
+    /// Renders a submission. Clicking the id opens the item on the website.
+    fn render_submission(&self, theme: Theme, row: &SubmissionRow) -> gpui::Div {
+        let id = row.id;
+
+        div()
+            .bg(theme.surface())
+            .border_1()
+            .border_color(theme.border())
+            .rounded_md()
+            .shadow_md()
+            .child(
+                div().p_1().child(
+                    StyledText::new(row.text.text.clone())
+                        .with_runs(rich_text_runs(theme, &row.text.layout).collect()),
+                ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_x(px(5.0))
+                    .p_1()
+                    .border_t_1()
+                    .border_color(theme.border())
+                    .italic()
+                    .text_size(rems(0.75))
+                    .child(row.label.clone())
+                    .when(row.indexed, |el| el.child("indexed"))
+                    .child(
+                        div()
+                            .id(("submission", id))
+                            .cursor_pointer()
+                            .rounded_md()
+                            .hover(hover_element(theme))
+                            .child(format!("({id})"))
+                            .on_click(move |_event, _window, app| {
+                                app.open_url(&format!("https://news.ycombinator.com/item?id={id}"));
+                            }),
+                    ),
+            )
+    }
+
+    /// Renders the previous and next page buttons. Prolific users have
+    /// thousands of submissions so pages aren't listed.
+    fn render_pagination(
+        &self,
+        theme: Theme,
+        profile: &UserProfile,
+        cx: &mut Context<Self>,
+    ) -> gpui::Div {
+        let previous = profile
+            .submissions
+            .previous_offset(PROFILE_PAGE_SIZE)
+            .filter(|_| !self.loading);
+        let next = profile.submissions.next_offset().filter(|_| !self.loading);
+
+        let page_button = |id: &'static str, label: &'static str, offset: Option<usize>| {
+            div()
+                .id(id)
+                .px_1()
+                .rounded_md()
+                .child(label)
+                .map(|el| match offset {
+                    Some(offset) => {
+                        el.cursor_pointer()
+                            .hover(hover_element(theme))
+                            .on_click(cx.listener(move |profile_view, _event, _window, cx| {
+                                profile_view.fetch(offset, cx);
+                            }))
+                    }
+                    None => el.opacity(0.5),
+                })
+        };
+
+        div()
+            .flex()
+            .flex_row()
+            .justify_center()
+            .gap_x(px(10.0))
+            .child(page_button("profile-previous", "←", previous))
+            .child(format!("{} of {}", profile.page(), profile.page_count()))
+            .child(page_button("profile-next", "→", next))
+    }
+}
+
+impl Render for ProfileView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme: Theme = window.appearance().into();
+
+        let Some(profile) = &self.profile else {
+            return div()
+                .p_1()
+                .child(self.handle.clone())
+                .child(match &self.error {
+                    Some(err) => err.clone(),
+                    None => "Loading...".into(),
+                });
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_y(px(5.0))
+            .p_1()
+            .child(self.render_summary(theme, profile))
+            .children(
+                self.submissions
+                    .iter()
+                    .map(|row| self.render_submission(theme, row)),
+            )
+            .child(self.render_pagination(theme, profile, cx))
+            .when_some(self.error.clone(), |el, err| el.child(err))
+    }
+}
@@ -9,12 +9,13 @@ use crate::{
     full_search::{FullSearchMsg, FullSearchState, SearchCriteria},
     header::{self, HeaderMsg, HeaderState},
     nav_history::{Content, History, HistoryElement},
+    profile::{ProfileMsg, ProfileState},
 };
 use hacker_news_api::ArticleType;
 use hacker_news_config::IndexConfig;
 use hacker_news_search::{
     SearchContext,
-    api::{Comment, Story},
+    api::{Comment, PollOption, Story},
 };
 use iced::{
     // clipboard,
@@ -60,6 +61,8 @@ pub struct App {
     pub history: Vec<HistoryElement>,
     /// Main content.
     pub content: Content,
+    /// Show the body of dead comments.
+    pub show_dead: bool,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -103,7 +106,10 @@ pub enum AppMsg {
     PaneResized(pane_grid::ResizeEvent),
     CommentsClosed,
     ClearVisited,
+    ToggleShowDead,
     FullSearch(FullSearchMsg),
+    OpenProfile(String),
+    Profile(ProfileMsg),
     SaveConfig,
     SwitchIndex {
         category: ArticleType,
@@ -149,11 +155,13 @@ pub fn update(app: &mut App, message: AppMsg) -> Task<AppMsg> {
                 _ => true,
             };
 
+            let poll_options = poll_options(&app.search_context, &article);
             let last_content = mem::replace(
                 &mut app.content,
                 Content::Comment(Box::new(CommentState {
                     search_context: app.search_context.clone(),
                     article,
+                    poll_options,
                     comments,
                     nav_stack,
                     search: None,
@@ -241,7 +249,7 @@ pub fn update(app: &mut App, message: AppMsg) -> Task<AppMsg> {
                 save_task(app),
             ])
         }
-        AppMsg::Articles(msg) => app.article_state.update(msg),
+        AppMsg::Articles(msg) => app.article_state.update(msg, app.header.rebuild_options),
         AppMsg::Comments(msg) => match &mut app.content {
             Content::Comment(comment_state) => comment_state.update(msg),
             _ => Task::none(),
@@ -256,6 +264,7 @@ pub fn update(app: &mut App, message: AppMsg) -> Task<AppMsg> {
             let scroll_id = widget::Id::new(match &app.content {
                 Content::Comment(_) => "comments",
                 Content::Search(_) => "full_search",
+                Content::Profile(_) => "profile",
                 Content::Empty(_) => "articles",
             });
             match scroll_by {
@@ -291,6 +300,10 @@ pub fn update(app: &mut App, message: AppMsg) -> Task<AppMsg> {
             app.article_state.visited.clear();
             save_task(app)
         }
+        AppMsg::ToggleShowDead => {
+            app.show_dead = !app.show_dead;
+            save_task(app)
+        }
         AppMsg::FullSearch(msg) => match &mut app.content {
             // We are switching to the search content from comments.
             // We'll create the initial search state here.
@@ -363,6 +376,32 @@ pub fn update(app: &mut App, message: AppMsg) -> Task<AppMsg> {
             }
             _ => Task::none(),
         },
+        AppMsg::OpenProfile(handle) => {
+            // Opening the profile that is already showing.
+            if matches!(&app.content, Content::Profile(profile) if profile.handle == handle) {
+                return Task::none();
+            }
+            let should_add_history = match &app.content {
+                Content::Empty(_) => app.history.is_empty(),
+                _ => true,
+            };
+            let last_content = mem::replace(
+                &mut app.content,
+                Content::Profile(Box::new(ProfileState::new(
+                    app.search_context.clone(),
+                    handle,
+                ))),
+            );
+            if should_add_history {
+                app.history.push(last_content.into_history_element());
+            }
+            app.article_state.viewing_item = None;
+            Task::done(ProfileMsg::Fetch { offset: 0 }).map(AppMsg::Profile)
+        }
+        AppMsg::Profile(msg) => match &mut app.content {
+            Content::Profile(profile_state) => profile_state.update(msg),
+            _ => Task::none(),
+        },
         AppMsg::SaveConfig => save_task(app),
         // AppMsg::Clipboard(s) => clipboard::write(s),
         AppMsg::SwitchIndex { category, count } => {
@@ -372,14 +411,11 @@ pub fn update(app: &mut App, message: AppMsg) -> Task<AppMsg> {
             if update_history {
                 app.history.push(last_content.into_history_element());
             }
-            let mut g = app.search_context.write().unwrap();
-            match g.activate_index(category) {
-                Ok(_) => Task::batch([
-                    Task::done(FooterMsg::CurrentIndex(category)).map(AppMsg::Footer),
-                    Task::done(ArticleMsg::TopStories(count)).map(AppMsg::Articles),
-                ]),
-                Err(err) => error_task(err),
-            }
+            app.search_context.write().unwrap().activate_index(category);
+            Task::batch([
+                Task::done(FooterMsg::CurrentIndex(category)).map(AppMsg::Footer),
+                Task::done(ArticleMsg::TopStories(count)).map(AppMsg::Articles),
+            ])
             // .chain(Task::batch([
             //     // Task::done(AppMsg::CloseSearch),
             //     // Task::done(AppMsg::CommentsClosed),
@@ -447,7 +483,7 @@ pub fn view(app: &App) -> iced::Element<'_, AppMsg> {
                         .iter()
                         .find(|story| story.id == *id)
                 }),
-                Content::Empty(_) => None,
+                Content::Profile(_) | Content::Empty(_) => None,
             }?;
 
             let title_text = widget::text(&story.title)
@@ -475,8 +511,9 @@ pub fn view(app: &App) -> iced::Element<'_, AppMsg> {
         pane_grid::Content::new(match state {
             PaneState::Articles => app.article_state.view(&app.theme),
             PaneState::Content => match &app.content {
-                Content::Comment(comment_state) => comment_state.view(),
-                Content::Search(full_search_state) => full_search_state.view(),
+                Content::Comment(comment_state) => comment_state.view(app.show_dead),
+                Content::Search(full_search_state) => full_search_state.view(app.show_dead),
+                Content::Profile(profile_state) => profile_state.view(),
                 Content::Empty(_) => widget::text("").into(),
             },
         })
@@ -570,6 +607,33 @@ pub fn view(app: &App) -> iced::Element<'_, AppMsg> {
                         .spacing(5),
                 )))
                 .always_show_controls(),
+                // Profile of a user.
+                Content::Profile(profile_state) => pane_grid::TitleBar::new(
+                    widget::container(widget::text(&profile_state.handle).font(ROBOTO_FONT.bold()))
+                        .padding(5),
+                )
+                .controls(pane_grid::Controls::new(
+                    widget::Row::new()
+                        .push(common::tooltip(
+                            widget::button(widget::text("⟲").shaping(Shaping::Advanced))
+                                .on_press_maybe(
+                                    (!profile_state.loading).then_some(AppMsg::Profile(
+                                        ProfileMsg::Fetch {
+                                            offset: profile_state
+                                                .profile
+                                                .as_ref()
+                                                .map(|profile| profile.submissions.offset)
+                                                .unwrap_or_default(),
+                                        },
+                                    )),
+                                ),
+                            "Refresh",
+                            widget::tooltip::Position::Bottom,
+                        ))
+                        .push(widget::button("X").on_press(AppMsg::Back))
+                        .spacing(5),
+                ))
+                .always_show_controls(),
                 Content::Empty(_) => pane_grid::TitleBar::new(""),
             },
         })
@@ -594,6 +658,8 @@ impl From<&App> for Config {
                 viewing_count: state.header.article_count,
                 viewing_type: state.header.article_type,
                 index_stats: state.footer.index_stats.values().cloned().collect(),
+                show_dead: state.show_dead,
+                rebuild_options: state.header.rebuild_options,
             },
             gui_config: GuiConfig {
                 visited: visited.clone(),
@@ -605,6 +671,21 @@ impl From<&App> for Config {
     }
 }
 
+/// Lookup the poll options when the article is a poll.
+fn poll_options(search_context: &RwLock<SearchContext>, article: &Story) -> Vec<PollOption> {
+    if article.ty != "poll" {
+        return Vec::new();
+    }
+    search_context
+        .read()
+        .unwrap()
+        .poll_options(article.id)
+        .unwrap_or_else(|err| {
+            error!("Failed to lookup poll options for {}: {err}", article.id);
+            Vec::new()
+        })
+}
+
 /// Save the current application state into a persistent configuration.
 pub fn save_task(app: &App) -> Task<AppMsg> {
     let config = Config::from(app);
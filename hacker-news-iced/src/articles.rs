@@ -5,11 +5,13 @@ use crate::{
     common::{self, FontExt as _, error_task, tooltip},
     footer::FooterMsg,
     full_search::FullSearchMsg,
-    header::HeaderMsg,
     parse_date,
     richtext::SearchSpanIter,
 };
-use hacker_news_search::{SearchContext, WatchState, api::Story, update_story, watch_story};
+use hacker_news_api::{SubscriptionStatus, status_stream};
+use hacker_news_search::{
+    RebuildOptions, SearchContext, WatchState, api::Story, update_story, watch_story,
+};
 use iced::{
     Background, Color, Element, Length, Shadow, Task, Theme,
     advanced::image::Handle,
@@ -30,13 +32,17 @@ use tokio::task::AbortHandle;
 /// Abort handles and task handle for a watched story.
 pub struct WatchHandles {
     ui_receiver: iced::task::Handle,
+    status_receiver: iced::task::Handle,
     abort_handles: [AbortHandle; 2],
+    /// Connection state of the story subscription.
+    status: SubscriptionStatus,
 }
 
 impl WatchHandles {
     /// Call abort on all handles.
     fn abort(self) {
         self.ui_receiver.abort();
+        self.status_receiver.abort();
         self.abort_handles.into_iter().for_each(|h| h.abort());
     }
 }
@@ -117,7 +123,7 @@ pub enum ArticleMsg {
     RemoveWatches,
     OpenNew { story_id: u64, beyond: u64 },
     ClearIndexStory(u64),
-    CheckHandles,
+    WatchStatus(u64, SubscriptionStatus),
     ToggleWatchFilter,
     StoryClicked(Story),
 }
@@ -147,7 +153,7 @@ impl ArticleState {
 
     fn render_article_title<'a>(&'a self, story: &'a Story) -> iced::Element<'a, AppMsg> {
         let title = widget::rich_text(
-            SearchSpanIter::new(&story.title, self.search.as_deref())
+            SearchSpanIter::new(&story.title, story.title_highlight.as_ref())
                 .map(|span| span.link(story.clone()))
                 .collect::<Vec<_>>(),
         )
@@ -176,7 +182,7 @@ impl ArticleState {
                 .size(10)
                 .color_maybe(widget::text::primary(theme).color),
         ])
-        .on_link_click(|by| AppMsg::Header(HeaderMsg::Search(format!("by:{by}"))));
+        .on_link_click(AppMsg::OpenProfile);
 
         let article_id = story.id;
 
@@ -278,7 +284,10 @@ impl ArticleState {
                                                     ArticleMsg::UnWatchStory(story.id)
                                                 })
                                             }),
-                                        "Watch",
+                                        match self.watch_handles.get(&story.id) {
+                                            Some(handle) => format!("Watch ({})", handle.status),
+                                            None => "Watch".to_string(),
+                                        },
                                         widget::tooltip::Position::FollowCursor,
                                     )
                                 }))
@@ -369,8 +378,9 @@ impl ArticleState {
         .into()
     }
 
-    /// Update the state of the top level story list view
-    pub fn update(&mut self, message: ArticleMsg) -> Task<AppMsg> {
+    /// Update the state of the top level story list view. Stories are
+    /// re-indexed with the `rebuild_options` from the header.
+    pub fn update(&mut self, message: ArticleMsg, rebuild_options: RebuildOptions) -> Task<AppMsg> {
         match message {
             ArticleMsg::Receive(articles) => {
                 log::debug!("Received {} articles", articles.len());
@@ -389,7 +399,13 @@ impl ArticleState {
                 } else {
                     self.search = Some(input.clone());
                     let g = self.search_context.read().unwrap();
-                    match g.search_stories(&input, self.article_limit, 0) {
+                    match g.search_stories(
+                        &input,
+                        Some(g.active_category()),
+                        self.article_limit,
+                        0,
+                        true,
+                    ) {
                         Ok(stories) => {
                             self.articles = stories;
                             Task::none()
@@ -400,7 +416,8 @@ impl ArticleState {
             }
             ArticleMsg::TopStories(limit) => {
                 self.article_limit = limit;
-                match self.search_context.read().unwrap().top_stories(limit, 0) {
+                let g = self.search_context.read().unwrap();
+                match g.top_stories(g.active_category(), limit, 0) {
                     Ok(stories) => Task::done(AppMsg::Articles(ArticleMsg::Receive(stories))),
                     Err(err) => error_task(err),
                 }
@@ -416,19 +433,20 @@ impl ArticleState {
                 if let Some(handle) = self.watch_handles.remove(&story.id) {
                     handle.abort();
                 }
-                Task::future(update_story(self.search_context.clone(), story)).then(move |result| {
-                    match result {
-                        Ok(Some(story)) => {
-                            Task::done(ArticleMsg::StoryUpdated(story)).map(AppMsg::Articles)
-                        }
-                        Ok(None) => clear_index_story_task(story_id),
-                        Err(err) => {
-                            Task::batch([error_task(err), clear_index_story_task(story_id)])
-                        }
+                Task::future(update_story(
+                    self.search_context.clone(),
+                    story,
+                    rebuild_options,
+                ))
+                .then(move |result| match result {
+                    Ok(Some(story)) => {
+                        Task::done(ArticleMsg::StoryUpdated(story)).map(AppMsg::Articles)
                     }
+                    Ok(None) => clear_index_story_task(story_id),
+                    Err(err) => Task::batch([error_task(err), clear_index_story_task(story_id)]),
                 })
             }
-            ArticleMsg::WatchStory(story) => self.watch_story(story),
+            ArticleMsg::WatchStory(story) => self.watch_story(story, rebuild_options),
             ArticleMsg::StoryUpdated(story) => {
                 let story_id = story.id;
 
@@ -488,42 +506,19 @@ impl ArticleState {
                 self.indexing_stories.retain(|id| id != &story_id);
                 Task::none()
             }
-            ArticleMsg::CheckHandles => {
-                let aborted_watchers = self
-                    .watch_handles
-                    .iter()
-                    .filter_map(|(story_id, watch_handle)| {
-                        watch_handle
-                            .abort_handles
-                            .iter()
-                            .any(|h| h.is_finished() || watch_handle.ui_receiver.is_aborted())
-                            .then_some(*story_id)
-                    })
-                    .collect::<Vec<_>>();
-
-                let mut re_connect_tasks = Vec::new();
-
-                for story_id in aborted_watchers {
-                    if let Some(watch_handler) = self.watch_handles.remove(&story_id) {
-                        info!("Reconnecting watch handler for {story_id}");
-                        watch_handler.abort();
-
-                        let story = self.search_context.read().unwrap().story(story_id);
-
-                        match story {
-                            Ok(story) => {
-                                re_connect_tasks.push(self.watch_story(story));
-                            }
-                            Err(err) => re_connect_tasks.push(error_task(err)),
-                        }
+            ArticleMsg::WatchStatus(story_id, status) => {
+                if status == SubscriptionStatus::GaveUp {
+                    info!("Watch subscription for {story_id} gave up");
+                    if let Some(handle) = self.watch_handles.remove(&story_id) {
+                        handle.abort();
                     }
+                    self.watch_changes.remove(&story_id);
+                    return error_task(format!("Lost connection watching story {story_id}"));
                 }
-
-                if re_connect_tasks.is_empty() {
-                    Task::none()
-                } else {
-                    Task::batch(re_connect_tasks)
+                if let Some(handle) = self.watch_handles.get_mut(&story_id) {
+                    handle.status = status;
                 }
+                Task::none()
             }
             ArticleMsg::ToggleWatchFilter => {
                 self.filter_watching = !self.filter_watching;
@@ -551,7 +546,7 @@ impl ArticleState {
 
     /// Turn on a watch on story. This subscribes to updates that originate from server
     /// side events. Each update event will send an `ArticleMsg::StoryUpdated` message.
-    fn watch_story(&mut self, story: Story) -> Task<AppMsg> {
+    fn watch_story(&mut self, story: Story, rebuild_options: RebuildOptions) -> Task<AppMsg> {
         let story_id = story.id;
         let last_comment_age = self
             .search_context
@@ -566,22 +561,31 @@ impl ArticleState {
                 beyond: last_comment_age.unwrap_or_default(),
             },
         );
-        match watch_story(self.search_context.clone(), story) {
+        match watch_story(self.search_context.clone(), story, rebuild_options) {
             Ok(WatchState {
                 receiver,
                 abort_handles,
+                status,
             }) => {
                 let (task, handle) = Task::run(receiver, ArticleMsg::StoryUpdated)
                     .map(AppMsg::Articles)
                     .abortable();
+                let (status_task, status_handle) =
+                    Task::run(status_stream(status.clone()), move |status| {
+                        ArticleMsg::WatchStatus(story_id, status)
+                    })
+                    .map(AppMsg::Articles)
+                    .abortable();
                 self.watch_handles.insert(
                     story_id,
                     WatchHandles {
                         ui_receiver: handle,
+                        status_receiver: status_handle,
                         abort_handles,
+                        status: *status.borrow(),
                     },
                 );
-                task
+                Task::batch([task, status_task])
             }
             Err(err) => error_task(err),
         }
@@ -5,13 +5,12 @@ use crate::{
     articles::ArticleMsg,
     common::{self, FontExt as _, PaginatingView, error_task},
     full_search::FullSearchMsg,
-    header::HeaderMsg,
     parse_date,
     richtext::render_rich_text,
 };
 use hacker_news_search::{
     SearchContext,
-    api::{Comment, Story},
+    api::{Comment, PollOption, Story},
 };
 use iced::{
     Border, Color, Element, Length, Shadow, Task, border, padding,
@@ -51,6 +50,8 @@ pub struct CommentState {
     pub search_context: Arc<RwLock<SearchContext>>,
     /// Article this comment belongs to
     pub article: Story,
+    /// Options when the article is a poll.
+    pub poll_options: Vec<PollOption>,
     /// parent comments.
     pub nav_stack: Vec<NavStack>,
     /// Children
@@ -94,16 +95,22 @@ pub enum CommentMsg {
 
 impl CommentState {
     /// Render the comments
-    pub fn view(&self) -> Element<'_, AppMsg> {
+    pub fn view(&self, show_dead: bool) -> Element<'_, AppMsg> {
         let article_text = self
             .article
             .body
             .as_deref()
             .map(|text| {
-                widget::rich_text(render_rich_text(text, self.search.as_deref(), false))
-                    .on_link_click(|url| AppMsg::OpenLink { url })
+                widget::rich_text(render_rich_text(
+                    text,
+                    self.article.body_highlight.as_ref(),
+                    false,
+                ))
+                .on_link_click(|url| AppMsg::OpenLink { url })
             })
-            .map(|rt| container(rt).padding([10, 10]).into());
+            .map(|rt| container(rt).padding([10, 10]).into())
+            .into_iter()
+            .chain((!self.poll_options.is_empty()).then(|| self.render_poll_options()));
 
         let total_parents = self
             .nav_stack
@@ -129,23 +136,25 @@ impl CommentState {
                         widget::container("").width(Length::Fixed(20.)).into()
                     }
                 }))
-                .push(self.render_comment(parent, true).style(|theme| {
-                    let palette = theme.extended_palette();
-
-                    container::Style {
-                        border: Border {
-                            color: palette.secondary.weak.color,
-                            width: 1.,
-                            radius: 8.into(),
-                        },
-                        shadow: Shadow {
-                            color: Color::BLACK,
-                            offset: iced::Vector { x: 2., y: 2. },
-                            blur_radius: 5.,
-                        },
-                        ..Default::default()
-                    }
-                }))
+                .push(self.render_comment(parent, true, show_dead).style(
+                    |theme| {
+                        let palette = theme.extended_palette();
+
+                        container::Style {
+                            border: Border {
+                                color: palette.secondary.weak.color,
+                                width: 1.,
+                                radius: 8.into(),
+                            },
+                            shadow: Shadow {
+                                color: Color::BLACK,
+                                offset: iced::Vector { x: 2., y: 2. },
+                                blur_radius: 5.,
+                            },
+                            ..Default::default()
+                        }
+                    },
+                ))
             })
             .map(Element::from)
             .collect::<Vec<_>>();
@@ -154,7 +163,7 @@ impl CommentState {
             .comments
             .iter()
             .map(|item| {
-                let comment_area = self.render_comment(item, false).style(|theme| {
+                let comment_area = self.render_comment(item, false, show_dead).style(|theme| {
                     let palette = theme.extended_palette();
                     container::Style {
                         background: Some(if self.active_comment_id == Some(item.id) {
@@ -213,6 +222,11 @@ impl CommentState {
                             .label("oneline")
                             .on_toggle(|_| AppMsg::Comments(CommentMsg::Oneline)),
                     )
+                    .push(
+                        widget::toggler(show_dead)
+                            .label("show dead")
+                            .on_toggle(|_| AppMsg::ToggleShowDead),
+                    )
                     .push(common::tooltip(
                         widget::button(widget::text("⌛").shaping(Shaping::Advanced)).on_press(
                             AppMsg::FullSearch(FullSearchMsg::StoryByTime {
@@ -254,11 +268,40 @@ impl CommentState {
         container(content.width(Length::Fill)).into()
     }
 
+    /// Render the poll options with their votes relative to the leading
+    /// option.
+    fn render_poll_options(&self) -> Element<'_, AppMsg> {
+        Column::with_children(self.poll_options.iter().map(|option| {
+            widget::Column::new()
+                .push(
+                    widget::rich_text(render_rich_text(&option.text, None, false))
+                        .on_link_click(|url| AppMsg::OpenLink { url }),
+                )
+                .push(
+                    widget::row![
+                        widget::progress_bar(0_f32..=1., option.relative_score(&self.poll_options))
+                            .girth(10),
+                        widget::text!("{} votes", option.score)
+                            .font(ROBOTO_FONT.italic())
+                            .size(14),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                )
+                .spacing(5)
+                .into()
+        }))
+        .spacing(10)
+        .padding([0, 10])
+        .into()
+    }
+
     /// Render a single comment
     fn render_comment<'a>(
         &'a self,
         comment: &'a Comment,
         is_parent: bool,
+        show_dead: bool,
     ) -> Container<'a, AppMsg> {
         let child_comments_button: Element<'_, AppMsg> = if comment.kids.is_empty() {
             widget::text("").into()
@@ -303,8 +346,8 @@ impl CommentState {
                         }))
                         .push(
                             widget::rich_text(render_rich_text(
-                                &comment.body,
-                                self.search.as_deref(),
+                                comment.display_body(show_dead),
+                                comment.body_highlight.as_ref(),
                                 self.oneline,
                             ))
                             .on_link_click(|url| AppMsg::OpenLink { url }),
@@ -313,7 +356,7 @@ impl CommentState {
                             widget::row![
                                 widget::rich_text([
                                     widget::span(format!("by {}", comment.by))
-                                        .link(format!("by:{}", comment.by.as_str()))
+                                        .link(comment.by.clone())
                                         .font(ROBOTO_FONT.italic())
                                         .size(14),
                                     widget::span(" "),
@@ -321,7 +364,7 @@ impl CommentState {
                                         .font(ROBOTO_FONT.italic().weight_light())
                                         .size(10),
                                 ])
-                                .on_link_click(|by| AppMsg::Header(HeaderMsg::Search(by))),
+                                .on_link_click(AppMsg::OpenProfile),
                                 child_comments_button,
                                 widget::container(common::tooltip(
                                     widget::button(widget::text!("{}", comment.id))
@@ -449,7 +492,7 @@ impl CommentState {
 
                     self.search = Some(search.clone());
                     let g = self.search_context.read().unwrap();
-                    match g.search_comments(&search, self.article.id, 10, self.offset) {
+                    match g.search_comments(&search, self.article.id, 10, self.offset, true) {
                         Ok((comments, count)) => {
                             self.comments = comments;
                             self.full_count = count;
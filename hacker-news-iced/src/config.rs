@@ -73,7 +73,8 @@ impl Config {
             scale: config.gui_config.scale,
             header: HeaderState::new(search_context.clone())
                 .article_count(config.index_config.viewing_count)
-                .article_type(config.index_config.viewing_type),
+                .article_type(config.index_config.viewing_type)
+                .rebuild_options(config.index_config.rebuild_options),
             footer: FooterState {
                 scale: config.gui_config.scale,
                 viewing_index: config.index_config.viewing_type,
@@ -96,6 +97,7 @@ impl Config {
             focused_pane: None,
             content: Content::Empty(config.index_config.viewing_type),
             history: Vec::new(),
+            show_dead: config.index_config.show_dead,
         }
     }
 }
@@ -3,14 +3,14 @@ use crate::{ROBOTO_FONT, app::AppMsg, common::FontExt as _};
 use chrono::{DateTime, Local, Utc};
 use chrono_tz::America::New_York;
 use hacker_news_api::ArticleType;
-use hacker_news_search::{IndexStats, RebuildProgress};
+use hacker_news_search::{IndexState, IndexStats, RebuildProgress, api_client};
 use iced::{
     Background, Color, Element, Length, Task, Theme,
     alignment::Vertical,
     padding,
     widget::{Column, Row, container, pick_list, progress_bar, stack, text},
 };
-use log::error;
+use log::{error, info};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy)]
@@ -41,6 +41,7 @@ pub enum FooterMsg {
     },
     CurrentIndex(ArticleType),
     IndexProgress(RebuildProgress),
+    IndexState(IndexState),
 }
 
 impl FooterState {
@@ -116,6 +117,7 @@ impl FooterState {
                     .push(
                         container(
                             Row::new()
+                                .push(text(api_client().metrics().to_string()).font(light_font()))
                                 .push(
                                     (self.scale != 1.0).then(|| {
                                         text!("Scale: {:.2}", self.scale).font(light_font())
@@ -189,6 +191,10 @@ impl FooterState {
                 }
                 RebuildProgress::Completed => self.index_progress = None,
             },
+            FooterMsg::IndexState(state) => {
+                info!("{state}");
+                self.status_line = state.to_string();
+            }
         }
         Task::none()
     }
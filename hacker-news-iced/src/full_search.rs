@@ -4,7 +4,6 @@ use crate::{
     app::AppMsg,
     comments::CommentMsg,
     common::{self, FontExt as _, PaginatingView, error_task},
-    header::HeaderMsg,
     parse_date,
     richtext::render_rich_text,
 };
@@ -60,12 +59,12 @@ pub enum FullSearchMsg {
 }
 
 impl FullSearchState {
-    pub fn view(&self) -> iced::Element<'_, AppMsg> {
+    pub fn view(&self, show_dead: bool) -> iced::Element<'_, AppMsg> {
         let comment_rows = self
             .search_results
             .iter()
             .map(|comment| {
-                self.render_comment(comment).style(|theme| {
+                self.render_comment(comment, show_dead).style(|theme| {
                     let palette = theme.extended_palette();
 
                     widget::container::Style {
@@ -97,7 +96,11 @@ impl FullSearchState {
         widget::container(content).into()
     }
 
-    fn render_comment<'a>(&'a self, comment: &'a Comment) -> widget::Container<'a, AppMsg> {
+    fn render_comment<'a>(
+        &'a self,
+        comment: &'a Comment,
+        show_dead: bool,
+    ) -> widget::Container<'a, AppMsg> {
         let child_comments_button: Element<'_, AppMsg> = if comment.kids.is_empty() {
             widget::text("").into()
         } else {
@@ -141,24 +144,23 @@ impl FullSearchState {
                             .align_right(Length::Fill),
                         ),
                 )
-                .push({
-                    let s = match &self.search {
-                        SearchCriteria::Query(s) => Some(s.as_str()),
-                        SearchCriteria::StoryId { .. } => None,
-                    };
-
+                .push(
                     widget::container(
-                        widget::rich_text(render_rich_text(&comment.body, s, false))
-                            .on_link_click(|url| AppMsg::OpenLink { url }),
+                        widget::rich_text(render_rich_text(
+                            comment.display_body(show_dead),
+                            comment.body_highlight.as_ref(),
+                            false,
+                        ))
+                        .on_link_click(|url| AppMsg::OpenLink { url }),
                     )
-                    .width(Length::FillPortion(6).enclose(Length::Fixed(50.)))
-                })
+                    .width(Length::FillPortion(6).enclose(Length::Fixed(50.))),
+                )
                 .push(
                     widget::Row::new()
                         .push(
                             widget::rich_text([
                                 widget::span(format!("by {}", comment.by))
-                                    .link(format!("by:{}", comment.by))
+                                    .link(comment.by.clone())
                                     .font(ROBOTO_FONT.italic())
                                     .size(14),
                                 widget::span(" "),
@@ -166,7 +168,7 @@ impl FullSearchState {
                                     .font(ROBOTO_FONT.weight_light().italic())
                                     .size(10),
                             ])
-                            .on_link_click(|by| AppMsg::Header(HeaderMsg::Search(by))),
+                            .on_link_click(AppMsg::OpenProfile),
                         )
                         .push(child_comments_button)
                         .spacing(5),
@@ -194,7 +196,7 @@ impl FullSearchState {
 
                     self.search = SearchCriteria::Query(search.clone());
                     let g = self.search_context.read().unwrap();
-                    match g.search_all_comments(&search, 10, self.offset) {
+                    match g.search_all_comments(&search, 10, self.offset, true) {
                         Ok((comments, count)) => {
                             self.search_results = comments;
                             self.full_count = count;
@@ -7,7 +7,9 @@ use crate::{
     full_search::FullSearchMsg,
 };
 use hacker_news_api::ArticleType;
-use hacker_news_search::{IndexStats, RebuildProgress, SearchContext, rebuild_index};
+use hacker_news_search::{
+    IndexStats, RebuildOptions, RebuildProgress, RebuildSetting, SearchContext, refresh_index,
+};
 use iced::{
     Background, Element, Length, Task,
     futures::channel::mpsc,
@@ -24,6 +26,10 @@ pub struct HeaderState {
     pub article_type: ArticleType,
     pub building_index: bool,
     pub full_search: Option<String>,
+    /// Limits used when rebuilding the index.
+    pub rebuild_options: RebuildOptions,
+    /// The rebuild options are being edited.
+    pub show_rebuild_options: bool,
 }
 
 impl HeaderState {
@@ -35,6 +41,8 @@ impl HeaderState {
             article_type: ArticleType::Top,
             building_index: false,
             full_search: None,
+            rebuild_options: hacker_news_config::rebuild_options(),
+            show_rebuild_options: false,
         }
     }
 
@@ -49,6 +57,12 @@ impl HeaderState {
         self.article_type = article_type;
         self
     }
+
+    /// Set rebuild options.
+    pub fn rebuild_options(mut self, rebuild_options: RebuildOptions) -> Self {
+        self.rebuild_options = rebuild_options;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -68,6 +82,8 @@ pub enum HeaderMsg {
     ClearSearch,
     // Forward,
     Back,
+    ToggleRebuildOptions,
+    RebuildSetting(RebuildSetting, String),
 }
 
 impl HeaderState {
@@ -190,6 +206,20 @@ impl HeaderState {
                             format!("Update {} articles", self.article_type.as_str()),
                             widget::tooltip::Position::Bottom,
                         ))
+                        .push(tooltip(
+                            widget::button(widget::text("⚙").shaping(text::Shaping::Advanced))
+                                .on_press(HeaderMsg::ToggleRebuildOptions)
+                                .style(move |theme, status| {
+                                    if self.show_rebuild_options {
+                                        button::primary(theme, status)
+                                    } else {
+                                        button::secondary(theme, status)
+                                    }
+                                })
+                                .padding(5),
+                            "Update options",
+                            widget::tooltip::Position::Bottom,
+                        ))
                         .push(tooltip(
                             widget::button(widget::text("↻").shaping(text::Shaping::Advanced))
                                 .on_press(HeaderMsg::ClearVisisted)
@@ -217,7 +247,44 @@ impl HeaderState {
             }
         });
 
-        Column::new().push(top_row).into()
+        Column::new()
+            .push(top_row)
+            .push(
+                self.show_rebuild_options
+                    .then(|| self.rebuild_options_row()),
+            )
+            .into()
+    }
+
+    /// Inputs for the options used when rebuilding the index. Empty depth
+    /// and comment limits index the whole comment tree.
+    fn rebuild_options_row(&self) -> Element<'_, HeaderMsg> {
+        let inputs = RebuildSetting::ALL.into_iter().map(|setting| {
+            widget::Row::new()
+                .push(text(setting.label()).size(14))
+                .push(
+                    widget::text_input("all", &setting.value(&self.rebuild_options))
+                        .on_input(move |value| HeaderMsg::RebuildSetting(setting, value))
+                        .width(Length::Fixed(60.))
+                        .padding(3),
+                )
+                .spacing(5)
+                .align_y(iced::Alignment::Center)
+                .into()
+        });
+
+        container(widget::Row::with_children(inputs).spacing(15).wrap())
+            .padding(5)
+            .width(Length::Fill)
+            .style(|theme| {
+                let palette = theme.extended_palette();
+
+                container::Style {
+                    background: Some(Background::Color(palette.background.weak.color)),
+                    ..Default::default()
+                }
+            })
+            .into()
     }
 
     fn header_type_button(
@@ -273,7 +340,7 @@ impl HeaderState {
                 let category = self.article_type;
 
                 let (tx, rx) = mpsc::channel::<RebuildProgress>(100);
-                let fut = rebuild_index(s, category, tx);
+                let fut = refresh_index(s, category, self.rebuild_options, tx);
 
                 Task::batch([
                     Task::future(fut).then(move |result| match result {
@@ -317,6 +384,17 @@ impl HeaderState {
                 Task::done(AppMsg::FullSearch(FullSearchMsg::CloseSearch))
             }
             HeaderMsg::Back => Task::done(AppMsg::Back),
+            HeaderMsg::ToggleRebuildOptions => {
+                self.show_rebuild_options = !self.show_rebuild_options;
+                Task::none()
+            }
+            HeaderMsg::RebuildSetting(setting, value) => {
+                if setting.set(&mut self.rebuild_options, &value) {
+                    Task::done(AppMsg::SaveConfig)
+                } else {
+                    Task::none()
+                }
+            }
         }
     }
 }
@@ -1,20 +1,17 @@
 use anyhow::Context;
 use app::{App, AppMsg, PaneState, ScrollBy, update, view};
-use articles::{ArticleMsg, ArticleState};
+use articles::ArticleState;
 use chrono::{DateTime, Utc};
-use footer::FooterState;
+use footer::{FooterMsg, FooterState};
 use hacker_news_api::ArticleType;
-#[cfg(target_family = "unix")]
-use hacker_news_config::limits::check_nofiles_limit;
-use hacker_news_config::{init_logger, search_context};
-use hacker_news_search::{SearchContext, api_client};
+use hacker_news_config::{api_client_builder, init_item_source, init_logger, search_context};
+use hacker_news_search::{IndexState, SearchContext, init_api_client};
 use header::{HeaderMsg, HeaderState};
 use iced::{
     Font, Size, Subscription, Task, Theme,
     advanced::graphics::core::window,
     event::listen_with,
     keyboard::{Key, Modifiers, key::Named},
-    time::every,
     widget::pane_grid::{self, Configuration},
     window::{close_requests, resize_events},
 };
@@ -24,7 +21,6 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     sync::{Arc, RwLock},
-    time::Duration,
 };
 
 use crate::config::load_config;
@@ -42,6 +38,7 @@ mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
 mod nav_history;
+mod profile;
 mod richtext;
 #[cfg(feature = "trace")]
 mod tracing;
@@ -50,13 +47,11 @@ const ROBOTO_FONT: Font = Font::with_name("Roboto");
 const ROBOTO_MONO: Font = Font::with_name("Roboto Mono");
 
 fn start() -> anyhow::Result<()> {
-    let _ = api_client();
+    init_api_client(api_client_builder().build()?);
+    init_item_source()?;
 
     init_logger("hacker-news-reader")?;
 
-    #[cfg(target_family = "unix")]
-    check_nofiles_limit();
-
     let app = create_app(search_context()?);
     let window_size = app.size;
     // TODO: Bit of a hack to get around Fn trait bound.
@@ -66,6 +61,7 @@ fn start() -> anyhow::Result<()> {
         let app = app.borrow_mut().take().expect("No app");
         let article_type = app.header.article_type;
         let article_count = app.header.article_count;
+        let index_state = app.search_context.read().unwrap().index_state();
         (
             app,
             Task::batch([
@@ -73,7 +69,17 @@ fn start() -> anyhow::Result<()> {
                     article_type,
                     article_count,
                 })
+                .chain(if index_state.needs_rebuild() {
+                    Task::done(HeaderMsg::RebuildIndex)
+                } else {
+                    Task::none()
+                })
                 .map(AppMsg::Header),
+                if index_state == IndexState::Ready {
+                    Task::none()
+                } else {
+                    Task::done(FooterMsg::IndexState(index_state)).map(AppMsg::Footer)
+                },
                 iced::widget::operation::focus(iced::widget::Id::new("article_search")),
             ]),
         )
@@ -81,14 +87,7 @@ fn start() -> anyhow::Result<()> {
 
     iced::application(boot, update, view)
         .theme(|app: &App| app.theme.clone())
-        .subscription(|app| {
-            // If we are watching any stories then check periodically on the subscriptions handles.
-            let story_handle_watcher = if !app.article_state.watch_handles.is_empty() {
-                every(Duration::from_secs(5)).map(|_| AppMsg::Articles(ArticleMsg::CheckHandles))
-            } else {
-                Subscription::none()
-            };
-
+        .subscription(|_app| {
             Subscription::batch([
                 listen_with(|event, _, _| {
                     if let iced::event::Event::Keyboard(iced::keyboard::Event::KeyReleased {
@@ -103,7 +102,6 @@ fn start() -> anyhow::Result<()> {
                 }),
                 close_requests().map(|_event| AppMsg::WindowClose),
                 resize_events().map(|(_id, size)| AppMsg::WindowResize(size)),
-                story_handle_watcher,
                 #[cfg(target_os = "linux")]
                 Subscription::run(linux::listen_to_system_changes),
             ])
@@ -150,13 +148,7 @@ fn create_app(search_context: Arc<RwLock<SearchContext>>) -> App {
                 scale: 1.,
                 #[cfg(target_os = "linux")]
                 scale: linux::initial_font_scale(),
-                header: HeaderState {
-                    search_context: search_context.clone(),
-                    article_count: 75,
-                    article_type: ArticleType::Top,
-                    building_index: false,
-                    full_search: None,
-                },
+                header: HeaderState::new(search_context.clone()).article_count(75),
                 footer: FooterState {
                     status_line: String::new(),
                     last_update: None,
@@ -179,6 +171,7 @@ fn create_app(search_context: Arc<RwLock<SearchContext>>) -> App {
                 focused_pane: None,
                 content: Content::Empty(ArticleType::Top),
                 history: Vec::new(),
+                show_dead: false,
             }
         });
     #[cfg(target_os = "linux")]
@@ -2,10 +2,14 @@
 use crate::{
     comments::{CommentState, NavStack},
     full_search::{FullSearchState, SearchCriteria},
+    profile::ProfileState,
 };
 use anyhow::Context;
 use hacker_news_api::ArticleType;
-use hacker_news_search::{SearchContext, api::CommentStack};
+use hacker_news_search::{
+    SearchContext,
+    api::{CommentStack, UserProfile},
+};
 use std::{
     fmt::Display,
     sync::{Arc, RwLock},
@@ -17,6 +21,8 @@ pub enum Content {
     Comment(Box<CommentState>),
     /// Comment search
     Search(FullSearchState),
+    /// User profile
+    Profile(Box<ProfileState>),
     /// Empty
     Empty(ArticleType),
 }
@@ -27,6 +33,7 @@ impl Content {
         match self {
             Content::Comment(comment_state) => comment_state.to_history().into(),
             Content::Search(full_search_state) => full_search_state.to_history().into(),
+            Content::Profile(profile_state) => profile_state.to_history().into(),
             Content::Empty(index) => HistoryElement::Empty(index),
         }
     }
@@ -39,7 +46,7 @@ impl Content {
                 SearchCriteria::Query(_) => None,
                 SearchCriteria::StoryId { story_id, .. } => Some(*story_id),
             },
-            Content::Empty(_) => None,
+            Content::Profile(_) | Content::Empty(_) => None,
         }
     }
 
@@ -60,6 +67,7 @@ impl Display for Content {
         match self {
             Content::Comment(_) => f.write_str("Comments"),
             Content::Search(_) => f.write_str("Search"),
+            Content::Profile(_) => f.write_str("Profile"),
             Content::Empty(index) => write!(f, "Empty for {index}"),
         }
     }
@@ -88,6 +96,8 @@ pub enum HistoryElement {
     Comment(CommentHistory),
     /// History for the search state
     Search(SearchHistory),
+    /// History for the profile state
+    Profile(ProfileHistory),
     /// History for no state
     Empty(ArticleType),
 }
@@ -109,8 +119,13 @@ impl HistoryElement {
                     FullSearchState::from_history(search_context, search_history)?;
                 (index, Content::Search(search_state))
             }
+            HistoryElement::Profile(profile_history) => {
+                let (index, profile_state) =
+                    ProfileState::from_history(search_context, profile_history)?;
+                (index, Content::Profile(Box::new(profile_state)))
+            }
             HistoryElement::Empty(index) => {
-                search_context.write().unwrap().activate_index(index)?;
+                search_context.write().unwrap().activate_index(index);
                 (index, Content::Empty(index))
             }
         })
@@ -129,6 +144,12 @@ impl From<SearchHistory> for HistoryElement {
     }
 }
 
+impl From<ProfileHistory> for HistoryElement {
+    fn from(history: ProfileHistory) -> Self {
+        Self::Profile(history)
+    }
+}
+
 /// History for the comment state.
 pub struct CommentHistory {
     story_id: u64,
@@ -152,7 +173,7 @@ impl History for CommentState {
         let mut sc = ctx.write().unwrap();
         if sc.active_category() != item.category {
             log::debug!("Switching active index to {}", item.category);
-            sc.activate_index(item.category)?;
+            sc.activate_index(item.category);
         }
         let (mut comments, total_comments) = sc
             .comments(item.parent_id, 10, item.offset)
@@ -186,12 +207,18 @@ impl History for CommentState {
         };
 
         let article = sc.story(item.story_id)?;
+        let poll_options = if article.ty == "poll" {
+            sc.poll_options(article.id)?
+        } else {
+            Vec::new()
+        };
 
         Ok((
             item.category,
             Self {
                 search_context,
                 article,
+                poll_options,
                 nav_stack,
                 comments,
                 search: item.search,
@@ -238,10 +265,10 @@ impl History for FullSearchState {
         let mut sc = ctx.write().unwrap();
         if sc.active_category() != item.category {
             log::debug!("Switching active index to {}", item.category);
-            sc.activate_index(item.category)?;
+            sc.activate_index(item.category);
         }
         let (search_results, full_count) = match &item.search {
-            SearchCriteria::Query(s) => sc.search_all_comments(s, 10, item.offset)?,
+            SearchCriteria::Query(s) => sc.search_all_comments(s, 10, item.offset, true)?,
             SearchCriteria::StoryId { story_id, beyond } => {
                 sc.story_comments_by_date(*story_id, *beyond, 10, item.offset)?
             }
@@ -268,3 +295,46 @@ impl History for FullSearchState {
         }
     }
 }
+
+/// History for the profile state. The fetched page is kept so going back
+/// doesn't fetch it again.
+pub struct ProfileHistory {
+    handle: String,
+    profile: Option<UserProfile>,
+    category: ArticleType,
+}
+
+impl History for ProfileState {
+    type HistoryItem = ProfileHistory;
+
+    fn from_history(
+        search_context: Arc<RwLock<SearchContext>>,
+        item: Self::HistoryItem,
+    ) -> anyhow::Result<(ArticleType, Self)> {
+        {
+            let mut sc = search_context.write().unwrap();
+            if sc.active_category() != item.category {
+                log::debug!("Switching active index to {}", item.category);
+                sc.activate_index(item.category);
+            }
+        }
+
+        Ok((
+            item.category,
+            Self {
+                search_context,
+                handle: item.handle,
+                profile: item.profile,
+                loading: false,
+            },
+        ))
+    }
+
+    fn to_history(self) -> Self::HistoryItem {
+        Self::HistoryItem {
+            category: self.search_context.read().unwrap().active_category(),
+            handle: self.handle,
+            profile: self.profile,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! State and view for a user profile.
+use crate::{
+    ROBOTO_FONT,
+    app::AppMsg,
+    common::{self, FontExt as _, error_task},
+    header::HeaderMsg,
+    parse_date,
+    richtext::render_rich_text,
+};
+use hacker_news_api::Item;
+use hacker_news_search::{
+    SearchContext,
+    api::{AgeLabel as _, PROFILE_PAGE_SIZE, UserProfile, user_profile},
+};
+use iced::{
+    Color, Element, Length, Shadow, Task, border, padding,
+    widget::{self, Column, text::Shaping, tooltip::Position},
+};
+use std::sync::{Arc, RwLock};
+
+/// Profile state
+pub struct ProfileState {
+    pub search_context: Arc<RwLock<SearchContext>>,
+    /// User handle
+    pub handle: String,
+    /// Fetched profile with the viewing page of submissions.
+    pub profile: Option<UserProfile>,
+    /// A page is being fetched.
+    pub loading: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ProfileMsg {
+    Fetch { offset: usize },
+    Loaded(Box<UserProfile>),
+    Failed(String),
+    Forward,
+    Back,
+    OpenItem(u64),
+}
+
+impl ProfileState {
+    /// Create a new profile state for a user handle.
+    pub fn new(search_context: Arc<RwLock<SearchContext>>, handle: String) -> Self {
+        Self {
+            search_context,
+            handle,
+            profile: None,
+            loading: false,
+        }
+    }
+
+    /// Render the profile
+    pub fn view(&self) -> Element<'_, AppMsg> {
+        let Some(profile) = &self.profile else {
+            return widget::container(widget::text(if self.loading { "Loading..." } else { "" }))
+                .center(Length::Fill)
+                .into();
+        };
+
+        let summary = widget::Row::new()
+            .push(widget::text!("{} karma", profile.user.karma).font(ROBOTO_FONT.bold()))
+            .push(
+                widget::text!("joined {}", profile.age_label().unwrap_or_default())
+                    .font(ROBOTO_FONT.italic()),
+            )
+            .push(widget::text!("{} submissions", profile.submissions.total))
+            .push(
+                widget::container(common::tooltip(
+                    widget::button(widget::text("🔍").shaping(Shaping::Advanced))
+                        .style(widget::button::text)
+                        .padding(0)
+                        .on_press(AppMsg::Header(HeaderMsg::Search(format!(
+                            "by:{}",
+                            self.handle
+                        )))),
+                    "Search indexed comments",
+                    Position::Left,
+                ))
+                .align_right(Length::Fill),
+            )
+            .spacing(15)
+            .align_y(iced::Alignment::Center);
+
+        let about = profile.user.about.as_deref().map(|about| {
+            widget::rich_text(render_rich_text(about, None, false))
+                .on_link_click(|url| AppMsg::OpenLink { url })
+        });
+
+        let submissions = profile.submissions.items.iter().map(|item| {
+            self.render_item(profile, item)
+                .style(|theme| {
+                    let palette = theme.extended_palette();
+                    widget::container::Style {
+                        background: Some(palette.background.weak.color.into()),
+                        border: border::rounded(8),
+                        shadow: Shadow {
+                            color: Color::BLACK,
+                            offset: iced::Vector { x: 2., y: 2. },
+                            blur_radius: 5.,
+                        },
+                        ..Default::default()
+                    }
+                })
+                .into()
+        });
+
+        let content = widget::Column::new()
+            .push(
+                widget::scrollable(
+                    widget::Column::new()
+                        .push(summary)
+                        .push(about)
+                        .push(Column::with_children(submissions).spacing(15))
+                        .spacing(15)
+                        .padding(padding::top(0).bottom(10).left(10).right(25)),
+                )
+                .id(profile_scroll_id())
+                .height(Length::Fill),
+            )
+            .push(self.pagination_element(profile))
+            .padding(padding::top(5));
+
+        widget::container(content.width(Length::Fill)).into()
+    }
+
+    /// Render a story or comment the user submitted.
+    fn render_item<'a>(
+        &'a self,
+        profile: &'a UserProfile,
+        item: &'a Item,
+    ) -> widget::Container<'a, AppMsg> {
+        let indexed = profile.is_indexed(item.id);
+
+        let body: Element<'_, AppMsg> = match (&item.title, &item.text) {
+            (Some(title), _) => widget::text(title)
+                .font(ROBOTO_FONT.bold())
+                .shaping(Shaping::Advanced)
+                .into(),
+            (None, Some(text)) => widget::rich_text(render_rich_text(text, None, true))
+                .on_link_click(|url| AppMsg::OpenLink { url })
+                .into(),
+            (None, None) => widget::text("").into(),
+        };
+
+        let open_button = if indexed {
+            common::tooltip(
+                widget::button(widget::text("💬").shaping(Shaping::Advanced))
+                    .style(widget::button::text)
+                    .padding(0)
+                    .on_press(AppMsg::Profile(ProfileMsg::OpenItem(item.id))),
+                "Open from local index",
+                Position::Left,
+            )
+        } else {
+            common::tooltip(
+                widget::button(widget::text!("{}", item.id))
+                    .style(widget::button::text)
+                    .padding(0)
+                    .on_press(AppMsg::OpenLink {
+                        url: format!("https://news.ycombinator.com/item?id={}", item.id),
+                    }),
+                "Not indexed, open in browser",
+                Position::Left,
+            )
+        };
+
+        widget::container(
+            widget::Column::new()
+                .push(body)
+                .push(
+                    widget::Row::new()
+                        .push(widget::text(&item.ty).font(ROBOTO_FONT.italic()).size(14))
+                        .push(
+                            widget::text(parse_date(item.time).unwrap_or_default())
+                                .font(ROBOTO_FONT.italic().weight_light())
+                                .size(10),
+                        )
+                        .push(indexed.then(|| widget::text("indexed").size(10)))
+                        .push(widget::container(open_button).align_right(Length::Fill))
+                        .spacing(5)
+                        .align_y(iced::Alignment::Center),
+                )
+                .padding(10)
+                .spacing(10)
+                .width(Length::Fill),
+        )
+    }
+
+    /// Previous and next buttons. Prolific users have thousands of
+    /// submissions so pages aren't listed.
+    fn pagination_element<'a>(&'a self, profile: &'a UserProfile) -> Element<'a, AppMsg> {
+        let submissions = &profile.submissions;
+        widget::container(
+            widget::Row::new()
+                .push(
+                    widget::button(widget::text("←").shaping(Shaping::Advanced)).on_press_maybe(
+                        (!self.loading && submissions.previous_offset(PROFILE_PAGE_SIZE).is_some())
+                            .then_some(AppMsg::Profile(ProfileMsg::Back)),
+                    ),
+                )
+                .push(widget::text!(
+                    "{} of {}",
+                    profile.page(),
+                    profile.page_count()
+                ))
+                .push(
+                    widget::button(widget::text("→").shaping(Shaping::Advanced)).on_press_maybe(
+                        (!self.loading && submissions.next_offset().is_some())
+                            .then_some(AppMsg::Profile(ProfileMsg::Forward)),
+                    ),
+                )
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+        )
+        .center_x(Length::Fill)
+        .padding([5, 0])
+        .into()
+    }
+
+    /// Update profile viewing state.
+    pub fn update(&mut self, message: ProfileMsg) -> Task<AppMsg> {
+        match message {
+            ProfileMsg::Fetch { offset } => {
+                self.loading = true;
+                Task::future(user_profile(
+                    self.search_context.clone(),
+                    self.handle.clone(),
+                    offset,
+                ))
+                .map(|result| match result {
+                    Ok(profile) => AppMsg::Profile(ProfileMsg::Loaded(Box::new(profile))),
+                    Err(err) => AppMsg::Profile(ProfileMsg::Failed(err.to_string())),
+                })
+            }
+            ProfileMsg::Loaded(profile) => {
+                // Ignore a late page for a profile that is no longer viewed.
+                if profile.user.id != self.handle {
+                    return Task::none();
+                }
+                self.loading = false;
+                self.profile = Some(*profile);
+                widget::operation::scroll_to(
+                    profile_scroll_id(),
+                    widget::operation::AbsoluteOffset { x: 0.0, y: 0.0 },
+                )
+            }
+            ProfileMsg::Failed(err) => {
+                self.loading = false;
+                error_task(err)
+            }
+            ProfileMsg::Forward => match self
+                .profile
+                .as_ref()
+                .and_then(|profile| profile.submissions.next_offset())
+            {
+                Some(offset) => Task::done(AppMsg::Profile(ProfileMsg::Fetch { offset })),
+                None => Task::none(),
+            },
+            ProfileMsg::Back => match self
+                .profile
+                .as_ref()
+                .and_then(|profile| profile.submissions.previous_offset(PROFILE_PAGE_SIZE))
+            {
+                Some(offset) => Task::done(AppMsg::Profile(ProfileMsg::Fetch { offset })),
+                None => Task::none(),
+            },
+            ProfileMsg::OpenItem(id) => {
+                let item = self
+                    .profile
+                    .iter()
+                    .flat_map(|profile| &profile.submissions.items)
+                    .find(|item| item.id == id);
+                match item {
+                    Some(item) if item.ty == "comment" => {
+                        common::show_thread(self.search_context.clone(), id)
+                    }
+                    Some(_) => match self.search_context.read().unwrap().story(id) {
+                        Ok(article) => Task::done(AppMsg::OpenComment {
+                            article,
+                            parent_id: id,
+                            comment_stack: Vec::new(),
+                        }),
+                        Err(err) => error_task(err),
+                    },
+                    None => Task::none(),
+                }
+            }
+        }
+    }
+}
+
+pub fn profile_scroll_id() -> widget::Id {
+    widget::Id::new("profile")
+}
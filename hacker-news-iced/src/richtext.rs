@@ -1,17 +1,18 @@
 //! Renders rich text from a simplified html string. Allows creating spans
-//! for search matches so search strings can be highlighted.
+//! for search matches so the words a search matched can be highlighted.
 use crate::{ROBOTO_FONT, ROBOTO_MONO, common::FontExt};
+use hacker_news_search::api::Highlight;
 use html_sanitizer::Anchor;
 use iced::{
     Color,
     widget::{span, text::Span},
 };
-use std::ops::Not;
+use std::{ops::Range, vec};
 
 /// Render a simplified html string into `Span`s for a `RichText` widget.
 pub fn render_rich_text<'a>(
     escaped_text: &'a str,
-    search: Option<&'a str>,
+    highlight: Option<&Highlight>,
     oneline: bool,
 ) -> Vec<Span<'a, String>> {
     let elements = html_sanitizer::parse_elements(escaped_text);
@@ -19,7 +20,9 @@ pub fn render_rich_text<'a>(
 
     for e in elements {
         match e {
-            html_sanitizer::Element::Text(text) => spans.extend(SearchSpanIter::new(text, search)),
+            html_sanitizer::Element::Text(text) => {
+                spans.extend(SearchSpanIter::new(text, highlight))
+            }
             html_sanitizer::Element::Link(link) => spans.extend(anchor_spans(link)),
             html_sanitizer::Element::Escaped(text) => spans.push(span(text)),
             html_sanitizer::Element::Paragraph => {
@@ -29,14 +32,15 @@ pub fn render_rich_text<'a>(
                 spans.push(span("\n\n"))
             }
             html_sanitizer::Element::Code(text) => {
-                spans.extend(split_search(text, search, |span| span.font(ROBOTO_MONO)));
+                spans.extend(split_search(text, highlight, |span| span.font(ROBOTO_MONO)));
             }
             // We can have one level nesting here.
             html_sanitizer::Element::Italic(nested) => {
                 for el in nested {
                     match el {
                         html_sanitizer::Element::Text(text) => spans.extend(
-                            SearchSpanIter::new(text, search).map(|s| s.font(ROBOTO_FONT.italic())),
+                            SearchSpanIter::new(text, highlight)
+                                .map(|s| s.font(ROBOTO_FONT.italic())),
                         ),
                         html_sanitizer::Element::Link(link) => spans.extend(anchor_spans(link)),
                         html_sanitizer::Element::Escaped(text) => {
@@ -47,7 +51,7 @@ pub fn render_rich_text<'a>(
                             for el in nested {
                                 match el {
                                     html_sanitizer::Element::Text(text) => spans.extend(
-                                        SearchSpanIter::new(text, search)
+                                        SearchSpanIter::new(text, highlight)
                                             .map(|s| s.font(ROBOTO_FONT.bold().italic())),
                                     ),
                                     html_sanitizer::Element::Link(link) => {
@@ -70,7 +74,8 @@ pub fn render_rich_text<'a>(
                 for el in inner {
                     match el {
                         html_sanitizer::Element::Text(text) => spans.extend(
-                            SearchSpanIter::new(text, search).map(|s| s.font(ROBOTO_FONT.bold())),
+                            SearchSpanIter::new(text, highlight)
+                                .map(|s| s.font(ROBOTO_FONT.bold())),
                         ),
                         html_sanitizer::Element::Link(link) => spans.extend(anchor_spans(link)),
                         html_sanitizer::Element::Escaped(text) => {
@@ -81,7 +86,7 @@ pub fn render_rich_text<'a>(
                             for el in nested {
                                 match el {
                                     html_sanitizer::Element::Text(text) => spans.extend(
-                                        SearchSpanIter::new(text, search)
+                                        SearchSpanIter::new(text, highlight)
                                             .map(|s| s.font(ROBOTO_FONT.bold().italic())),
                                     ),
                                     html_sanitizer::Element::Link(link) => {
@@ -122,28 +127,25 @@ fn anchor_spans(link: Anchor<'_>) -> impl Iterator<Item = Span<'_, String>> {
 }
 
 /// Split an owned string into multiple owned spans.
-fn split_search<Link>(
+fn split_search<'a, Link>(
     text: String,
-    search: Option<&str>,
-    update_span: impl Fn(Span<'_, Link>) -> Span<'_, Link>,
-) -> Vec<Span<'_, Link>> {
-    match search {
-        Some(s) => {
+    highlight: Option<&Highlight>,
+    update_span: impl Fn(Span<'a, Link>) -> Span<'a, Link>,
+) -> Vec<Span<'a, Link>> {
+    match highlight {
+        Some(highlight) => {
             let mut spans = Vec::new();
             let mut last_index = 0;
 
-            for (index, matched) in text.match_indices(s) {
-                let (unmatched, _) = text.split_at(index);
-                if !unmatched.is_empty() {
-                    let segment = &unmatched[last_index..];
+            for range in highlight.ranges_in(&text) {
+                let segment = &text[last_index..range.start];
+                if !segment.is_empty() {
                     spans.push(update_span(span(segment.to_owned())));
                 }
-                spans.push(update_span(
-                    span(matched.to_owned())
-                        .color(Color::BLACK)
-                        .background(Color::from_rgb8(255, 255, 0)),
-                ));
-                last_index = index + s.len();
+                spans.push(update_span(highlighted(span(
+                    text[range.clone()].to_owned(),
+                ))));
+                last_index = range.end;
             }
 
             let remaining = &text[last_index..];
@@ -157,30 +159,38 @@ fn split_search<Link>(
     }
 }
 
-/// Yield multiple spans from a single str reference.
-pub struct SearchSpanIter<'a, 'b, Link> {
+/// Style a span of matched text.
+fn highlighted<Link>(span: Span<'_, Link>) -> Span<'_, Link> {
+    span.color(Color::BLACK)
+        .background(Color::from_rgb8(255, 255, 0))
+}
+
+/// Yield multiple spans from a single str reference, highlighting the words
+/// a search matched.
+pub struct SearchSpanIter<'a, Link> {
     last_index: usize,
-    search: Option<&'b str>,
     text: &'a str,
-    matcher: Option<Box<dyn Iterator<Item = (usize, &'a str)> + 'a>>,
+    ranges: vec::IntoIter<Range<usize>>,
     finished: bool,
     next_match: Option<Span<'a, Link>>,
 }
 
-impl<'a, 'b, Link> SearchSpanIter<'a, 'b, Link> {
-    pub fn new(text: &'a str, search: Option<&'b str>) -> Self {
+impl<'a, Link> SearchSpanIter<'a, Link> {
+    pub fn new(text: &'a str, highlight: Option<&Highlight>) -> Self {
         Self {
             last_index: 0,
-            search,
             text,
-            matcher: None,
+            ranges: highlight
+                .map(|highlight| highlight.ranges_in(text))
+                .unwrap_or_default()
+                .into_iter(),
             finished: false,
             next_match: None,
         }
     }
 }
 
-impl<'a, 'b: 'a, Link> Iterator for SearchSpanIter<'a, 'b, Link> {
+impl<'a, Link> Iterator for SearchSpanIter<'a, Link> {
     type Item = Span<'a, Link>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -192,41 +202,22 @@ impl<'a, 'b: 'a, Link> Iterator for SearchSpanIter<'a, 'b, Link> {
             return Some(s);
         }
 
-        match self.search {
-            Some(search) => {
-                if self.matcher.is_none() {
-                    self.matcher = Some(Box::new(self.text.match_indices(search)));
-                }
+        match self.ranges.next() {
+            Some(range) => {
+                let segment = &self.text[self.last_index..range.start];
+                let next_match = highlighted(span(&self.text[range.clone()]));
+                self.last_index = range.end;
 
-                if let Some((index, matched)) = self.matcher.as_mut()?.next() {
-                    let (unmatched, _) = self.text.split_at(index);
-
-                    let segment: Option<Span<'a, Link>> = unmatched
-                        .is_empty()
-                        .not()
-                        .then(|| span(&unmatched[self.last_index..]));
-
-                    let next_match = Some(
-                        span(matched)
-                            .color(Color::BLACK)
-                            .background(Color::from_rgb8(255, 255, 0)),
-                    );
-                    self.last_index = index + search.len();
-
-                    if segment.is_some() {
-                        self.next_match = next_match;
-                        segment
-                    } else {
-                        next_match
-                    }
+                if segment.is_empty() {
+                    Some(next_match)
                 } else {
-                    self.finished = true;
-                    Some(span(&self.text[self.last_index..]))
+                    self.next_match = Some(next_match);
+                    Some(span(segment))
                 }
             }
             None => {
                 self.finished = true;
-                Some(span(self.text))
+                Some(span(&self.text[self.last_index..]))
             }
         }
     }
@@ -1,9 +1,10 @@
 use crate::{
-    ITEM_BODY, ITEM_BY, ITEM_DESCENDANT_COUNT, ITEM_ID, ITEM_KIDS, ITEM_PARENT_ID, ITEM_RANK,
-    ITEM_SCORE, ITEM_STORY_ID, ITEM_TIME, ITEM_TITLE, ITEM_TYPE, ITEM_URL, SearchContext,
-    SearchError, SearchResult,
+    ITEM_BODY, ITEM_BY, ITEM_DEAD, ITEM_DELETED, ITEM_DESCENDANT_COUNT, ITEM_ID, ITEM_KIDS,
+    ITEM_PARENT_ID, ITEM_POLL_ID, ITEM_RANK, ITEM_SCORE, ITEM_STORY_ID, ITEM_TIME, ITEM_TITLE,
+    ITEM_TYPE, ITEM_URL, SearchContext, SearchError, SearchResult,
 };
 use chrono::{DateTime, Utc};
+use hacker_news_api::ArticleType;
 use std::collections::HashMap;
 use tantivy::{
     Document, TantivyDocument,
@@ -11,9 +12,14 @@ use tantivy::{
 };
 
 mod comment;
+mod highlight;
+mod poll;
 mod story;
+mod user;
 
 pub use comment::CommentStack;
+pub use highlight::Highlight;
+pub use user::{PROFILE_PAGE_SIZE, UserProfile, user_profile};
 
 pub trait AgeLabel {
     fn time(&self) -> u64;
@@ -61,6 +67,28 @@ pub struct Story {
     pub score: u64,
     /// Rank
     pub rank: u64,
+    /// Title words matched by a search
+    pub title_highlight: Option<Highlight>,
+    /// Body words matched by a search
+    pub body_highlight: Option<Highlight>,
+}
+
+/// An indexed story with its rank in each category it is listed in.
+#[derive(Debug, Clone)]
+pub struct IndexedStory {
+    pub story: Story,
+    pub ranks: Vec<(ArticleType, u64)>,
+}
+
+impl IndexedStory {
+    /// Ranks in the categories other than `category`.
+    pub fn other_ranks(&self, category: ArticleType) -> Vec<(ArticleType, u64)> {
+        self.ranks
+            .iter()
+            .filter(|(ranked_in, _)| *ranked_in != category)
+            .copied()
+            .collect()
+    }
 }
 
 impl AgeLabel for Story {
@@ -88,6 +116,31 @@ pub struct Comment {
     pub parent_id: u64,
     /// Rank
     pub rank: u64,
+    /// Killed by flags or moderators
+    pub dead: bool,
+    /// Deleted by the author
+    pub deleted: bool,
+    /// Body words matched by a search
+    pub body_highlight: Option<Highlight>,
+}
+
+/// Placeholder body for deleted comments.
+pub const DELETED_PLACEHOLDER: &str = "[deleted]";
+/// Placeholder body for dead comments when not showing dead comments.
+pub const DEAD_PLACEHOLDER: &str = "[dead]";
+
+impl Comment {
+    /// The body to render. Deleted comments, and dead comments unless
+    /// `show_dead` is set, are replaced with a placeholder.
+    pub fn display_body(&self, show_dead: bool) -> &str {
+        if self.deleted {
+            DELETED_PLACEHOLDER
+        } else if self.dead && !show_dead {
+            DEAD_PLACEHOLDER
+        } else {
+            &self.body
+        }
+    }
 }
 
 impl AgeLabel for Comment {
@@ -96,8 +149,56 @@ impl AgeLabel for Comment {
     }
 }
 
+#[derive(Debug, Clone)]
+/// Hacker news poll option
+pub struct PollOption {
+    /// Id
+    pub id: u64,
+    /// Poll this option belongs to
+    pub poll_id: u64,
+    /// Option text
+    pub text: String,
+    /// Votes
+    pub score: u64,
+    /// Display order
+    pub rank: u64,
+}
+
+impl PollOption {
+    /// Share of the votes relative to the option with the most votes in
+    /// the range `0.0..=1.0`.
+    pub fn relative_score(&self, options: &[PollOption]) -> f32 {
+        let max_score = options
+            .iter()
+            .map(|option| option.score)
+            .max()
+            .unwrap_or_default();
+        if max_score == 0 {
+            0.
+        } else {
+            self.score as f32 / max_score as f32
+        }
+    }
+}
+
 impl SearchContext {
     fn to_story(&self, doc: TantivyDocument) -> SearchResult<Story> {
+        self.to_category_story(doc, self.active_category)
+    }
+
+    /// Story ranked in `category`. Stories not listed in the category get
+    /// their best rank in another category.
+    fn to_category_story(
+        &self,
+        doc: TantivyDocument,
+        category: ArticleType,
+    ) -> SearchResult<Story> {
+        let ranks = self.category_ranks(&doc);
+        let rank = ranks
+            .iter()
+            .find(|(ranked_in, _)| *ranked_in == category)
+            .or_else(|| ranks.iter().min_by_key(|(_, rank)| *rank))
+            .map(|(_, rank)| *rank);
         let mut fields = self.extract_fields(&doc);
 
         Ok(Story {
@@ -128,13 +229,25 @@ impl SearchContext {
                 .and_then(u64_value)
                 .ok_or_else(|| missing_field(ITEM_TIME))?,
             score: fields.remove(ITEM_SCORE).and_then(u64_value).unwrap_or(1),
-            rank: fields
-                .remove(ITEM_RANK)
-                .and_then(u64_value)
+            rank: rank
+                .or_else(|| fields.remove(ITEM_RANK).and_then(u64_value))
                 .ok_or_else(|| missing_field(ITEM_RANK))?,
+            title_highlight: None,
+            body_highlight: None,
         })
     }
 
+    /// Rank of a story in each category it is listed in.
+    fn category_ranks(&self, doc: &TantivyDocument) -> Vec<(ArticleType, u64)> {
+        ArticleType::ALL
+            .into_iter()
+            .filter_map(|category| {
+                let rank = doc.get_first(self.fields.category_rank(category))?;
+                Some((category, rank.as_u64()?))
+            })
+            .collect()
+    }
+
     fn to_comment(&self, doc: TantivyDocument) -> SearchResult<Comment> {
         let mut fields = self.extract_fields(&doc);
 
@@ -143,10 +256,11 @@ impl SearchContext {
                 .remove(ITEM_ID)
                 .and_then(u64_value)
                 .ok_or_else(|| missing_field(ITEM_ID))?,
+            // Deleted comments have no body.
             body: fields
                 .remove(ITEM_BODY)
                 .and_then(str_value)
-                .ok_or_else(|| missing_field(ITEM_BODY))?,
+                .unwrap_or_default(),
             by: fields
                 .remove(ITEM_BY)
                 .and_then(str_value)
@@ -168,6 +282,42 @@ impl SearchContext {
                 .remove(ITEM_RANK)
                 .and_then(u64_value)
                 .ok_or_else(|| missing_field(ITEM_RANK))?,
+            dead: fields
+                .remove(ITEM_DEAD)
+                .and_then(bool_value)
+                .unwrap_or_default(),
+            deleted: fields
+                .remove(ITEM_DELETED)
+                .and_then(bool_value)
+                .unwrap_or_default(),
+            body_highlight: None,
+        })
+    }
+
+    fn to_poll_option(&self, doc: TantivyDocument) -> SearchResult<PollOption> {
+        let mut fields = self.extract_fields(&doc);
+
+        Ok(PollOption {
+            id: fields
+                .remove(ITEM_ID)
+                .and_then(u64_value)
+                .ok_or_else(|| missing_field(ITEM_ID))?,
+            poll_id: fields
+                .remove(ITEM_POLL_ID)
+                .and_then(u64_value)
+                .ok_or_else(|| missing_field(ITEM_POLL_ID))?,
+            text: fields
+                .remove(ITEM_BODY)
+                .and_then(str_value)
+                .unwrap_or_default(),
+            score: fields
+                .remove(ITEM_SCORE)
+                .and_then(u64_value)
+                .unwrap_or_default(),
+            rank: fields
+                .remove(ITEM_RANK)
+                .and_then(u64_value)
+                .ok_or_else(|| missing_field(ITEM_RANK))?,
         })
     }
 
@@ -193,6 +343,10 @@ fn u64_value(mut owned_value: Vec<CompactDocValue<'_>>) -> Option<u64> {
     owned_value.pop()?.as_u64()
 }
 
+fn bool_value(mut owned_value: Vec<CompactDocValue<'_>>) -> Option<bool> {
+    owned_value.pop()?.as_bool()
+}
+
 fn u64_values(owned_value: Vec<CompactDocValue<'_>>) -> Vec<u64> {
     owned_value
         .into_iter()
@@ -1,10 +1,10 @@
 //! Search API for user comments.
-use super::{Comment, Story};
+use super::{Comment, Story, highlight::Highlighter};
 use crate::{ITEM_RANK, ITEM_TIME, SearchContext, SearchError, SearchResult};
-use std::{ops::Bound, time::SystemTime};
+use std::{collections::HashMap, ops::Bound, time::SystemTime};
 use tantivy::{
     Order, Searcher, Term,
-    collector::{Count, MultiCollector, TopDocs},
+    collector::{Count, DocSetCollector, MultiCollector, TopDocs},
     query::{BooleanQuery, Occur, Query, RangeQuery, TermQuery},
     schema::IndexRecordOption,
 };
@@ -96,6 +96,24 @@ impl SearchContext {
         Ok((comments, count))
     }
 
+    /// All indexed comments of a story by id.
+    pub fn indexed_comments(&self, story_id: u64) -> SearchResult<HashMap<u64, Comment>> {
+        let by_story = TermQuery::new(
+            Term::from_field_u64(self.fields.story_id, story_id),
+            IndexRecordOption::Basic,
+        );
+
+        let searcher = self.searcher();
+        searcher
+            .search(&by_story, &DocSetCollector)?
+            .into_iter()
+            .map(|doc_address| {
+                let comment = self.to_comment(searcher.doc(doc_address)?)?;
+                Ok((comment.id, comment))
+            })
+            .collect()
+    }
+
     pub fn last_comment_age(&self, story_id: u64) -> SearchResult<Option<u64>> {
         let by_story = TermQuery::new(
             Term::from_field_u64(self.fields.story_id, story_id),
@@ -116,12 +134,14 @@ impl SearchContext {
     }
 
     /// Search user comments with term, related story, limit and pagination offset.
+    /// The matched words of each comment are included when `highlight` is set.
     pub fn search_comments(
         &self,
         search: &str,
         story_id: u64,
         limit: usize,
         offset: usize,
+        highlight: bool,
     ) -> SearchResult<(Vec<Comment>, usize)> {
         let story_term = Box::new(TermQuery::new(
             Term::from_field_u64(self.fields.story_id, story_id),
@@ -133,15 +153,17 @@ impl SearchContext {
         let combined_query =
             BooleanQuery::new(vec![(Occur::Must, story_term), (Occur::Must, parsed_query)]);
 
-        self.top_comments_with_count(limit, offset, combined_query)
+        self.top_comments_with_count(limit, offset, combined_query, highlight)
     }
 
     /// Search all comments across all stories with limit and pagination offset.
+    /// The matched words of each comment are included when `highlight` is set.
     pub fn search_all_comments(
         &self,
         search: &str,
         limit: usize,
         offset: usize,
+        highlight: bool,
     ) -> SearchResult<(Vec<Comment>, usize)> {
         let parsed_query = self.query_parser().parse_query(search)?;
 
@@ -155,16 +177,18 @@ impl SearchContext {
             (Occur::Must, parsed_query),
         ]);
 
-        self.top_comments_with_count(limit, offset, query)
+        self.top_comments_with_count(limit, offset, query, highlight)
     }
 
     /// Search query returning the total count and matching documents within
-    /// offset and limit.
+    /// offset and limit. The matched words of each comment are included
+    /// when `highlight` is set.
     fn top_comments_with_count(
         &self,
         limit: usize,
         offset: usize,
         query: impl Query,
+        highlight: bool,
     ) -> SearchResult<(Vec<Comment>, usize)> {
         let searcher = self.searcher();
 
@@ -179,10 +203,20 @@ impl SearchContext {
         let docs = docs_handle.extract(&mut multi_fruit);
         let count = count_handle.extract(&mut multi_fruit);
 
+        let highlighter = highlight
+            .then(|| Highlighter::new(&searcher, &query, self.fields.body))
+            .transpose()?;
+
         let comments = docs
             .into_iter()
-            .map(|(_, doc_address)| self.to_comment(searcher.doc(doc_address)?))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|(_, doc_address)| {
+                let mut comment = self.to_comment(searcher.doc(doc_address)?)?;
+                if let Some(highlighter) = &highlighter {
+                    comment.body_highlight = highlighter.highlight(&comment.body);
+                }
+                Ok(comment)
+            })
+            .collect::<SearchResult<Vec<_>>>()?;
 
         Ok((comments, count))
     }
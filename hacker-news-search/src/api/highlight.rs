@@ -0,0 +1,94 @@
+//! Highlights of the words a search matched.
+use crate::SearchResult;
+use std::ops::Range;
+use tantivy::{Searcher, query::Query, schema::Field, snippet::SnippetGenerator};
+
+/// Words of a field a search matched. Found by the index so words sharing a
+/// stem with a search term are included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Highlight {
+    /// Best matching fragment of the stored field, which may contain markup.
+    pub snippet: String,
+    /// Byte ranges of the matches in `snippet`.
+    pub snippet_ranges: Vec<Range<usize>>,
+    /// Distinct matched words in the order they first appear.
+    pub words: Vec<String>,
+}
+
+impl Highlight {
+    /// Byte ranges of the matched words in `text`, usually a rendered part
+    /// of the field. Only whole words are matched.
+    pub fn ranges_in(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = self
+            .words
+            .iter()
+            .flat_map(|word| {
+                text.match_indices(word.as_str())
+                    .map(|(index, word)| index..index + word.len())
+            })
+            .filter(|range| {
+                let before = text[..range.start].chars().next_back();
+                let after = text[range.end..].chars().next();
+                [before, after]
+                    .into_iter()
+                    .flatten()
+                    .all(|c| !c.is_alphanumeric())
+            })
+            .collect::<Vec<_>>();
+
+        // Keep the longest of overlapping matches.
+        ranges.sort_by_key(|range| (range.start, usize::MAX - range.end));
+        let mut end = 0;
+        ranges.retain(|range| {
+            let keep = range.start >= end;
+            end = end.max(range.end);
+            keep
+        });
+        ranges
+    }
+}
+
+/// Highlights the matches of a search query in a field.
+pub(crate) struct Highlighter {
+    /// Best fragment of the default snippet length.
+    snippets: SnippetGenerator,
+    /// Single fragment with every match in the field.
+    matches: SnippetGenerator,
+}
+
+impl Highlighter {
+    pub fn new(searcher: &Searcher, query: &dyn Query, field: Field) -> SearchResult<Self> {
+        let mut matches = SnippetGenerator::create(searcher, query, field)?;
+        matches.set_max_num_chars(usize::MAX);
+        Ok(Self {
+            snippets: SnippetGenerator::create(searcher, query, field)?,
+            matches,
+        })
+    }
+
+    /// Highlight of `text`, the stored value of the field. `None` when the
+    /// search matched nothing in it.
+    pub fn highlight(&self, text: &str) -> Option<Highlight> {
+        let matches = self.matches.snippet(text);
+        if matches.is_empty() {
+            return None;
+        }
+        let mut words = Vec::<String>::new();
+        for range in matches.highlighted() {
+            let word = &matches.fragment()[range.clone()];
+            if !words.iter().any(|w| w == word) {
+                words.push(word.to_owned());
+            }
+        }
+
+        let snippet = self.snippets.snippet(text);
+        Some(Highlight {
+            snippet: snippet.fragment().to_owned(),
+            snippet_ranges: snippet.highlighted().to_vec(),
+            words,
+        })
+    }
+}
+
+#[cfg(test)]
+mod highlight_tests;
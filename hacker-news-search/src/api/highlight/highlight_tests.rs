@@ -0,0 +1,148 @@
+use super::{Highlight, Highlighter};
+use crate::SearchContext;
+use hacker_news_api::ArticleType;
+use std::path::PathBuf;
+use tantivy::{IndexWriter, doc};
+
+/// Comments of story 1 by their id and body.
+const COMMENTS: &[(u64, &str)] = &[
+    (2, "She was running late and runs daily."),
+    (
+        3,
+        "Async rust is fine, rust async is better, sync rust is simplest.",
+    ),
+    (4, "Über naïve café owners serve café crème."),
+];
+
+/// Index with story 1, ranked first in top stories, and [`COMMENTS`].
+fn context(name: &str) -> (PathBuf, SearchContext) {
+    let dir =
+        std::env::temp_dir().join(format!("hn-search-highlight-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let ctx = SearchContext::new(&dir, ArticleType::Top).unwrap();
+    let fields = ctx.fields;
+
+    let mut writer: IndexWriter = ctx.index.writer(15_000_000).unwrap();
+    writer
+        .add_document(doc!(
+            fields.id => 1u64,
+            fields.title => "Running Rust",
+            fields.body => "Notes on running rust in production.",
+            fields.by => "alice",
+            fields.ty => "story",
+            fields.time => 1_700_000_000u64,
+            fields.category => ArticleType::Top.as_str(),
+            fields.category_rank(ArticleType::Top) => 1u64,
+        ))
+        .unwrap();
+    for (id, body) in COMMENTS {
+        writer
+            .add_document(doc!(
+                fields.id => *id,
+                fields.body => *body,
+                fields.by => "bob",
+                fields.ty => "comment",
+                fields.time => 1_700_000_000u64 + id,
+                fields.story_id => 1u64,
+                fields.parent_id => 1u64,
+                fields.rank => *id,
+            ))
+            .unwrap();
+    }
+    writer.commit().unwrap();
+    ctx.refresh_reader().unwrap();
+    (dir, ctx)
+}
+
+/// Highlight of `text` in the comment bodies for `search`.
+fn highlight(ctx: &SearchContext, search: &str, text: &str) -> Option<Highlight> {
+    let query = ctx.query_parser().parse_query(search).unwrap();
+    Highlighter::new(&ctx.searcher(), query.as_ref(), ctx.fields.body)
+        .unwrap()
+        .highlight(text)
+}
+
+/// Text of each snippet range.
+fn snippet_words(highlight: &Highlight) -> Vec<&str> {
+    highlight
+        .snippet_ranges
+        .iter()
+        .map(|range| &highlight.snippet[range.clone()])
+        .collect()
+}
+
+#[test]
+fn stemmed_matches() {
+    let (dir, ctx) = context("stemmed");
+
+    let highlight = highlight(&ctx, "run", COMMENTS[0].1).unwrap();
+    assert_eq!(highlight.words, ["running", "runs"]);
+    assert_eq!(snippet_words(&highlight), ["running", "runs"]);
+    assert!(highlight.snippet.contains("running late"));
+
+    assert_eq!(self::highlight(&ctx, "run", COMMENTS[1].1), None);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn phrase_matches() {
+    let (dir, ctx) = context("phrase");
+
+    let highlight = highlight(&ctx, "\"rust async\"", COMMENTS[1].1).unwrap();
+    // Every word of the phrase is highlighted in the order they appear.
+    assert_eq!(highlight.words, ["Async", "rust", "async"]);
+    assert!(!snippet_words(&highlight).contains(&"sync"));
+
+    let text = "Rust, async and rust async.";
+    let ranges = highlight.ranges_in(text);
+    let words = ranges
+        .iter()
+        .map(|range| &text[range.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(words, ["async", "rust", "async"]);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn multibyte_offsets() {
+    let (dir, ctx) = context("multibyte");
+
+    let highlight = highlight(&ctx, "café", COMMENTS[2].1).unwrap();
+    assert_eq!(highlight.words, ["café"]);
+    assert_eq!(snippet_words(&highlight), ["café", "café"]);
+    for range in &highlight.snippet_ranges {
+        assert!(highlight.snippet.is_char_boundary(range.start));
+        assert!(highlight.snippet.is_char_boundary(range.end));
+    }
+
+    // Ranges are bytes and only whole words are matched.
+    let text = "Ünïcödé café, cafés and café.";
+    let ranges = highlight.ranges_in(text);
+    assert_eq!(ranges, [12..17, 30..35]);
+    assert!(ranges.iter().all(|range| &text[range.clone()] == "café"));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn highlight_is_optional() {
+    let (dir, ctx) = context("optional");
+
+    let (comments, count) = ctx.search_all_comments("runs", 10, 0, false).unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(comments[0].body_highlight, None);
+    let (comments, _) = ctx.search_all_comments("runs", 10, 0, true).unwrap();
+    let highlight = comments[0].body_highlight.as_ref().unwrap();
+    assert_eq!(highlight.words, ["running", "runs"]);
+
+    let (comments, _) = ctx.search_comments("café", 1, 10, 0, false).unwrap();
+    assert_eq!(comments[0].body_highlight, None);
+
+    let stories = ctx.search_stories("rust", None, 10, 0, false).unwrap();
+    assert_eq!(stories.len(), 1);
+    assert_eq!(stories[0].title_highlight, None);
+    assert_eq!(stories[0].body_highlight, None);
+    let stories = ctx.search_stories("rust", None, 10, 0, true).unwrap();
+    assert_eq!(stories[0].title_highlight.as_ref().unwrap().words, ["Rust"]);
+    assert_eq!(stories[0].body_highlight.as_ref().unwrap().words, ["rust"]);
+    let _ = std::fs::remove_dir_all(dir);
+}
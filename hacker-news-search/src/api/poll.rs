@@ -0,0 +1,44 @@
+//! Search API for poll options.
+use super::PollOption;
+use crate::{ITEM_RANK, SearchContext, SearchResult};
+use tantivy::{
+    Order, Term,
+    collector::TopDocs,
+    query::{BooleanQuery, Occur, TermQuery},
+    schema::IndexRecordOption,
+};
+
+/// Polls on hacker news have a handful of options.
+const MAX_POLL_OPTIONS: usize = 100;
+
+impl SearchContext {
+    /// Lookup the options of a poll in display order.
+    pub fn poll_options(&self, story_id: u64) -> SearchResult<Vec<PollOption>> {
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_u64(self.fields.poll_id, story_id),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(self.fields.ty, "pollopt"),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+        ]);
+
+        let searcher = self.searcher();
+        let top_docs =
+            TopDocs::with_limit(MAX_POLL_OPTIONS).order_by_u64_field(ITEM_RANK, Order::Asc);
+
+        searcher
+            .search(&query, &top_docs)?
+            .into_iter()
+            .map(|(_, doc_address)| self.to_poll_option(searcher.doc(doc_address)?))
+            .collect()
+    }
+}
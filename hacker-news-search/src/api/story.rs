@@ -1,11 +1,19 @@
 //! Search API for top stories.
-use super::Story;
-use crate::{ITEM_RANK, SearchContext, SearchError, SearchResult};
-use std::sync::OnceLock;
+use super::{IndexedStory, Story, highlight::Highlighter};
+use crate::{
+    SearchContext, SearchError, SearchResult, category_rank_name,
+    query::{Comparison, Filter, StoryQuery, StoryType},
+};
+use chrono::Utc;
+use hacker_news_api::ArticleType;
+use std::{collections::HashMap, ops::Bound, sync::OnceLock};
 use tantivy::{
     Order, TantivyDocument, Term,
-    collector::TopDocs,
-    query::{BooleanQuery, Occur, Query, TermQuery},
+    collector::{DocSetCollector, TopDocs},
+    query::{
+        AllQuery, BooleanQuery, EmptyQuery, Occur, PhraseQuery, Query, RangeQuery, RegexQuery,
+        TermQuery,
+    },
     schema::{Field, IndexRecordOption},
 };
 
@@ -25,29 +33,62 @@ fn story_job_poll(type_field: Field) -> BooleanQuery {
 }
 
 impl SearchContext {
-    /// Lookup top stories applying limit and  offset pagination.
-    pub fn top_stories(&self, limit: usize, offset: usize) -> SearchResult<Vec<Story>> {
-        let query = STORY_OR_JOB_OR_POLL.get_or_init(|| story_job_poll(self.fields.ty));
+    /// Lookup top stories of a category applying limit and offset pagination.
+    pub fn top_stories(
+        &self,
+        category: ArticleType,
+        limit: usize,
+        offset: usize,
+    ) -> SearchResult<Vec<Story>> {
+        let query = self.category_query(category);
+        let rank_field = category_rank_name(category);
         let searcher = self.searcher();
         let top_docs = TopDocs::with_limit(limit)
             // Pagination
             .and_offset(offset)
             // Ordering
-            .order_by_u64_field(ITEM_RANK, Order::Asc);
+            .order_by_u64_field(&rank_field, Order::Asc);
 
         searcher
-            .search(query, &top_docs)?
+            .search(&query, &top_docs)?
             .into_iter()
-            .map(|(_, doc_address)| self.to_story(searcher.doc(doc_address)?))
+            .map(|(_, doc_address)| self.to_category_story(searcher.doc(doc_address)?, category))
             .collect::<Result<Vec<_>, _>>()
     }
 
-    /// Search all stories with term and offset pagination.
+    /// All stories, jobs and polls in the index by id.
+    pub fn indexed_stories(&self) -> SearchResult<HashMap<u64, IndexedStory>> {
+        let query = STORY_OR_JOB_OR_POLL.get_or_init(|| story_job_poll(self.fields.ty));
+        let searcher = self.searcher();
+
+        searcher
+            .search(query, &DocSetCollector)?
+            .into_iter()
+            .map(|doc_address| {
+                let doc = searcher.doc(doc_address)?;
+                let ranks = self.category_ranks(&doc);
+                let story = self.to_story(doc)?;
+                Ok((story.id, IndexedStory { story, ranks }))
+            })
+            .collect()
+    }
+
+    /// Rank of a story in each category it is listed in.
+    pub fn story_ranks(&self, story_id: u64) -> SearchResult<Vec<(ArticleType, u64)>> {
+        Ok(self.category_ranks(&self.story_doc(story_id)?))
+    }
+
+    /// Search stories with the [query language](crate::query) and offset
+    /// pagination. A number looks up the story with that id. Stories in all
+    /// categories are searched when `category` is `None`. The matched words
+    /// of each story are included when `highlight` is set.
     pub fn search_stories(
         &self,
         search: &str,
+        category: Option<ArticleType>,
         limit: usize,
         offset: usize,
+        highlight: bool,
     ) -> SearchResult<Vec<Story>> {
         let story_id_query = search
             .parse::<u64>()
@@ -63,28 +104,157 @@ impl SearchContext {
             match story_id_query.as_ref() {
                 Some(q) => q,
                 None => &BooleanQuery::new(vec![
-                    (
-                        Occur::Must,
-                        Box::new(TermQuery::new(
-                            Term::from_field_text(self.fields.title, &search.to_lowercase()),
-                            IndexRecordOption::WithFreqs,
-                        )),
-                    ),
+                    (Occur::Must, self.story_query(&search.parse()?)?),
                     (Occur::Must, Box::new(story_job_poll(self.fields.ty))),
                 ]),
             }
         };
+        let query = match category {
+            Some(category) => &BooleanQuery::new(vec![
+                (Occur::Must, query.box_clone()),
+                (Occur::Must, Box::new(self.category_query(category))),
+            ]),
+            None => query,
+        };
 
         let searcher = self.searcher();
         let top_docs = TopDocs::with_limit(limit).and_offset(offset);
+        let highlighters = highlight
+            .then(|| {
+                Ok::<_, SearchError>((
+                    Highlighter::new(&searcher, query, self.fields.title)?,
+                    Highlighter::new(&searcher, query, self.fields.body)?,
+                ))
+            })
+            .transpose()?;
 
         searcher
             .search(query, &top_docs)?
             .into_iter()
-            .map(|(_, doc_address)| self.to_story(searcher.doc(doc_address)?))
+            .map(|(_, doc_address)| {
+                let doc = searcher.doc(doc_address)?;
+                let mut story = match category {
+                    Some(category) => self.to_category_story(doc, category)?,
+                    None => self.to_story(doc)?,
+                };
+                if let Some((title, body)) = &highlighters {
+                    story.title_highlight = title.highlight(&story.title);
+                    story.body_highlight = story.body.as_deref().and_then(|b| body.highlight(b));
+                }
+                Ok(story)
+            })
             .collect::<Result<Vec<_>, _>>()
     }
 
+    /// Tantivy query for a parsed story search.
+    fn story_query(&self, query: &StoryQuery) -> SearchResult<Box<dyn Query>> {
+        let combine = |occur, queries: &[StoryQuery]| -> SearchResult<Box<dyn Query>> {
+            Ok(Box::new(BooleanQuery::new(
+                queries
+                    .iter()
+                    .map(|query| Ok((occur, self.story_query(query)?)))
+                    .collect::<SearchResult<_>>()?,
+            )))
+        };
+
+        Ok(match query {
+            StoryQuery::Term(text) | StoryQuery::Phrase(text) => self.text_query(text)?,
+            StoryQuery::Filter(filter) => self.filter_query(filter)?,
+            // A boolean query with only excluded clauses matches nothing.
+            StoryQuery::Not(query) => Box::new(BooleanQuery::new(vec![
+                (Occur::Must, Box::new(AllQuery)),
+                (Occur::MustNot, self.story_query(query)?),
+            ])),
+            StoryQuery::And(queries) => combine(Occur::Must, queries)?,
+            StoryQuery::Or(queries) => combine(Occur::Should, queries)?,
+        })
+    }
+
+    /// Words matching the title or text in order. The words are split the
+    /// same way as when the fields were indexed.
+    fn text_query(&self, text: &str) -> SearchResult<Box<dyn Query>> {
+        let mut field_queries = Vec::<(Occur, Box<dyn Query>)>::new();
+        for field in [self.fields.title, self.fields.body] {
+            let mut terms = Vec::new();
+            self.index
+                .tokenizer_for_field(field)?
+                .token_stream(text)
+                .process(&mut |token| terms.push(Term::from_field_text(field, &token.text)));
+
+            let query: Box<dyn Query> = match terms.len() {
+                0 => Box::new(EmptyQuery),
+                1 => Box::new(TermQuery::new(
+                    terms.remove(0),
+                    IndexRecordOption::WithFreqs,
+                )),
+                _ => Box::new(PhraseQuery::new(terms)),
+            };
+            field_queries.push((Occur::Should, query));
+        }
+        Ok(Box::new(BooleanQuery::new(field_queries)))
+    }
+
+    /// Query for a story search filter.
+    fn filter_query(&self, filter: &Filter) -> SearchResult<Box<dyn Query>> {
+        let term_query =
+            |term| -> Box<dyn Query> { Box::new(TermQuery::new(term, IndexRecordOption::Basic)) };
+        let range_query = |field, comparison: Comparison| -> Box<dyn Query> {
+            let term = |value| Term::from_field_u64(field, value);
+            let (lower, upper) = match comparison {
+                Comparison::Less(n) => (Bound::Unbounded, Bound::Excluded(term(n))),
+                Comparison::LessOrEqual(n) => (Bound::Unbounded, Bound::Included(term(n))),
+                Comparison::Equal(n) => return term_query(term(n)),
+                Comparison::GreaterOrEqual(n) => (Bound::Included(term(n)), Bound::Unbounded),
+                Comparison::Greater(n) => (Bound::Excluded(term(n)), Bound::Unbounded),
+            };
+            Box::new(RangeQuery::new(lower, upper))
+        };
+        let type_query = |ty| term_query(Term::from_field_text(self.fields.ty, ty));
+        // Ask and show stories are listed in their category while they are
+        // new and keep their title prefix afterwards.
+        let listed_or_titled = |category: ArticleType, prefix| -> SearchResult<Box<dyn Query>> {
+            Ok(Box::new(BooleanQuery::new(vec![
+                (Occur::Should, Box::new(self.category_query(category))),
+                (Occur::Should, self.text_query(prefix)?),
+            ])))
+        };
+        let now = Utc::now();
+
+        Ok(match filter {
+            Filter::By(by) => term_query(Term::from_field_text(self.fields.by, by)),
+            Filter::Site(domain) => {
+                // Urls are indexed whole so the host is matched with a pattern.
+                let domain = domain.replace('.', r"\.");
+                Box::new(RegexQuery::from_pattern(
+                    &format!(r"https?://([^/?#]*\.)?{domain}([:/?#].*)?"),
+                    self.fields.url,
+                )?)
+            }
+            Filter::Score(comparison) => range_query(self.fields.score, *comparison),
+            Filter::Comments(comparison) => range_query(self.fields.descendant_count, *comparison),
+            Filter::Type(StoryType::Ask) => listed_or_titled(ArticleType::Ask, "Ask HN")?,
+            Filter::Type(StoryType::Show) => listed_or_titled(ArticleType::Show, "Show HN")?,
+            Filter::Type(StoryType::Job) => type_query("job"),
+            Filter::Type(StoryType::Poll) => type_query("poll"),
+            Filter::Type(StoryType::Story) => type_query("story"),
+            Filter::After(when) => range_query(
+                self.fields.time,
+                Comparison::GreaterOrEqual(when.timestamp(now)),
+            ),
+            Filter::Before(when) => {
+                range_query(self.fields.time, Comparison::Less(when.timestamp(now)))
+            }
+        })
+    }
+
+    /// Stories, jobs and polls listed in `category`.
+    fn category_query(&self, category: ArticleType) -> TermQuery {
+        TermQuery::new(
+            Term::from_field_text(self.fields.category, category.as_str()),
+            IndexRecordOption::Basic,
+        )
+    }
+
     /// Lookup a single story.
     pub fn story(&self, story_id: u64) -> SearchResult<Story> {
         self.to_story(self.story_doc(story_id)?)
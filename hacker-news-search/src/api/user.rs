@@ -0,0 +1,103 @@
+//! Search API for user profiles.
+use super::{AgeLabel, u64_value};
+use crate::{ITEM_ID, SearchContext, SearchResult, api_client};
+use hacker_news_api::{Item, SubmissionsPage, User};
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+use tantivy::{TantivyDocument, Term, collector::DocSetCollector, query::TermSetQuery};
+
+/// Number of submissions on a profile page.
+pub const PROFILE_PAGE_SIZE: usize = 10;
+
+/// A user profile with a page of the user's submissions.
+#[derive(Debug, Clone)]
+pub struct UserProfile {
+    pub user: User,
+    /// Page of stories and comments, newest first.
+    pub submissions: SubmissionsPage,
+    /// Submissions on the page that are in the local index.
+    pub indexed: HashSet<u64>,
+}
+
+impl UserProfile {
+    /// Whether a submission on the page is in the local index.
+    pub fn is_indexed(&self, id: u64) -> bool {
+        self.indexed.contains(&id)
+    }
+
+    /// Current page number starting at 1.
+    pub fn page(&self) -> usize {
+        self.submissions.offset / PROFILE_PAGE_SIZE + 1
+    }
+
+    /// Number of pages.
+    pub fn page_count(&self) -> usize {
+        self.submissions.total.div_ceil(PROFILE_PAGE_SIZE).max(1)
+    }
+}
+
+/// Age of the account.
+impl AgeLabel for UserProfile {
+    fn time(&self) -> u64 {
+        self.user.created
+    }
+}
+
+impl AgeLabel for Item {
+    fn time(&self) -> u64 {
+        self.time
+    }
+}
+
+/// Fetch a user and the page of submissions at `offset`. The user is
+/// fetched for every page so the karma and submissions stay current.
+pub async fn user_profile(
+    ctx: Arc<RwLock<SearchContext>>,
+    handle: String,
+    offset: usize,
+) -> SearchResult<UserProfile> {
+    let api = api_client();
+    let user = api.user(&handle).await?;
+    let submissions = api
+        .user_submissions(&user, offset, PROFILE_PAGE_SIZE)
+        .await?;
+    let ids = submissions
+        .items
+        .iter()
+        .map(|item| item.id)
+        .collect::<Vec<_>>();
+    let indexed = ctx.read().unwrap().indexed_ids(&ids)?;
+
+    Ok(UserProfile {
+        user,
+        submissions,
+        indexed,
+    })
+}
+
+impl SearchContext {
+    /// The subset of `ids` that are in the index, such as a page of
+    /// a user's submissions.
+    pub fn indexed_ids(&self, ids: &[u64]) -> SearchResult<HashSet<u64>> {
+        let query = TermSetQuery::new(
+            ids.iter()
+                .map(|id| Term::from_field_u64(self.fields.id, *id)),
+        );
+        let searcher = self.searcher();
+
+        let mut indexed = HashSet::new();
+        for doc_address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(id) = self
+                .extract_fields(&doc)
+                .remove(ITEM_ID)
+                .and_then(u64_value)
+            {
+                indexed.insert(id);
+            }
+        }
+        Ok(indexed)
+    }
+}
@@ -1,23 +1,36 @@
 //! Create index.
 use crate::{
-    HackerNewsFields, ITEM_TYPE, SearchContext, SearchError, SearchResult,
-    api::{Comment, Story},
+    DEFAULT_WRITER_MEMORY, HackerNewsFields, SearchContext, SearchError, SearchResult,
+    api::{Comment, IndexedStory, Story},
+};
+use futures::{SinkExt, StreamExt, TryFutureExt, TryStreamExt, channel::mpsc, stream};
+use hacker_news_api::{
+    ApiClient, ArticleType, ChangeFeed, Item, ItemEventData, ItemSource, ItemTree,
+    SubscriptionStatus, SupervisorConfig, TreeOptions,
 };
-use futures::{SinkExt, Stream, StreamExt, TryFutureExt, TryStreamExt, channel::mpsc};
-use futures_util::stream::FuturesUnordered;
-use hacker_news_api::{ApiClient, ArticleType, Item, ItemEventData};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{HashMap, HashSet},
     convert::identity,
     future::ready,
     mem,
+    pin::pin,
     sync::{Arc, OnceLock, RwLock},
     time::{Duration, Instant, SystemTime},
 };
-use tantivy::{IndexWriter, TantivyDocument, Term};
+use tantivy::{
+    IndexWriter, TantivyDocument, TantivyError, Term,
+    collector::{Count, DocSetCollector},
+    directory::error::LockError,
+    query::{BooleanQuery, Occur, Query, TermQuery, TermSetQuery},
+    schema::{IndexRecordOption, Value as _},
+};
 use tokio::{
-    sync::mpsc::{Receiver, Sender, channel},
+    sync::{
+        mpsc::{Receiver, Sender, channel},
+        watch,
+    },
     task::AbortHandle,
     time::timeout,
 };
@@ -27,12 +40,77 @@ use tracing::{Instrument as _, instrument};
 /// Single api client for connection pooling re-use.
 static API: OnceLock<Arc<ApiClient>> = OnceLock::new();
 
+/// Install the api client shared by the indexer. This must be called before
+/// the first call to [`api_client`] otherwise the default client is kept.
+/// Returns the active shared client.
+pub fn init_api_client(client: ApiClient) -> Arc<ApiClient> {
+    let mut client = Some(client);
+    let active = API.get_or_init(|| Arc::new(client.take().unwrap()));
+    if client.is_some() {
+        warn!("Api client already initialized, ignoring new client");
+    }
+    active.clone()
+}
+
+/// Shared api client. Uses the default firebase client if no client was
+/// installed via [`init_api_client`].
 pub fn api_client() -> Arc<ApiClient> {
     let client =
         API.get_or_init(|| Arc::new(ApiClient::new().expect("Could not create API client")));
     client.clone()
 }
 
+/// Item source used for comment trees when rebuilding.
+static ITEM_SOURCE: OnceLock<Arc<dyn ItemSource>> = OnceLock::new();
+
+/// Install an alternative source for fetching story comment trees when
+/// rebuilding, such as the [`hacker_news_api::AlgoliaClient`]. Without one
+/// comments are fetched one by one from the shared api client.
+pub fn init_item_source(source: Arc<dyn ItemSource>) {
+    if ITEM_SOURCE.set(source).is_err() {
+        warn!("Item source already initialized, ignoring new source");
+    }
+}
+
+/// Single change feed shared by all watched items.
+static CHANGE_FEED: OnceLock<ChangeFeed> = OnceLock::new();
+
+/// Shared change feed of updated items. Started with the shared api client
+/// on first use and never gives up reconnecting since it can't be restarted.
+pub fn change_feed() -> &'static ChangeFeed {
+    CHANGE_FEED.get_or_init(|| {
+        let config = SupervisorConfig {
+            max_attempts: None,
+            ..Default::default()
+        };
+        ChangeFeed::start(&api_client(), config, 32)
+    })
+}
+
+/// Fetch the item each time it shows up in the change feed and forward it
+/// to the event handler when it differs from the item last forwarded.
+async fn forward_item_changes(client: Arc<ApiClient>, item_id: u64, tx: Sender<ItemEventData>) {
+    let mut changes = std::pin::pin!(change_feed().changed_items(vec![item_id]));
+    let mut last = None;
+
+    while changes.next().await.is_some() {
+        match client.item(item_id).await {
+            Ok(item) if last.as_ref() == Some(&item) => {}
+            Ok(item) => {
+                last = Some(item.clone());
+                let event = ItemEventData {
+                    path: "/".to_owned(),
+                    data: item,
+                };
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+            Err(err) => error!("Failed to fetch changed item {item_id}: {err}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub struct IndexStats {
     pub total_documents: u64,
@@ -45,6 +123,141 @@ pub struct IndexStats {
     pub category: ArticleType,
 }
 
+/// Limits and resources used when rebuilding a category index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RebuildOptions {
+    /// Number of stories to index.
+    pub story_limit: usize,
+    /// Deepest comment level to index. Top level comments are at depth 1.
+    pub max_comment_depth: Option<usize>,
+    /// Maximum number of comments indexed for each story.
+    pub max_comments: Option<usize>,
+    /// Time allowed to fetch the comments of a story.
+    pub comments_timeout: Duration,
+    /// Time allowed to collect a story with its comments and poll options.
+    pub story_timeout: Duration,
+    /// Number of stories collected at the same time. Item requests are
+    /// further limited by the api client.
+    pub concurrency: usize,
+    /// Memory budget of the index writer in bytes.
+    pub writer_memory: usize,
+}
+
+impl Default for RebuildOptions {
+    fn default() -> Self {
+        Self {
+            story_limit: 75,
+            max_comment_depth: None,
+            max_comments: None,
+            comments_timeout: Duration::from_secs(60),
+            story_timeout: Duration::from_secs(60 * 3),
+            concurrency: 75,
+            writer_memory: DEFAULT_WRITER_MEMORY,
+        }
+    }
+}
+
+impl RebuildOptions {
+    /// Limits for fetching the comments of a story.
+    pub fn tree_options(&self) -> TreeOptions {
+        TreeOptions {
+            max_depth: self.max_comment_depth,
+            max_nodes: self.max_comments,
+        }
+    }
+}
+
+/// A setting of [`RebuildOptions`] that frontends edit as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebuildSetting {
+    StoryLimit,
+    MaxCommentDepth,
+    MaxComments,
+    CommentsTimeout,
+    StoryTimeout,
+    Concurrency,
+    WriterMemory,
+}
+
+impl RebuildSetting {
+    pub const ALL: [RebuildSetting; 7] = [
+        RebuildSetting::StoryLimit,
+        RebuildSetting::MaxCommentDepth,
+        RebuildSetting::MaxComments,
+        RebuildSetting::CommentsTimeout,
+        RebuildSetting::StoryTimeout,
+        RebuildSetting::Concurrency,
+        RebuildSetting::WriterMemory,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RebuildSetting::StoryLimit => "Stories",
+            RebuildSetting::MaxCommentDepth => "Comment depth",
+            RebuildSetting::MaxComments => "Comments per story",
+            RebuildSetting::CommentsTimeout => "Comments timeout (s)",
+            RebuildSetting::StoryTimeout => "Story timeout (s)",
+            RebuildSetting::Concurrency => "Concurrent stories",
+            RebuildSetting::WriterMemory => "Writer memory (MB)",
+        }
+    }
+
+    /// The setting as text. Settings without a limit are empty.
+    pub fn value(&self, options: &RebuildOptions) -> String {
+        let value = match self {
+            RebuildSetting::StoryLimit => Some(options.story_limit),
+            RebuildSetting::MaxCommentDepth => options.max_comment_depth,
+            RebuildSetting::MaxComments => options.max_comments,
+            RebuildSetting::CommentsTimeout => Some(options.comments_timeout.as_secs() as usize),
+            RebuildSetting::StoryTimeout => Some(options.story_timeout.as_secs() as usize),
+            RebuildSetting::Concurrency => Some(options.concurrency),
+            RebuildSetting::WriterMemory => Some(options.writer_memory / 1_000_000),
+        };
+        value.map(|value| value.to_string()).unwrap_or_default()
+    }
+
+    /// Set the setting from text. Empty text removes the limit of the depth
+    /// and comment settings. Returns `false` and leaves `options` unchanged
+    /// when the text is not a valid value.
+    pub fn set(&self, options: &mut RebuildOptions, value: &str) -> bool {
+        let value = value.trim();
+        let limit = if value.is_empty() {
+            None
+        } else {
+            match value.parse::<usize>() {
+                Ok(n) if n >= self.minimum() => Some(n),
+                _ => return false,
+            }
+        };
+
+        match (self, limit) {
+            (RebuildSetting::MaxCommentDepth, limit) => options.max_comment_depth = limit,
+            (RebuildSetting::MaxComments, limit) => options.max_comments = limit,
+            (_, None) => return false,
+            (RebuildSetting::StoryLimit, Some(n)) => options.story_limit = n,
+            (RebuildSetting::CommentsTimeout, Some(n)) => {
+                options.comments_timeout = Duration::from_secs(n as u64)
+            }
+            (RebuildSetting::StoryTimeout, Some(n)) => {
+                options.story_timeout = Duration::from_secs(n as u64)
+            }
+            (RebuildSetting::Concurrency, Some(n)) => options.concurrency = n,
+            (RebuildSetting::WriterMemory, Some(n)) => options.writer_memory = n * 1_000_000,
+        }
+        true
+    }
+
+    /// Smallest valid value. The index writer needs at least 15 MB.
+    fn minimum(&self) -> usize {
+        match self {
+            RebuildSetting::MaxCommentDepth | RebuildSetting::MaxComments => 0,
+            RebuildSetting::WriterMemory => 15,
+            _ => 1,
+        }
+    }
+}
+
 struct CommentRef {
     story_id: u64,
     comment: Item,
@@ -53,36 +266,119 @@ struct CommentRef {
 
 struct StoryRef {
     story: Item,
+    /// Rank in each category the story is listed in.
+    ranks: Vec<(ArticleType, u64)>,
+}
+
+struct PollOptionRef {
+    option: Item,
     rank: u64,
 }
 
 enum ItemRef {
     Story(StoryRef),
     Comment(CommentRef),
+    PollOption(PollOptionRef),
+    /// An indexed comment no longer reachable from its story.
+    Unreachable(u64),
+}
+
+/// What is fetched for a story when collecting it.
+enum StoryRefresh {
+    /// The story is not indexed, fetch all of its comments.
+    Full,
+    /// The comment count is unchanged, only the story and poll options are
+    /// written again.
+    StoryOnly,
+    /// The comment count changed. Comments that are indexed and unchanged
+    /// are not written again.
+    Comments(IndexedComments),
+}
+
+impl StoryRefresh {
+    /// Indexed comments of the story, or `None` when comments aren't
+    /// fetched.
+    fn into_indexed_comments(self) -> Option<IndexedComments> {
+        match self {
+            StoryRefresh::Full => Some(IndexedComments::default()),
+            StoryRefresh::StoryOnly => None,
+            StoryRefresh::Comments(comments) => Some(comments),
+        }
+    }
+}
+
+/// The indexed comments of a story being refreshed.
+#[derive(Default)]
+struct IndexedComments {
+    comments: HashMap<u64, Comment>,
+    /// How many more comments the story has than when it was indexed. The
+    /// walk stops once that many new comments were found. `None` when the
+    /// count went down so the whole tree is walked.
+    new: Option<usize>,
+}
+
+/// A story listed in the category being indexed.
+struct ListedStory {
+    story: Item,
+    /// Rank in each category the story is listed in.
+    ranks: Vec<(ArticleType, u64)>,
+    /// The indexed story. All comments are fetched without one.
+    indexed: Option<Story>,
+}
+
+/// The comment is indexed with the same text, replies and status.
+fn is_indexed(indexed: &IndexedComments, comment: &Item) -> bool {
+    indexed.comments.get(&comment.id).is_some_and(|indexed| {
+        indexed.kids == comment.kids
+            && indexed.dead == comment.dead
+            && indexed.deleted == comment.deleted
+            && indexed.body == comment.text.as_deref().unwrap_or_default()
+    })
 }
 
-pub struct WriteContext<'a> {
+pub struct WriteContext {
     writer: IndexWriter,
-    story_category: &'a str,
     fields: HackerNewsFields,
 }
 
-impl<'a> WriteContext<'a> {
-    pub fn new(
-        fields: HackerNewsFields,
-        writer: IndexWriter,
-        story_category: &'a str,
-    ) -> SearchResult<Self> {
-        Ok(Self {
-            writer,
-            story_category,
-            fields,
-        })
+impl WriteContext {
+    pub fn new(fields: HackerNewsFields, writer: IndexWriter) -> SearchResult<Self> {
+        Ok(Self { writer, fields })
     }
 
     fn write_story(&self, item: StoryRef) -> SearchResult<()> {
-        let StoryRef { story: item, rank } = item;
-        self.write_doc(&item, rank, None)
+        let StoryRef { story, ranks } = item;
+        let mut doc = self.item_doc(&story, None);
+        self.add_ranks(&mut doc, &ranks);
+        self.write_doc(story.id, doc)
+    }
+
+    /// Rewrite an indexed story document with new category ranks.
+    fn write_story_ranks(
+        &self,
+        story_doc: &TantivyDocument,
+        ranks: &[(ArticleType, u64)],
+    ) -> SearchResult<()> {
+        let id = story_doc
+            .get_first(self.fields.id)
+            .and_then(|id| id.as_u64())
+            .ok_or(SearchError::BadDoc)?;
+
+        let mut doc = TantivyDocument::new();
+        for (field, value) in story_doc.field_values().filter(|(field, _)| {
+            *field != self.fields.category && !self.fields.category_ranks.contains(field)
+        }) {
+            doc.add_field_value(field, value);
+        }
+        self.add_ranks(&mut doc, ranks);
+        self.write_doc(id, doc)
+    }
+
+    fn add_ranks(&self, doc: &mut TantivyDocument, ranks: &[(ArticleType, u64)]) {
+        for (category, rank) in ranks {
+            doc.add_text(self.fields.category, category.as_str());
+            doc.add_u64(self.fields.category_rank(*category), *rank);
+        }
     }
 
     fn write_comment(&self, comment: CommentRef) -> SearchResult<()> {
@@ -91,16 +387,33 @@ impl<'a> WriteContext<'a> {
             comment,
             rank,
         } = comment;
-        self.write_doc(&comment, rank, Some(story_id))
-            .inspect_err(|err| {
-                error!("Failed to write doc: {err}");
-            })
+        let mut doc = self.item_doc(&comment, Some(story_id));
+        doc.add_u64(self.fields.rank, rank);
+        self.write_doc(comment.id, doc).inspect_err(|err| {
+            error!("Failed to write doc: {err}");
+        })
+    }
+
+    fn write_poll_option(&self, poll_option: PollOptionRef) -> SearchResult<()> {
+        let PollOptionRef { option, rank } = poll_option;
+        let mut doc = self.item_doc(&option, None);
+        doc.add_u64(self.fields.rank, rank);
+        self.write_doc(option.id, doc)
+    }
+
+    /// Write the document for an item, replacing the item's document if it
+    /// is already indexed.
+    fn write_doc(&self, id: u64, doc: TantivyDocument) -> SearchResult<()> {
+        self.writer
+            .delete_term(Term::from_field_u64(self.fields.id, id));
+        self.writer.add_document(doc)?;
+        Ok(())
     }
 
-    fn write_doc(&self, item: &Item, rank: u64, story_id: Option<u64>) -> SearchResult<()> {
+    /// Document with the fields of an item.
+    fn item_doc(&self, item: &Item, story_id: Option<u64>) -> TantivyDocument {
         let mut doc = TantivyDocument::new();
 
-        doc.add_u64(self.fields.rank, rank);
         doc.add_u64(self.fields.id, item.id);
         if let Some(id) = item.parent {
             doc.add_u64(self.fields.parent_id, id);
@@ -125,32 +438,39 @@ impl<'a> WriteContext<'a> {
             doc.add_u64(self.fields.story_id, id);
         }
 
-        if item.ty == "story" {
-            doc.add_text(self.fields.category, self.story_category);
+        if let "story" | "poll" | "pollopt" = item.ty.as_str() {
             doc.add_u64(self.fields.score, item.score);
         }
 
+        if let Some(id) = item.poll {
+            doc.add_u64(self.fields.poll_id, id);
+        }
+
         doc.add_u64(self.fields.time, item.time);
 
         for id in &item.kids {
             doc.add_u64(self.fields.kids, *id);
         }
 
-        self.writer.add_document(doc)?;
-        Ok(())
+        doc.add_bool(self.fields.dead, item.dead);
+        doc.add_bool(self.fields.deleted, item.deleted);
+        doc
+    }
+
+    /// Delete a single comment. Its replies are kept.
+    fn delete_comment(&self, id: u64) {
+        self.writer
+            .delete_term(Term::from_field_u64(self.fields.id, id));
     }
 
-    /// Delete a story and all it's child comments.
+    /// Delete a story and all it's child comments and poll options.
     fn delete_story(&self, story: &Story) {
         self.writer
             .delete_term(Term::from_field_u64(self.fields.id, story.id));
         self.writer
             .delete_term(Term::from_field_u64(self.fields.story_id, story.id));
-    }
-
-    /// Delete all documents from the active index.
-    fn delete_all_docs(&mut self) -> SearchResult<u64> {
-        Ok(self.writer.delete_all_documents()?)
+        self.writer
+            .delete_term(Term::from_field_u64(self.fields.poll_id, story.id));
     }
 
     /// Commit changes to the index.
@@ -159,74 +479,246 @@ impl<'a> WriteContext<'a> {
         Ok(ts)
     }
 }
-/// Yield a stream of comments for the given comment_ids.
-#[cfg_attr(feature = "trace", instrument(skip_all))]
-fn comment_stream(
-    client: &ApiClient,
-    story_id: u64,
-    comment_ids: &[u64],
-) -> impl Stream<Item = CommentRef> {
-    client
-        .items(comment_ids)
-        .inspect_err(|err| {
-            error!("Failed to fetch comment: {err}");
-        })
-        .filter_map(|item| ready(item.ok()))
-        .enumerate()
-        .map(move |(index, item)| CommentRef {
-            story_id,
-            comment: item,
-            rank: index as u64,
-        })
-}
-
-/// Recurse through all child comments and send each one to the index
-/// writer channel.
+/// Fetch the descendants of `parent_id` and send each one to the index
+/// writer channel. Deleted and dead comments are kept so their replies are
+/// still reachable. Comments in `indexed` that haven't changed are skipped.
+///
+/// The firebase api has no descendant count for a comment, so a changed
+/// subtree can't be told apart before it's fetched. When the number of new
+/// comments is known the breadth first walk stops once that many live
+/// comments missing from `indexed` were found, leaving the rest of the tree
+/// unfetched.
+///
+/// Returns the id of every comment reached when the whole tree was walked,
+/// or `None` when the walk stopped early or a comment failed to fetch.
 #[cfg_attr(feature = "trace", instrument(skip_all))]
 async fn send_comments(
     client: &ApiClient,
     story_id: u64,
+    parent_id: u64,
     comment_ids: Vec<u64>,
+    tree_options: TreeOptions,
+    indexed: &IndexedComments,
     tx: Sender<ItemRef>,
-) {
-    let mut comment_stack = comment_stream(client, story_id, &comment_ids)
-        .collect::<Vec<_>>()
-        .await;
-
-    while let Some(comment) = comment_stack.pop() {
-        let children = comment_stream(client, story_id, &comment.comment.kids)
-            .collect::<Vec<_>>()
-            .await;
-        comment_stack.extend(children);
+) -> Option<HashSet<u64>> {
+    let mut comments = pin!(client.descendants_stream(parent_id, comment_ids, tree_options));
+    let mut reached = Some(HashSet::new());
+    let mut new = indexed.new;
+
+    while let Some(result) = comments.next().await {
+        let node = match result {
+            Ok(node) => node,
+            Err(err) => {
+                error!("Failed to fetch comment: {err}");
+                reached = None;
+                continue;
+            }
+        };
 
         if tx.is_closed() {
             error!("Index writer channel is closed");
-            break;
+            return None;
         }
 
+        if let Some(reached) = reached.as_mut() {
+            reached.insert(node.item.id);
+        }
+        if is_indexed(indexed, &node.item) {
+            continue;
+        }
+        let is_new =
+            !indexed.comments.contains_key(&node.item.id) && !node.item.dead && !node.item.deleted;
+
+        let comment = CommentRef {
+            story_id,
+            comment: node.item,
+            rank: node.rank as u64,
+        };
         if let Err(err) = tx.send(ItemRef::Comment(comment)).await {
             error!("Failed to send comment {err}");
         }
+
+        if is_new {
+            new = new.map(|new| new.saturating_sub(1));
+            if new == Some(0) {
+                debug!("Found the new comments of story_id {story_id}");
+                return None;
+            }
+        }
+    }
+
+    reached
+}
+
+/// Fetch the whole comment tree of a story from the item source and send
+/// each comment within the depth and comment limits to the index writer
+/// channel. Comments in `indexed` that haven't changed are skipped.
+///
+/// Returns the id of every comment within the limits, or `None` when the
+/// tree couldn't be fetched whole or sent.
+#[cfg_attr(feature = "trace", instrument(skip_all))]
+async fn send_comment_tree(
+    source: &dyn ItemSource,
+    story_id: u64,
+    tree_options: TreeOptions,
+    indexed: &IndexedComments,
+    tx: Sender<ItemRef>,
+) -> Option<HashSet<u64>> {
+    let ItemTree { items, failed } = match source.item_tree(story_id).await {
+        Ok(tree) => tree,
+        Err(err) => {
+            error!("Failed to fetch comment tree from {}: {err}", source.name());
+            return None;
+        }
+    };
+
+    // Rank each comment by its position amongst its siblings and find its
+    // depth. Parents come before their children.
+    let mut places = HashMap::from([(story_id, (0, 0))]);
+    for item in &items {
+        let depth = places.get(&item.id).map(|(depth, _)| *depth).unwrap_or(1);
+        places.extend(
+            item.kids
+                .iter()
+                .zip(0..)
+                .map(|(kid, rank)| (*kid, (depth + 1, rank))),
+        );
+    }
+
+    let comments = items
+        .into_iter()
+        .filter(|item| item.ty == "comment")
+        .filter_map(|comment| {
+            let (depth, rank) = places.get(&comment.id).copied().unwrap_or((1, 0));
+            tree_options
+                .max_depth
+                .is_none_or(|max_depth| depth <= max_depth)
+                .then_some((comment, rank))
+        })
+        .take(tree_options.max_nodes.unwrap_or(usize::MAX))
+        .collect::<Vec<_>>();
+    let reached = comments
+        .iter()
+        .map(|(comment, _)| comment.id)
+        .collect::<HashSet<_>>();
+
+    for (comment, rank) in comments {
+        if is_indexed(indexed, &comment) {
+            continue;
+        }
+        if let Err(err) = tx
+            .send(ItemRef::Comment(CommentRef {
+                story_id,
+                comment,
+                rank,
+            }))
+            .await
+        {
+            error!("Failed to send comment {err}");
+            return None;
+        }
+    }
+
+    if failed > 0 {
+        warn!("{failed} comments of story {story_id} failed to fetch");
+        return None;
+    }
+    Some(reached)
+}
+
+/// Send the options of a poll to the index writer channel.
+#[cfg_attr(feature = "trace", instrument(skip_all))]
+async fn send_poll_options(client: &ApiClient, option_ids: &[u64], tx: &Sender<ItemRef>) {
+    let options = client
+        .items(option_ids)
+        .inspect_err(|err| {
+            error!("Failed to fetch poll option: {err}");
+        })
+        .filter_map(|result| ready(result.ok()))
+        .collect::<Vec<_>>()
+        .await;
+
+    for (option, rank) in options.into_iter().zip(0..) {
+        if let Err(err) = tx
+            .send(ItemRef::PollOption(PollOptionRef { option, rank }))
+            .await
+        {
+            error!("Failed to send poll option {err}");
+        }
     }
 }
 
-/// Get the story and nested comments from the firebase REST api and send each
-/// document to the index writer channel.
+/// Get the story, poll options and nested comments from the firebase REST api
+/// and send each document to the index writer channel.
 #[cfg_attr(feature = "trace", instrument(skip_all, fields(story_id = story.id)))]
-async fn collect_story(client: Arc<ApiClient>, tx: Sender<ItemRef>, mut story: Item, rank: u64) {
+async fn collect_story(
+    client: Arc<ApiClient>,
+    tx: Sender<ItemRef>,
+    mut story: Item,
+    ranks: Vec<(ArticleType, u64)>,
+    options: RebuildOptions,
+    refresh: StoryRefresh,
+) {
     let story_id = story.id;
-    debug!("Collecting comments for story_id {story_id}");
-
-    // Collect all the nested comments for the story.
-    let result = timeout(
-        Duration::from_secs(60),
-        send_comments(&client, story.id, mem::take(&mut story.kids), tx.clone()),
-    )
-    .await
-    .map_err(|_| SearchError::TimedOut(format!("story_id {story_id}, sending comments")));
+    let comment_ids = mem::take(&mut story.kids);
+
+    // Collect the nested comments for the story.
+    if let Some(indexed) = refresh.into_indexed_comments() {
+        debug!(
+            "Collecting comments for story_id {story_id}, {} indexed, {:?} new",
+            indexed.comments.len(),
+            indexed.new
+        );
+        let send = async {
+            match ITEM_SOURCE.get() {
+                Some(source) if !comment_ids.is_empty() => {
+                    send_comment_tree(
+                        source.as_ref(),
+                        story_id,
+                        options.tree_options(),
+                        &indexed,
+                        tx.clone(),
+                    )
+                    .await
+                }
+                _ => {
+                    send_comments(
+                        &client,
+                        story_id,
+                        story_id,
+                        comment_ids,
+                        options.tree_options(),
+                        &indexed,
+                        tx.clone(),
+                    )
+                    .await
+                }
+            }
+        };
+        let result = timeout(options.comments_timeout, send)
+            .await
+            .map_err(|_| SearchError::TimedOut(format!("story_id {story_id}, sending comments")));
+
+        match result {
+            // Only a complete walk tells which indexed comments are gone.
+            Ok(Some(reached)) => {
+                for id in indexed
+                    .comments
+                    .into_keys()
+                    .filter(|id| !reached.contains(id))
+                {
+                    if let Err(err) = tx.send(ItemRef::Unreachable(id)).await {
+                        error!("Failed to send unreachable comment {err}");
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(err) => error!("{err}"),
+        }
+    }
 
-    if let Err(err) = result {
-        error!("{err}");
+    if !story.parts.is_empty() {
+        send_poll_options(&client, &story.parts, &tx).await;
     }
 
     if tx.is_closed() {
@@ -234,44 +726,61 @@ async fn collect_story(client: Arc<ApiClient>, tx: Sender<ItemRef>, mut story: I
     }
 
     // Create the story document.
-    if let Err(err) = tx.send(ItemRef::Story(StoryRef { story, rank })).await {
+    if let Err(err) = tx.send(ItemRef::Story(StoryRef { story, ranks })).await {
         error!("Failed to send story {err}");
     }
 }
 
-/// Get all stories and nested comments for the given category and send
-/// each document to the index writer channel.
-#[cfg_attr(feature = "trace", instrument(skip(tx)))]
+/// Get the nested comments for the given stories and send each document to
+/// the index writer channel. Indexed stories only have their comments
+/// fetched when the comment count changed.
+#[cfg_attr(feature = "trace", instrument(skip_all))]
 async fn collect(
+    ctx: Arc<RwLock<SearchContext>>,
     tx: Sender<ItemRef>,
-    category_type: ArticleType,
+    stories: Vec<ListedStory>,
+    options: RebuildOptions,
     mut progress_tx: mpsc::Sender<RebuildProgress>,
 ) -> SearchResult<()> {
     let client = api_client();
-    let stories = client.articles(75, category_type).await?;
     if let Err(err) = progress_tx.try_send(RebuildProgress::Started(stories.len())) {
         error!("Failed to send progress status: {err}");
     }
 
-    info!("Building {} top docs for {category_type}", stories.len());
-
-    let mut handles = stories
-        .into_iter()
-        .zip(1..)
-        .map(|(story, rank)| {
+    info!(
+        "Building {} top docs, {} indexed",
+        stories.len(),
+        stories
+            .iter()
+            .filter(|listed| listed.indexed.is_some())
+            .count()
+    );
+
+    // Stories are only started, and their timeout only runs, once there
+    // is room within the concurrency limit.
+    let mut handles = stream::iter(stories)
+        .map(|listed| {
+            let ListedStory {
+                story,
+                ranks,
+                indexed,
+            } = listed;
             let client = client.clone();
             let tx = tx.clone();
             let story_id = story.id;
+            let refresh = story_refresh(&ctx, indexed.as_ref(), &story);
             timeout(
-                Duration::from_secs(60 * 3),
+                options.story_timeout,
                 #[cfg(feature = "trace")]
-                tokio::spawn(collect_story(client, tx, story, rank).in_current_span()),
+                tokio::spawn(
+                    collect_story(client, tx, story, ranks, options, refresh).in_current_span(),
+                ),
                 #[cfg(not(feature = "trace"))]
-                tokio::spawn(collect_story(client, tx, story, rank)),
+                tokio::spawn(collect_story(client, tx, story, ranks, options, refresh)),
             )
             .map_err(move |_| SearchError::TimedOut(format!("collecting story: {story_id}")))
         })
-        .collect::<FuturesUnordered<_>>();
+        .buffer_unordered(options.concurrency.max(1));
 
     while let Some(result) = handles.next().await {
         if let Err(err) = result.and_then(|r| Ok(r?)) {
@@ -291,36 +800,149 @@ async fn collect(
     Ok(())
 }
 
+/// Decide what to fetch for a story from its indexed version.
+fn story_refresh(
+    ctx: &RwLock<SearchContext>,
+    indexed: Option<&Story>,
+    story: &Item,
+) -> StoryRefresh {
+    match indexed {
+        None => StoryRefresh::Full,
+        Some(indexed) if indexed.descendants == story.descendants.unwrap_or_default() => {
+            StoryRefresh::StoryOnly
+        }
+        Some(indexed) => match ctx.read().unwrap().indexed_comments(story.id) {
+            Ok(comments) => StoryRefresh::Comments(IndexedComments {
+                comments,
+                new: story
+                    .descendants
+                    .unwrap_or_default()
+                    .checked_sub(indexed.descendants)
+                    .map(|new| new as usize),
+            }),
+            Err(err) => {
+                error!("Failed to read indexed comments for {}: {err}", story.id);
+                StoryRefresh::Full
+            }
+        },
+    }
+}
+
 async fn write_items(
     mut rx: Receiver<ItemRef>,
-    writer_context: &mut WriteContext<'_>,
+    writer_context: &mut WriteContext,
 ) -> SearchResult<()> {
     while let Some(item) = rx.recv().await {
         match item {
             ItemRef::Story(s) => writer_context.write_story(s)?,
             ItemRef::Comment(c) => writer_context.write_comment(c)?,
+            ItemRef::PollOption(p) => writer_context.write_poll_option(p)?,
+            ItemRef::Unreachable(id) => writer_context.delete_comment(id),
         }
     }
     Ok(())
 }
 
+/// Rebuild a category. Stories only listed in the category are removed
+/// and the listed stories are fetched again with all their comments.
 #[cfg_attr(feature = "trace", instrument(skip(ctx)))]
 pub async fn rebuild_index(
     ctx: Arc<RwLock<SearchContext>>,
     category_type: ArticleType,
+    options: RebuildOptions,
     progress_tx: mpsc::Sender<RebuildProgress>,
+) -> SearchResult<IndexStats> {
+    info!("Creating index for {category_type} with {options:?}");
+    index_category(ctx, category_type, options, progress_tx, true).await
+}
+
+/// Refresh a category in place. Stories that fell off the category are
+/// removed, or only lose their rank in it when listed in another category,
+/// and indexed stories have their rank and score updated. New stories are
+/// fetched with all their comments. The comment tree of an indexed story is
+/// only walked when its comment count changed, until as many new comments
+/// were found as the count grew by, and only new or changed comments are
+/// written. When the whole tree was walked, indexed comments that are no
+/// longer reachable are removed. Changes are committed once at the end so
+/// the index stays readable throughout.
+#[cfg_attr(feature = "trace", instrument(skip(ctx)))]
+pub async fn refresh_index(
+    ctx: Arc<RwLock<SearchContext>>,
+    category_type: ArticleType,
+    options: RebuildOptions,
+    progress_tx: mpsc::Sender<RebuildProgress>,
+) -> SearchResult<IndexStats> {
+    info!("Refreshing index for {category_type} with {options:?}");
+    index_category(ctx, category_type, options, progress_tx, false).await
+}
+
+/// Index the stories listed in a category. Stories indexed for another
+/// category are reused unless `full` is set.
+async fn index_category(
+    ctx: Arc<RwLock<SearchContext>>,
+    category_type: ArticleType,
+    options: RebuildOptions,
+    progress_tx: mpsc::Sender<RebuildProgress>,
+    full: bool,
 ) -> SearchResult<IndexStats> {
     let start_time = Instant::now();
-    info!("Creating index for {category_type}");
 
-    let mut writer_context = ctx.read().unwrap().writer_context()?;
-    writer_context.delete_all_docs()?;
+    let stories = api_client()
+        .articles(options.story_limit, category_type)
+        .await?;
+    let current = stories.iter().map(|story| story.id).collect::<HashSet<_>>();
+
+    let (stories, mut writer_context) = {
+        let g = ctx.read().unwrap();
+        let mut indexed = g.indexed_stories()?;
+        let writer_context = g.writer_context_with_memory(options.writer_memory)?;
+
+        let mut removed = 0;
+        for indexed_story in indexed.values() {
+            let story = &indexed_story.story;
+            let listed = current.contains(&story.id);
+            let other_ranks = indexed_story.other_ranks(category_type);
+            if listed && full {
+                writer_context.delete_story(story);
+            } else if listed || other_ranks.len() == indexed_story.ranks.len() {
+                // Listed or not ranked in this category.
+            } else if other_ranks.is_empty() {
+                writer_context.delete_story(story);
+                removed += 1;
+            } else {
+                writer_context.write_story_ranks(&g.story_doc(story.id)?, &other_ranks)?;
+            }
+        }
+        info!("Removing {removed} stories no longer in {category_type}");
+
+        let stories = stories
+            .into_iter()
+            .zip(1..)
+            .map(|(story, rank)| {
+                let indexed = indexed.remove(&story.id);
+                let mut ranks = indexed
+                    .as_ref()
+                    .map(|indexed| indexed.other_ranks(category_type))
+                    .unwrap_or_default();
+                ranks.push((category_type, rank));
+                ListedStory {
+                    story,
+                    ranks,
+                    indexed: indexed
+                        .filter(|_| !full)
+                        .map(|IndexedStory { story, .. }| story),
+                }
+            })
+            .collect::<Vec<_>>();
+        (stories, writer_context)
+    };
 
     let (tx, rx) = channel::<ItemRef>(100);
     #[cfg(feature = "trace")]
-    let result = tokio::spawn(collect(tx, category_type, progress_tx).in_current_span());
+    let result =
+        tokio::spawn(collect(ctx.clone(), tx, stories, options, progress_tx).in_current_span());
     #[cfg(not(feature = "trace"))]
-    let result = tokio::spawn(collect(tx, category_type, progress_tx));
+    let result = tokio::spawn(collect(ctx.clone(), tx, stories, options, progress_tx));
 
     let writing_result = write_items(rx, &mut writer_context).await;
 
@@ -331,6 +953,13 @@ pub async fn rebuild_index(
 
     writer_context.commit()?;
 
+    if let Some(cache) = api_client().item_cache() {
+        info!(
+            "Item cache after indexing {category_type}: {:?}",
+            cache.stats()
+        );
+    }
+
     let g = ctx.read().unwrap();
     g.refresh_reader()?;
     document_stats(&g, start_time.elapsed(), category_type)
@@ -346,6 +975,7 @@ pub enum RebuildProgress {
 pub async fn update_story(
     ctx: Arc<RwLock<SearchContext>>,
     story: Story,
+    options: RebuildOptions,
 ) -> SearchResult<Option<Story>> {
     let api = api_client();
     let latest = api.item(story.id).await?;
@@ -357,8 +987,7 @@ pub async fn update_story(
             latest.descendants.unwrap_or_default()
         );
 
-        let writer_context = ctx.read().unwrap().writer_context()?;
-        rebuild_story(api, writer_context, &story, latest).await?;
+        rebuild_story(api, &ctx, &story, latest, options).await?;
         info!("Rebuilt story {story_id}");
         let g = ctx.read().unwrap();
         g.refresh_reader()?;
@@ -368,18 +997,49 @@ pub async fn update_story(
     })
 }
 
+/// Writer for the index once no other task is writing. Only one writer can
+/// be open at a time and watched items are written as they change, so a
+/// busy index is retried for a while.
+async fn shared_writer_context(ctx: &RwLock<SearchContext>) -> SearchResult<WriteContext> {
+    const WAIT: Duration = Duration::from_secs(30);
+    let deadline = Instant::now() + WAIT;
+
+    loop {
+        let result = ctx.read().unwrap().writer_context();
+        match result {
+            Err(SearchError::Tantivy(TantivyError::LockFailure(LockError::LockBusy, _)))
+                if Instant::now() < deadline =>
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Re-index this story along with all it's nested comments. Comments
-/// will be be fetched recursively and concurrently.
+/// will be be fetched recursively and concurrently with `options`. The
+/// story keeps its category ranks.
 async fn rebuild_story(
     client: Arc<ApiClient>,
-    mut writer_context: WriteContext<'_>,
+    ctx: &RwLock<SearchContext>,
     story: &Story,
     latest: Item,
+    options: RebuildOptions,
 ) -> SearchResult<()> {
+    let mut writer_context = shared_writer_context(ctx).await?;
+    let ranks = ctx.read().unwrap().story_ranks(story.id)?;
     writer_context.delete_story(story);
     let (tx, rx) = channel::<ItemRef>(100);
 
-    let result = tokio::spawn(collect_story(client, tx, latest, story.rank));
+    let result = tokio::spawn(collect_story(
+        client,
+        tx,
+        latest,
+        ranks,
+        options,
+        StoryRefresh::Full,
+    ));
 
     write_items(rx, &mut writer_context).await?;
 
@@ -394,6 +1054,7 @@ async fn handle_story_events(
     ctx: Arc<RwLock<SearchContext>>,
     client: Arc<ApiClient>,
     story: Story,
+    options: RebuildOptions,
     mut ui_tx: mpsc::Sender<Story>,
     mut rx: Receiver<ItemEventData>,
 ) -> SearchResult<()> {
@@ -418,8 +1079,7 @@ async fn handle_story_events(
 
         // We'll rebuild this story if either the number of comments or score has changed.
         if latest_descendants != current_story.descendants || latest.score != current_story.score {
-            let writer_context = ctx.read().unwrap().writer_context()?;
-            match rebuild_story(client.clone(), writer_context, &current_story, latest).await {
+            match rebuild_story(client.clone(), &ctx, &current_story, latest, options).await {
                 Ok(_) => {
                     current_story.descendants = latest_descendants;
                     let new_story = {
@@ -457,19 +1117,31 @@ async fn handle_comment_events(
         let mut comment = comment.clone();
         let (tx_comment, rx_comment) = channel(10);
         let story_id = comment.story_id;
+        let comment_id = comment.id;
 
         let child_ids = item_event.data.kids.clone();
 
         let client = client.clone();
         let result = tokio::spawn(async move {
             let client = client.clone();
-            send_comments(&client, story_id, child_ids, tx_comment.clone()).await;
+            send_comments(
+                &client,
+                story_id,
+                comment_id,
+                child_ids,
+                TreeOptions::default(),
+                &IndexedComments::default(),
+                tx_comment.clone(),
+            )
+            .await;
         });
 
-        let mut writer_context = ctx.read().unwrap().writer_context()?;
+        let mut writer_context = shared_writer_context(&ctx).await?;
         write_items(rx_comment, &mut writer_context).await?;
 
         result.await?;
+        writer_context.commit()?;
+        ctx.read().unwrap().refresh_reader()?;
 
         comment.kids = item_event.data.kids;
         if let Err(err) = ui_tx.send(comment).await {
@@ -484,33 +1156,29 @@ async fn handle_comment_events(
 pub struct WatchState<const N: usize, EventData> {
     pub abort_handles: [AbortHandle; N],
     pub receiver: mpsc::Receiver<EventData>,
+    /// Connection state of the item event subscription.
+    pub status: watch::Receiver<SubscriptionStatus>,
 }
 
 pub fn watch_story(
     ctx: Arc<RwLock<SearchContext>>,
     story: Story,
+    options: RebuildOptions,
 ) -> SearchResult<WatchState<2, Story>> {
     let client = api_client();
-    let (tx, rx) = channel(10);
     let (ui_tx, ui_rx) = mpsc::channel::<Story>(10);
-    let story_id = story.id;
-    let c = client.clone();
+    let (tx, receiver) = channel(10);
 
     Ok(WatchState {
         receiver: ui_rx,
+        status: change_feed().status(),
         abort_handles: [
-            tokio::spawn(async move {
-                c.item_stream(story_id, tx)
-                    .inspect_err(|err| error!("Failed to subscribe to story events: {err}"))
-                    .await
-            })
-            .abort_handle(),
+            tokio::spawn(forward_item_changes(client.clone(), story.id, tx)).abort_handle(),
             tokio::spawn(
-                handle_story_events(ctx.clone(), client.clone(), story, ui_tx, rx).inspect_err(
-                    |err| {
+                handle_story_events(ctx.clone(), client.clone(), story, options, ui_tx, receiver)
+                    .inspect_err(|err| {
                         error!("Story event handler encountered an error: {err}");
-                    },
-                ),
+                    }),
             )
             .abort_handle(),
         ],
@@ -522,22 +1190,16 @@ pub fn watch_comment(
     comment: Comment,
 ) -> SearchResult<WatchState<2, Comment>> {
     let client = api_client();
-    let (tx, rx) = channel(10);
     let (ui_tx, ui_rx) = mpsc::channel::<Comment>(10);
-    let comment_id = comment.id;
-    let c = client.clone();
+    let (tx, receiver) = channel(10);
 
     Ok(WatchState {
         receiver: ui_rx,
+        status: change_feed().status(),
         abort_handles: [
-            tokio::spawn(async move {
-                c.item_stream(comment_id, tx)
-                    .inspect_err(|err| error!("Failed to subscribe to item events: {err}"))
-                    .await
-            })
-            .abort_handle(),
+            tokio::spawn(forward_item_changes(client.clone(), comment.id, tx)).abort_handle(),
             tokio::spawn(
-                handle_comment_events(ctx, client, comment, ui_tx, rx)
+                handle_comment_events(ctx, client, comment, ui_tx, receiver)
                     .inspect_err(|err| error!("Comment event handler encountered an error: {err}")),
             )
             .abort_handle(),
@@ -545,24 +1207,53 @@ pub fn watch_comment(
     })
 }
 
+/// Count the stories listed in a category and their comments and poll
+/// options.
 pub fn document_stats(
     ctx: &SearchContext,
     build_time: Duration,
     category: ArticleType,
 ) -> SearchResult<IndexStats> {
-    let searcher = if ctx.active_index == category {
-        ctx.searcher()
-    } else {
-        ctx.indices.get_index(category).reader()?.searcher()
+    let searcher = ctx.searcher();
+    let fields = ctx.fields;
+
+    let term_query =
+        |term: Term| -> Box<dyn Query> { Box::new(TermQuery::new(term, IndexRecordOption::Basic)) };
+    let category_term = Term::from_field_text(fields.category, category.as_str());
+    let count_type = |ty: &str| -> SearchResult<u64> {
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, term_query(category_term.clone())),
+            (
+                Occur::Must,
+                term_query(Term::from_field_text(fields.ty, ty)),
+            ),
+        ]);
+        Ok(searcher.search(&query, &Count)? as u64)
     };
-
-    let type_field = ctx.schema.get_field(ITEM_TYPE)?;
-
-    let total_comments = searcher.doc_freq(&Term::from_field_text(type_field, "comment"))?;
-    let total_jobs = searcher.doc_freq(&Term::from_field_text(type_field, "job"))?;
-    let total_stories = searcher.doc_freq(&Term::from_field_text(type_field, "story"))?;
-    let total_polls = searcher.doc_freq(&Term::from_field_text(type_field, "poll"))?;
-    let total_documents = searcher.num_docs();
+    let total_jobs = count_type("job")?;
+    let total_stories = count_type("story")?;
+    let total_polls = count_type("poll")?;
+
+    let story_ids = searcher
+        .search(
+            &TermQuery::new(category_term, IndexRecordOption::Basic),
+            &DocSetCollector,
+        )?
+        .into_iter()
+        .map(|doc_address| {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            doc.get_first(fields.id)
+                .and_then(|id| id.as_u64())
+                .ok_or(SearchError::BadDoc)
+        })
+        .collect::<SearchResult<Vec<_>>>()?;
+    let count_children = |field| -> SearchResult<u64> {
+        let query = TermSetQuery::new(story_ids.iter().map(|id| Term::from_field_u64(field, *id)));
+        Ok(searcher.search(&query, &Count)? as u64)
+    };
+    let total_comments = count_children(fields.story_id)?;
+    let total_documents =
+        total_jobs + total_stories + total_polls + total_comments + count_children(fields.poll_id)?;
 
     Ok(IndexStats {
         total_documents,
@@ -1,10 +1,10 @@
 //! Search document storage and retrieval.
-use hacker_news_api::ArticleType;
-use log::info;
-use std::{fs::create_dir_all, path::Path};
+use hacker_news_api::{ApiError, ArticleType};
+use log::{error, info};
+use std::{fmt, fs::create_dir_all, path::Path};
 use tantivy::{
     Index, IndexReader, Searcher, TantivyError,
-    directory::{MmapDirectory, error::OpenDirectoryError},
+    directory::error::OpenDirectoryError,
     query::{QueryParser, QueryParserError},
     schema::{
         FAST, Field, INDEXED, IndexRecordOption, STORED, STRING, Schema, TEXT, TextFieldIndexing,
@@ -15,6 +15,8 @@ use thiserror::Error;
 
 pub mod api;
 pub mod create_index;
+mod migrate;
+pub mod query;
 
 pub use create_index::*;
 
@@ -34,32 +36,70 @@ pub struct HackerNewsFields {
     story_id: Field,
     kids: Field,
     score: Field,
+    dead: Field,
+    deleted: Field,
+    poll_id: Field,
+    /// Rank of a story in each category in [`ArticleType::ALL`] order.
+    category_ranks: [Field; 6],
 }
 
-/// The indices for each category
-pub struct HackerNewsIndices {
-    top: Index,
-    ask: Index,
-    best: Index,
-    job: Index,
-    new: Index,
-    show: Index,
+impl HackerNewsFields {
+    /// Field with the rank of stories listed in `category`.
+    fn category_rank(&self, category: ArticleType) -> Field {
+        self.category_ranks[category as usize]
+    }
+}
+
+/// Directory of the index shared by all categories.
+const INDEX_DIR: &str = "All";
+
+/// Version of [`document_schema`] stored next to the index. Bump it when
+/// the fields change so existing indices are migrated when opened. Version 1
+/// was a separate index for each category.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// State of the index after opening it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexState {
+    /// The index has the current schema.
+    Ready,
+    /// Documents were copied from an index with an older schema version.
+    /// Fields added since are empty until the stories are indexed again.
+    Migrated { from: Option<u32> },
+    /// The index could not be migrated and was recreated empty. The
+    /// categories need to be rebuilt.
+    Rebuild { from: Option<u32> },
 }
 
-impl HackerNewsIndices {
-    /// Get the index for the article type.
-    pub fn get_index(&self, article_type: ArticleType) -> &Index {
-        match article_type {
-            ArticleType::New => &self.new,
-            ArticleType::Best => &self.best,
-            ArticleType::Top => &self.top,
-            ArticleType::Ask => &self.ask,
-            ArticleType::Show => &self.show,
-            ArticleType::Job => &self.job,
+impl IndexState {
+    /// The index is empty and the categories need to be rebuilt.
+    pub fn needs_rebuild(&self) -> bool {
+        matches!(self, IndexState::Rebuild { .. })
+    }
+}
+
+impl fmt::Display for IndexState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexState::Ready => write!(f, "Index is up to date"),
+            IndexState::Migrated { from: Some(from) } => write!(
+                f,
+                "Index migrated from schema version {from} to {SCHEMA_VERSION}"
+            ),
+            IndexState::Migrated { from: None } => {
+                write!(f, "Index migrated to schema version {SCHEMA_VERSION}")
+            }
+            IndexState::Rebuild { .. } => write!(
+                f,
+                "Index could not be migrated to schema version {SCHEMA_VERSION}, rebuilding"
+            ),
         }
     }
 }
 
+/// Default memory budget of an index writer in bytes.
+pub const DEFAULT_WRITER_MEMORY: usize = 50_000_000;
+
 pub const ITEM_ID: &str = "id";
 pub const ITEM_PARENT_ID: &str = "parent_id";
 pub const ITEM_TITLE: &str = "title";
@@ -74,6 +114,14 @@ pub const ITEM_TIME: &str = "time";
 pub const ITEM_STORY_ID: &str = "story_id";
 pub const ITEM_KIDS: &str = "kids";
 pub const ITEM_SCORE: &str = "score";
+pub const ITEM_DEAD: &str = "dead";
+pub const ITEM_DELETED: &str = "deleted";
+pub const ITEM_POLL_ID: &str = "poll_id";
+
+/// Name of the rank field of a category.
+fn category_rank_name(category: ArticleType) -> String {
+    format!("{ITEM_RANK}_{}", category.as_str().to_lowercase())
+}
 
 #[derive(Debug, Error)]
 pub enum SearchError {
@@ -82,9 +130,11 @@ pub enum SearchError {
     #[error("Failed to open directory: {0}")]
     OpenDirectory(#[from] OpenDirectoryError),
     #[error("API client error: {0}")]
-    Client(#[from] anyhow::Error),
+    Client(#[from] ApiError),
     #[error("Bad query: {0}")]
     Query(#[from] QueryParserError),
+    #[error("Bad search: {0}")]
+    StoryQuery(#[from] query::QueryError),
     #[error("Failed to create index folder")]
     IO(#[from] std::io::Error),
     #[error("Failed to transform doc")]
@@ -105,54 +155,67 @@ type SearchResult<T> = Result<T, SearchError>;
 pub struct SearchContext {
     reader: IndexReader,
     schema: Schema,
-    indices: HackerNewsIndices,
-    active_index: ArticleType,
+    index: Index,
+    active_category: ArticleType,
     fields: HackerNewsFields,
+    index_state: IndexState,
 }
 
-fn create_indices(base_path: &Path, schema: &Schema) -> SearchResult<HackerNewsIndices> {
-    let create_index = |article_type: ArticleType| -> SearchResult<Index> {
-        let key = article_type.as_str();
-        let full_path = base_path.join(key);
-        if !full_path.exists() {
-            info!("Creating directory {full_path:?} for index {key}");
-            create_dir_all(full_path)?;
-        }
+/// Open the index shared by all categories, migrating an index with an
+/// older schema version and moving the documents of the per category
+/// indices of version 1 into it. Per category indices that can't be moved
+/// are removed and the categories need to be rebuilt.
+fn open_index(
+    base_path: &Path,
+    schema: &Schema,
+    fields: HackerNewsFields,
+) -> SearchResult<(Index, IndexState)> {
+    let full_path = base_path.join(INDEX_DIR);
+    if !full_path.exists() {
+        info!("Creating directory {full_path:?} for index");
+        create_dir_all(&full_path)?;
+    }
 
-        let index =
-            Index::open_or_create(MmapDirectory::open(base_path.join(key))?, schema.clone())?;
-        Ok(index)
+    let (index, state) = migrate::open_versioned(&full_path, schema)?;
+    let state = match migrate::migrate_category_indices(base_path, &index, fields) {
+        Ok(true) if state == IndexState::Ready => IndexState::Migrated { from: Some(1) },
+        Ok(_) => state,
+        Err(err) => {
+            error!("Failed to migrate the category indices, rebuilding: {err}");
+            if let Err(err) = migrate::remove_category_indices(base_path) {
+                error!("Failed to remove the category indices: {err}");
+            }
+            IndexState::Rebuild { from: Some(1) }
+        }
     };
-
-    Ok(HackerNewsIndices {
-        top: create_index(ArticleType::Top)?,
-        ask: create_index(ArticleType::Ask)?,
-        best: create_index(ArticleType::Best)?,
-        job: create_index(ArticleType::Job)?,
-        new: create_index(ArticleType::New)?,
-        show: create_index(ArticleType::Show)?,
-    })
+    Ok((index, state))
 }
 
 impl SearchContext {
-    pub fn new(index_path: &Path, active_index: ArticleType) -> SearchResult<Self> {
+    pub fn new(index_path: &Path, active_category: ArticleType) -> SearchResult<Self> {
         let (schema, fields) = document_schema();
-        let indices = create_indices(index_path, &schema)?;
-        let reader = indices.get_index(active_index).reader()?;
+        let (index, index_state) = open_index(index_path, &schema, fields)?;
+        let reader = index.reader()?;
 
         Ok(SearchContext {
             reader,
-            active_index,
-            indices,
+            active_category,
+            index,
             schema,
             fields,
+            index_state,
         })
     }
 
-    pub fn activate_index(&mut self, active_index: ArticleType) -> SearchResult<()> {
-        self.active_index = active_index;
-        self.reader = self.indices.get_index(active_index).reader()?;
-        Ok(())
+    /// State of the index when it was opened.
+    pub fn index_state(&self) -> IndexState {
+        self.index_state
+    }
+
+    /// Set the category being viewed. Stories found outside of a category
+    /// listing are ranked by it.
+    pub fn activate_index(&mut self, active_category: ArticleType) {
+        self.active_category = active_category;
     }
 
     pub fn searcher(&self) -> Searcher {
@@ -163,26 +226,26 @@ impl SearchContext {
         let title = self.fields.title;
         let body = self.fields.body;
 
-        QueryParser::for_index(self.indices.get_index(self.active_index), vec![title, body])
+        QueryParser::for_index(&self.index, vec![title, body])
     }
 
-    /// Get the active index category.
+    /// Get the category being viewed.
     pub fn active_category(&self) -> ArticleType {
-        self.active_index
+        self.active_category
     }
 
-    /// Get the total number of documents in the active index.
+    /// Get the total number of documents in the index.
     pub fn doc_count(&self) -> u64 {
         self.reader.searcher().num_docs()
     }
 
-    pub fn writer_context(&self) -> SearchResult<WriteContext<'static>> {
-        let index = self.indices.get_index(self.active_index);
-        WriteContext::new(
-            self.fields,
-            index.writer(50_000_000)?,
-            self.active_index.as_str(),
-        )
+    pub fn writer_context(&self) -> SearchResult<WriteContext> {
+        self.writer_context_with_memory(DEFAULT_WRITER_MEMORY)
+    }
+
+    /// Writer for the index with a memory budget in bytes.
+    pub fn writer_context_with_memory(&self, memory: usize) -> SearchResult<WriteContext> {
+        WriteContext::new(self.fields, self.index.writer(memory)?)
     }
 
     pub fn refresh_reader(&self) -> SearchResult<()> {
@@ -216,11 +279,17 @@ fn document_schema() -> (Schema, HackerNewsFields) {
         ty: schema_builder.add_text_field(ITEM_TYPE, TEXT | STORED),
         rank: schema_builder.add_u64_field(ITEM_RANK, STORED | INDEXED | FAST),
         descendant_count: schema_builder.add_u64_field(ITEM_DESCENDANT_COUNT, STORED | INDEXED),
-        category: schema_builder.add_text_field(ITEM_CATEGORY, STRING),
+        category: schema_builder.add_text_field(ITEM_CATEGORY, STRING | STORED),
         time: schema_builder.add_u64_field(ITEM_TIME, STORED | INDEXED | FAST),
         story_id: schema_builder.add_u64_field(ITEM_STORY_ID, FAST | INDEXED | STORED),
         kids: schema_builder.add_u64_field(ITEM_KIDS, FAST | INDEXED | STORED),
         score: schema_builder.add_u64_field(ITEM_SCORE, INDEXED | STORED),
+        dead: schema_builder.add_bool_field(ITEM_DEAD, INDEXED | STORED),
+        deleted: schema_builder.add_bool_field(ITEM_DELETED, INDEXED | STORED),
+        poll_id: schema_builder.add_u64_field(ITEM_POLL_ID, INDEXED | STORED),
+        category_ranks: ArticleType::ALL.map(|category| {
+            schema_builder.add_u64_field(&category_rank_name(category), STORED | INDEXED | FAST)
+        }),
     };
 
     (schema_builder.build(), fields)
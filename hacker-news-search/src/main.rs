@@ -41,7 +41,7 @@ fn comments() -> anyhow::Result<()> {
 
 fn top_stories() -> anyhow::Result<()> {
     let ctx = SearchContext::new(Path::new(INDEX_PATH), ArticleType::Top)?;
-    dbg!(ctx.top_stories(100, 0)?);
+    dbg!(ctx.top_stories(ArticleType::Top, 100, 0)?);
 
     Ok(())
 }
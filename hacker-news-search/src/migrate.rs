@@ -0,0 +1,237 @@
+//! Migration of indices created with an older schema.
+use crate::{
+    DEFAULT_WRITER_MEMORY, HackerNewsFields, ITEM_CATEGORY, ITEM_ID, ITEM_RANK, ITEM_TYPE,
+    IndexState, SCHEMA_VERSION, SearchResult,
+};
+use hacker_news_api::ArticleType;
+use log::{error, info};
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, read_to_string, remove_dir_all, rename, write},
+    path::Path,
+};
+use tantivy::{
+    Index, IndexWriter, TantivyDocument, Term,
+    collector::DocSetCollector,
+    query::AllQuery,
+    schema::{Schema, Value as _},
+};
+
+/// File next to the index files with the schema version of the index.
+const SCHEMA_VERSION_FILE: &str = "schema_version";
+
+/// Open the index in `path` with `schema`. The documents of an index with
+/// another schema version are copied into a new index. An index that can't
+/// be read or copied is replaced with an empty one.
+pub(crate) fn open_versioned(path: &Path, schema: &Schema) -> SearchResult<(Index, IndexState)> {
+    if !path.join("meta.json").exists() {
+        let index = Index::create_in_dir(path, schema.clone())?;
+        write_schema_version(path)?;
+        return Ok((index, IndexState::Ready));
+    }
+
+    let version = read_to_string(path.join(SCHEMA_VERSION_FILE))
+        .ok()
+        .and_then(|version| version.trim().parse::<u32>().ok());
+
+    let migrated = match Index::open_in_dir(path) {
+        // Indices created before the version was stored have no version.
+        Ok(index)
+            if index.schema() == *schema
+                && version.is_none_or(|version| version == SCHEMA_VERSION) =>
+        {
+            if version.is_none() {
+                write_schema_version(path)?;
+            }
+            return Ok((index, IndexState::Ready));
+        }
+        Ok(index) => {
+            info!("Migrating index from schema version {version:?} to {SCHEMA_VERSION}");
+            migrate_documents(path, index, schema)
+        }
+        Err(err) => Err(err.into()),
+    };
+
+    match migrated {
+        Ok(index) => Ok((index, IndexState::Migrated { from: version })),
+        Err(err) => {
+            error!("Failed to migrate index, recreating it: {err}");
+            remove_dir_all(path)?;
+            create_dir_all(path)?;
+            let index = Index::create_in_dir(path, schema.clone())?;
+            write_schema_version(path)?;
+            Ok((index, IndexState::Rebuild { from: version }))
+        }
+    }
+}
+
+fn write_schema_version(path: &Path) -> SearchResult<()> {
+    Ok(write(
+        path.join(SCHEMA_VERSION_FILE),
+        SCHEMA_VERSION.to_string(),
+    )?)
+}
+
+/// Copy the documents of `index` into a new index with `schema` and
+/// replace `index` with it once all documents are copied.
+fn migrate_documents(path: &Path, index: Index, schema: &Schema) -> SearchResult<Index> {
+    let migrating_path = path.with_extension("migrating");
+    if migrating_path.exists() {
+        remove_dir_all(&migrating_path)?;
+    }
+    create_dir_all(&migrating_path)?;
+
+    let migrated = Index::create_in_dir(&migrating_path, schema.clone())?;
+    let mut writer: IndexWriter = migrated.writer(DEFAULT_WRITER_MEMORY)?;
+    let searcher = index.reader()?.searcher();
+    let mut count = 0;
+    for doc_address in searcher.search(&AllQuery, &DocSetCollector)? {
+        let doc: TantivyDocument = searcher.doc(doc_address)?;
+        writer.add_document(convert_doc(&doc, &index.schema(), schema, &[]))?;
+        count += 1;
+    }
+    writer.commit()?;
+    writer.wait_merging_threads()?;
+    info!("Copied {count} documents");
+
+    // Close both indices before swapping their directories.
+    drop((searcher, index, migrated));
+    let previous_path = path.with_extension("previous");
+    if previous_path.exists() {
+        remove_dir_all(&previous_path)?;
+    }
+    rename(path, &previous_path)?;
+    rename(&migrating_path, path)?;
+    remove_dir_all(previous_path)?;
+    write_schema_version(path)?;
+
+    Ok(Index::open_in_dir(path)?)
+}
+
+/// Copy the documents of the per category indices under `base_path` into
+/// the shared `index` and remove the per category directories. A story
+/// indexed in several categories becomes a single document ranked in each
+/// of them. Does nothing once the per category directories are gone.
+/// Returns whether any documents were migrated.
+pub(crate) fn migrate_category_indices(
+    base_path: &Path,
+    index: &Index,
+    fields: HackerNewsFields,
+) -> SearchResult<bool> {
+    let category_indices = ArticleType::ALL
+        .into_iter()
+        .map(|category| (category, base_path.join(category.as_str())))
+        .filter(|(_, path)| path.join("meta.json").exists())
+        .map(|(category, path)| Ok((category, Index::open_in_dir(&path)?)))
+        .collect::<SearchResult<Vec<_>>>()?;
+
+    if category_indices.is_empty() {
+        return Ok(false);
+    }
+    info!(
+        "Migrating {} category indices to the shared index",
+        category_indices.len()
+    );
+
+    let schema = index.schema();
+    let mut writer: IndexWriter = index.writer(DEFAULT_WRITER_MEMORY)?;
+    // Stories are written last once their rank in every category is known.
+    let mut stories = HashMap::<u64, (TantivyDocument, Vec<(ArticleType, u64)>)>::new();
+
+    for (category, category_index) in &category_indices {
+        let category_schema = category_index.schema();
+        let searcher = category_index.reader()?.searcher();
+
+        for doc_address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let category_doc: TantivyDocument = searcher.doc(doc_address)?;
+            let value = |name: &str| {
+                let field = category_schema.get_field(name).ok()?;
+                category_doc.get_first(field)
+            };
+            let Some(id) = value(ITEM_ID).and_then(|id| id.as_u64()) else {
+                continue;
+            };
+
+            if let Some("story" | "job" | "poll") = value(ITEM_TYPE).and_then(|ty| ty.as_str()) {
+                let rank = value(ITEM_RANK)
+                    .and_then(|rank| rank.as_u64())
+                    .unwrap_or_default();
+                stories
+                    .entry(id)
+                    .or_insert_with(|| {
+                        (
+                            convert_doc(
+                                &category_doc,
+                                &category_schema,
+                                &schema,
+                                &[ITEM_RANK, ITEM_CATEGORY],
+                            ),
+                            Vec::new(),
+                        )
+                    })
+                    .1
+                    .push((*category, rank));
+            } else {
+                writer.delete_term(Term::from_field_u64(fields.id, id));
+                writer.add_document(convert_doc(&category_doc, &category_schema, &schema, &[]))?;
+            }
+        }
+    }
+
+    for (id, (mut doc, ranks)) in stories {
+        for (category, rank) in ranks {
+            doc.add_text(fields.category, category.as_str());
+            doc.add_u64(fields.category_rank(category), rank);
+        }
+        writer.delete_term(Term::from_field_u64(fields.id, id));
+        writer.add_document(doc)?;
+    }
+    writer.commit()?;
+
+    // Close the category indices before removing their directories.
+    drop(category_indices);
+    remove_category_indices(base_path)?;
+
+    Ok(true)
+}
+
+/// Remove the per category index directories under `base_path`.
+pub(crate) fn remove_category_indices(base_path: &Path) -> SearchResult<()> {
+    for category in ArticleType::ALL {
+        let path = base_path.join(category.as_str());
+        if path.exists() {
+            info!("Removing {category} index");
+            remove_dir_all(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy the stored fields of a document into a document for the `to`
+/// schema. Fields named in `skip` and fields that are missing or have
+/// another value type in `to` are left out.
+fn convert_doc(
+    doc: &TantivyDocument,
+    from: &Schema,
+    to: &Schema,
+    skip: &[&str],
+) -> TantivyDocument {
+    let mut converted = TantivyDocument::new();
+    for (field, value) in doc.field_values() {
+        let name = from.get_field_name(field);
+        if skip.contains(&name) {
+            continue;
+        }
+        let value_type = from.get_field_entry(field).field_type().value_type();
+        match to.get_field(name) {
+            Ok(field) if to.get_field_entry(field).field_type().value_type() == value_type => {
+                converted.add_field_value(field, value);
+            }
+            _ => (),
+        }
+    }
+    converted
+}
+
+#[cfg(test)]
+mod migrate_tests;
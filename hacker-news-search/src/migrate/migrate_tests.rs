@@ -0,0 +1,281 @@
+use super::{SCHEMA_VERSION_FILE, migrate_category_indices, open_versioned};
+use crate::{
+    INDEX_DIR, ITEM_CATEGORY, ITEM_ID, ITEM_RANK, ITEM_STORY_ID, ITEM_TITLE, ITEM_TYPE, IndexState,
+    SCHEMA_VERSION, SearchContext, document_schema,
+};
+use hacker_news_api::ArticleType;
+use std::{
+    fs::{create_dir_all, read_to_string, remove_file, write},
+    path::{Path, PathBuf},
+};
+use tantivy::{
+    Index, IndexWriter, TantivyDocument,
+    collector::DocSetCollector,
+    doc,
+    query::AllQuery,
+    schema::{INDEXED, STORED, STRING, Schema, TEXT, Value as _},
+};
+
+/// Empty directory for a test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hn-search-migrate-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Create a category index of schema version 1 with `(id, type, rank)`
+/// documents. Stories are tagged with `category` and comments belong to
+/// story 1.
+fn legacy_index(base_path: &Path, category: ArticleType, items: &[(u64, &str, u64)]) {
+    let mut builder = Schema::builder();
+    let id = builder.add_u64_field(ITEM_ID, STORED | INDEXED);
+    let title = builder.add_text_field(ITEM_TITLE, TEXT | STORED);
+    let ty = builder.add_text_field(ITEM_TYPE, TEXT | STORED);
+    let rank = builder.add_u64_field(ITEM_RANK, STORED | INDEXED);
+    let category_field = builder.add_text_field(ITEM_CATEGORY, STRING | STORED);
+    let story_id = builder.add_u64_field(ITEM_STORY_ID, STORED | INDEXED);
+
+    let path = base_path.join(category.as_str());
+    create_dir_all(&path).unwrap();
+    let index = Index::create_in_dir(&path, builder.build()).unwrap();
+    let mut writer: IndexWriter = index.writer(15_000_000).unwrap();
+    for (item_id, item_type, item_rank) in items {
+        let mut doc = doc!(
+            id => *item_id,
+            title => format!("Item {item_id}"),
+            ty => *item_type,
+            rank => *item_rank,
+        );
+        if *item_type == "comment" {
+            doc.add_u64(story_id, 1);
+        } else {
+            doc.add_text(category_field, category.as_str());
+        }
+        writer.add_document(doc).unwrap();
+    }
+    writer.commit().unwrap();
+}
+
+/// Every document of `index`.
+fn all_docs(index: &Index) -> Vec<TantivyDocument> {
+    let searcher = index.reader().unwrap().searcher();
+    searcher
+        .search(&AllQuery, &DocSetCollector)
+        .unwrap()
+        .into_iter()
+        .map(|address| searcher.doc(address).unwrap())
+        .collect()
+}
+
+/// Add a story with `title` to the index in `path`.
+fn add_story(path: &Path, title: &str) {
+    let index = Index::open_in_dir(path).unwrap();
+    let schema = index.schema();
+    let mut writer: IndexWriter = index.writer(15_000_000).unwrap();
+    writer
+        .add_document(doc!(
+            schema.get_field(ITEM_ID).unwrap() => 1u64,
+            schema.get_field(ITEM_TITLE).unwrap() => title,
+        ))
+        .unwrap();
+    writer.commit().unwrap();
+}
+
+/// Stored titles of the documents of `index`.
+fn titles(index: &Index) -> Vec<String> {
+    let title = index.schema().get_field(ITEM_TITLE).unwrap();
+    all_docs(index)
+        .iter()
+        .filter_map(|doc| doc.get_first(title).and_then(|title| title.as_str()))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn stored_version(path: &Path) -> Option<String> {
+    read_to_string(path.join(SCHEMA_VERSION_FILE)).ok()
+}
+
+#[test]
+fn fresh_index_is_created() {
+    let path = test_dir("fresh");
+    let (schema, _) = document_schema();
+
+    let (index, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Ready);
+    assert_eq!(index.schema(), schema);
+    assert_eq!(stored_version(&path), Some(SCHEMA_VERSION.to_string()));
+    let _ = std::fs::remove_dir_all(path);
+}
+
+#[test]
+fn matching_version_is_opened() {
+    let path = test_dir("matching");
+    let (schema, _) = document_schema();
+    drop(open_versioned(&path, &schema).unwrap());
+    add_story(&path, "Kept");
+
+    let (index, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Ready);
+    assert_eq!(titles(&index), ["Kept"]);
+    let _ = std::fs::remove_dir_all(path);
+}
+
+#[test]
+fn missing_version_file_is_written() {
+    let path = test_dir("unversioned");
+    let (schema, _) = document_schema();
+    drop(open_versioned(&path, &schema).unwrap());
+    add_story(&path, "Kept");
+    remove_file(path.join(SCHEMA_VERSION_FILE)).unwrap();
+
+    // Indices created before the version was stored are kept as they are.
+    let (index, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Ready);
+    assert_eq!(titles(&index), ["Kept"]);
+    assert_eq!(stored_version(&path), Some(SCHEMA_VERSION.to_string()));
+    let _ = std::fs::remove_dir_all(path);
+}
+
+#[test]
+fn schema_mismatch_is_migrated() {
+    let path = test_dir("mismatch");
+    let mut builder = Schema::builder();
+    builder.add_u64_field(ITEM_ID, STORED | INDEXED);
+    builder.add_text_field(ITEM_TITLE, TEXT | STORED);
+    // A field that was dropped since.
+    builder.add_text_field("removed", TEXT | STORED);
+    Index::create_in_dir(&path, builder.build()).unwrap();
+    write(path.join(SCHEMA_VERSION_FILE), "1").unwrap();
+    add_story(&path, "Copied");
+
+    let (schema, fields) = document_schema();
+    let (index, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Migrated { from: Some(1) });
+    assert_eq!(index.schema(), schema);
+    assert_eq!(titles(&index), ["Copied"]);
+    let docs = all_docs(&index);
+    assert_eq!(
+        docs[0].get_first(fields.id).and_then(|id| id.as_u64()),
+        Some(1)
+    );
+    assert_eq!(stored_version(&path), Some(SCHEMA_VERSION.to_string()));
+    assert!(!path.with_extension("migrating").exists());
+    assert!(!path.with_extension("previous").exists());
+
+    // Migrated once.
+    let (_, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Ready);
+    let _ = std::fs::remove_dir_all(path);
+}
+
+#[test]
+fn stale_previous_index_is_replaced() {
+    let path = test_dir("stale");
+    let mut builder = Schema::builder();
+    builder.add_u64_field(ITEM_ID, STORED | INDEXED);
+    builder.add_text_field(ITEM_TITLE, TEXT | STORED);
+    Index::create_in_dir(&path, builder.build()).unwrap();
+    write(path.join(SCHEMA_VERSION_FILE), "1").unwrap();
+    add_story(&path, "Copied");
+    // Left behind by a migration that stopped before removing it.
+    let previous_path = path.with_extension("previous");
+    let _ = std::fs::remove_dir_all(&previous_path);
+    create_dir_all(&previous_path).unwrap();
+    write(previous_path.join("meta.json"), "stale").unwrap();
+
+    let (schema, _) = document_schema();
+    let (index, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Migrated { from: Some(1) });
+    assert_eq!(titles(&index), ["Copied"]);
+    assert!(!previous_path.exists());
+    let _ = std::fs::remove_dir_all(path);
+}
+
+#[test]
+fn unreadable_index_is_recreated() {
+    let path = test_dir("corrupt");
+    write(path.join("meta.json"), "not an index").unwrap();
+    write(path.join(SCHEMA_VERSION_FILE), "1").unwrap();
+
+    let (schema, _) = document_schema();
+    let (index, state) = open_versioned(&path, &schema).unwrap();
+    assert_eq!(state, IndexState::Rebuild { from: Some(1) });
+    assert!(state.needs_rebuild());
+    assert_eq!(index.schema(), schema);
+    assert!(all_docs(&index).is_empty());
+    assert_eq!(stored_version(&path), Some(SCHEMA_VERSION.to_string()));
+    let _ = std::fs::remove_dir_all(path);
+}
+
+#[test]
+fn story_in_two_categories_becomes_one_doc() {
+    let base_path = test_dir("merge");
+    legacy_index(
+        &base_path,
+        ArticleType::Top,
+        &[(1, "story", 3), (2, "comment", 0)],
+    );
+    legacy_index(&base_path, ArticleType::Best, &[(1, "story", 1)]);
+
+    let (schema, fields) = document_schema();
+    let index_path = base_path.join(INDEX_DIR);
+    create_dir_all(&index_path).unwrap();
+    let (index, state) = open_versioned(&index_path, &schema).unwrap();
+    assert_eq!(state, IndexState::Ready);
+    assert!(migrate_category_indices(&base_path, &index, fields).unwrap());
+
+    let docs = all_docs(&index);
+    assert_eq!(docs.len(), 2);
+    let id = |doc: &TantivyDocument| doc.get_first(fields.id).and_then(|id| id.as_u64());
+    let story = docs.iter().find(|doc| id(doc) == Some(1)).unwrap();
+    let rank = |category| {
+        story
+            .get_first(fields.category_rank(category))
+            .and_then(|rank| rank.as_u64())
+    };
+    assert_eq!(rank(ArticleType::Top), Some(3));
+    assert_eq!(rank(ArticleType::Best), Some(1));
+    assert_eq!(rank(ArticleType::New), None);
+    let mut categories = story
+        .get_all(fields.category)
+        .filter_map(|category| category.as_str())
+        .collect::<Vec<_>>();
+    categories.sort();
+    assert_eq!(categories, ["Best", "Top"]);
+    assert!(story.get_first(fields.rank).is_none());
+
+    let comment = docs.iter().find(|doc| id(doc) == Some(2)).unwrap();
+    assert_eq!(
+        comment
+            .get_first(fields.story_id)
+            .and_then(|id| id.as_u64()),
+        Some(1)
+    );
+
+    assert!(!base_path.join("Top").exists());
+    assert!(!base_path.join("Best").exists());
+    // Nothing is left to migrate.
+    assert!(!migrate_category_indices(&base_path, &index, fields).unwrap());
+    let _ = std::fs::remove_dir_all(base_path);
+}
+
+#[test]
+fn unreadable_category_index_is_removed() {
+    let base_path = test_dir("unreadable");
+    legacy_index(&base_path, ArticleType::Best, &[(1, "story", 1)]);
+    let top = base_path.join("Top");
+    create_dir_all(&top).unwrap();
+    std::fs::write(top.join("meta.json"), "not an index").unwrap();
+
+    let ctx = SearchContext::new(&base_path, ArticleType::Top).unwrap();
+    assert_eq!(ctx.index_state(), IndexState::Rebuild { from: Some(1) });
+    assert!(!top.exists());
+    assert!(!base_path.join("Best").exists());
+
+    // The next open finds nothing to migrate.
+    drop(ctx);
+    let ctx = SearchContext::new(&base_path, ArticleType::Top).unwrap();
+    assert_eq!(ctx.index_state(), IndexState::Ready);
+    let _ = std::fs::remove_dir_all(base_path);
+}
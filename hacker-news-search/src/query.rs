@@ -0,0 +1,401 @@
+//! Story search query language.
+//!
+//! Terms and quoted phrases match the title or text of a story. Terms are
+//! combined with `AND` unless separated by `OR`, can be grouped with
+//! parentheses and negated with `NOT` or a leading `-`. Filters narrow the
+//! matching stories:
+//!
+//! | Filter                          | Matches                                   |
+//! |---------------------------------|-------------------------------------------|
+//! | `by:pg`                         | Submitted by a user, case sensitive       |
+//! | `site:github.com`, `domain:`    | Linking to a domain or its sub domains    |
+//! | `score:>100`                    | Score compared with `>`, `>=`, `<`, `<=`  |
+//! | `comments:>=50`                 | Comment count compared the same way       |
+//! | `type:ask`                      | `ask`, `show`, `job`, `poll` or `story`   |
+//! | `after:7d`, `before:2024-01-31` | Submitted after or before an age or date  |
+//!
+//! Ages are a number followed by `h`, `d`, `w` or `y`. User names are
+//! matched exactly as they are on Hacker News, so `by:PG` doesn't find the
+//! stories of `pg`. A word with a `:` is read as a filter, quote it to search
+//! for it such as `"std::io"`.
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use std::{iter::Peekable, str::FromStr, vec::IntoIter};
+use thiserror::Error;
+
+/// Parsed story search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoryQuery {
+    /// Word matching the title or text.
+    Term(String),
+    /// Quoted words matching the title or text in order.
+    Phrase(String),
+    Filter(Filter),
+    Not(Box<StoryQuery>),
+    And(Vec<StoryQuery>),
+    Or(Vec<StoryQuery>),
+}
+
+/// Filter on a story field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Submitted by a user. The name must match exactly, including case.
+    By(String),
+    /// Url host is the domain or one of its sub domains.
+    Site(String),
+    Score(Comparison),
+    /// Number of comments.
+    Comments(Comparison),
+    Type(StoryType),
+    /// Submitted at or after a time.
+    After(When),
+    /// Submitted before a time.
+    Before(When),
+}
+
+/// Comparison of a numeric field with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less(u64),
+    LessOrEqual(u64),
+    Equal(u64),
+    GreaterOrEqual(u64),
+    Greater(u64),
+}
+
+/// Kind of story for the `type:` filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoryType {
+    Ask,
+    Show,
+    Job,
+    Poll,
+    Story,
+}
+
+/// Point in time for the `after:` and `before:` filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    /// Time relative to now.
+    Ago(Duration),
+    /// Start of a day in UTC.
+    Date(NaiveDate),
+}
+
+impl When {
+    /// Unix timestamp of this point in time relative to `now`. Ages
+    /// before the unix epoch are clamped to it.
+    pub fn timestamp(&self, now: DateTime<Utc>) -> u64 {
+        let time = match self {
+            When::Ago(duration) => now
+                .checked_sub_signed(*duration)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC),
+            When::Date(date) => date.and_time(NaiveTime::MIN).and_utc(),
+        };
+        time.timestamp().try_into().unwrap_or_default()
+    }
+}
+
+/// Story search that could not be parsed. Columns count characters from 1.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum QueryError {
+    #[error("Search is empty")]
+    Empty,
+    #[error("Missing closing quote for the phrase at column {0}")]
+    UnclosedPhrase(usize),
+    #[error("Empty phrase at column {0}")]
+    EmptyPhrase(usize),
+    #[error("Missing closing parenthesis for the group at column {0}")]
+    UnclosedGroup(usize),
+    #[error("Empty group at column {0}")]
+    EmptyGroup(usize),
+    #[error("Unexpected closing parenthesis at column {0}")]
+    UnexpectedClose(usize),
+    #[error("{operator} at column {column} is missing a search term")]
+    MissingOperand {
+        operator: &'static str,
+        column: usize,
+    },
+    #[error(
+        "Unknown filter \"{name}:\" at column {column}, \
+         expected by:, site:, domain:, score:, comments:, type:, after: or before:, \
+         quote the word to search for it"
+    )]
+    UnknownFilter { name: String, column: usize },
+    #[error("Missing value for \"{name}:\" at column {column}")]
+    MissingValue { name: String, column: usize },
+    #[error("Invalid value \"{value}\" for \"{name}:\" at column {column}, expected {expected}")]
+    InvalidValue {
+        name: String,
+        value: String,
+        column: usize,
+        expected: &'static str,
+    },
+}
+
+impl FromStr for StoryQuery {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?.into_iter().peekable(),
+        };
+        if parser.tokens.peek().is_none() {
+            return Err(QueryError::Empty);
+        }
+        let query = parser.parse_or()?;
+        match parser.tokens.next() {
+            Some((column, _)) => Err(QueryError::UnexpectedClose(column)),
+            None => Ok(query),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+    Phrase(String),
+}
+
+impl Token {
+    fn operator(&self) -> Option<&'static str> {
+        match self {
+            Token::And => Some("AND"),
+            Token::Or => Some("OR"),
+            Token::Not => Some("NOT"),
+            _ => None,
+        }
+    }
+}
+
+/// Split a search into tokens with the column they start at.
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().zip(1..).peekable();
+
+    while let Some((c, column)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' => tokens.push((column, Token::Open)),
+            ')' => tokens.push((column, Token::Close)),
+            '-' if chars.peek().is_some_and(|(next, _)| !next.is_whitespace()) => {
+                tokens.push((column, Token::Not))
+            }
+            '"' => {
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some(('"', _)) => break,
+                        Some((c, _)) => phrase.push(c),
+                        None => return Err(QueryError::UnclosedPhrase(column)),
+                    }
+                }
+                if phrase.trim().is_empty() {
+                    return Err(QueryError::EmptyPhrase(column));
+                }
+                tokens.push((column, Token::Phrase(phrase)));
+            }
+            c => {
+                let mut word = String::from(c);
+                while let Some((c, _)) =
+                    chars.next_if(|(c, _)| !c.is_whitespace() && !matches!(c, '(' | ')' | '"'))
+                {
+                    word.push(c);
+                }
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                tokens.push((column, token));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Operator with the column it is at.
+type Operator = (usize, &'static str);
+
+/// Recursive descent parser where `OR` binds looser than `AND` which binds
+/// looser than `NOT`. Each step is given the operator before it, if any,
+/// to report when its operand is missing.
+struct Parser {
+    tokens: Peekable<IntoIter<(usize, Token)>>,
+}
+
+impl Parser {
+    fn parse_or(&mut self) -> Result<StoryQuery, QueryError> {
+        let mut queries = vec![self.parse_and(None)?];
+        while let Some((column, _)) = self.tokens.next_if(|(_, token)| *token == Token::Or) {
+            queries.push(self.parse_and(Some((column, "OR")))?);
+        }
+        Ok(flatten(queries, StoryQuery::Or))
+    }
+
+    fn parse_and(&mut self, before: Option<Operator>) -> Result<StoryQuery, QueryError> {
+        let mut queries = vec![self.parse_not(before)?];
+        loop {
+            let before = match self.tokens.peek() {
+                None | Some((_, Token::Or | Token::Close)) => break,
+                Some(&(column, Token::And)) => {
+                    self.tokens.next();
+                    Some((column, "AND"))
+                }
+                Some(_) => None,
+            };
+            queries.push(self.parse_not(before)?);
+        }
+        Ok(flatten(queries, StoryQuery::And))
+    }
+
+    fn parse_not(&mut self, before: Option<Operator>) -> Result<StoryQuery, QueryError> {
+        match self.tokens.next_if(|(_, token)| *token == Token::Not) {
+            Some((column, _)) => Ok(StoryQuery::Not(Box::new(
+                self.parse_not(Some((column, "NOT")))?,
+            ))),
+            None => self.parse_primary(before),
+        }
+    }
+
+    fn parse_primary(&mut self, before: Option<Operator>) -> Result<StoryQuery, QueryError> {
+        let missing_operand = |(column, operator)| QueryError::MissingOperand { operator, column };
+        let Some((column, token)) = self.tokens.next() else {
+            return Err(before.map(missing_operand).unwrap_or(QueryError::Empty));
+        };
+        match token {
+            Token::Word(word) => parse_word(word, column),
+            Token::Phrase(phrase) => Ok(StoryQuery::Phrase(phrase)),
+            Token::Open => {
+                match self.tokens.peek() {
+                    None => return Err(QueryError::UnclosedGroup(column)),
+                    Some((_, Token::Close)) => return Err(QueryError::EmptyGroup(column)),
+                    Some(_) => (),
+                }
+                let query = self.parse_or()?;
+                match self.tokens.next() {
+                    Some((_, Token::Close)) => Ok(query),
+                    _ => Err(QueryError::UnclosedGroup(column)),
+                }
+            }
+            Token::Close => Err(before
+                .map(missing_operand)
+                .unwrap_or(QueryError::UnexpectedClose(column))),
+            operator => Err(missing_operand(
+                before.unwrap_or((column, operator.operator().unwrap_or_default())),
+            )),
+        }
+    }
+}
+
+/// A single query or `combine` of several.
+fn flatten(mut queries: Vec<StoryQuery>, combine: fn(Vec<StoryQuery>) -> StoryQuery) -> StoryQuery {
+    if queries.len() == 1 {
+        queries.remove(0)
+    } else {
+        combine(queries)
+    }
+}
+
+/// A term or a `name:value` filter.
+fn parse_word(word: String, column: usize) -> Result<StoryQuery, QueryError> {
+    let Some((name, value)) = word.split_once(':') else {
+        return Ok(StoryQuery::Term(word));
+    };
+    if value.is_empty() {
+        return Err(QueryError::MissingValue {
+            name: name.to_owned(),
+            column,
+        });
+    }
+    let invalid = |expected| QueryError::InvalidValue {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        column,
+        expected,
+    };
+
+    let filter = match name {
+        "by" => Filter::By(value.to_owned()),
+        "site" | "domain" => {
+            let domain = value.trim_start_matches("www.").to_lowercase();
+            if domain
+                .split('.')
+                .any(|label| label.is_empty() || !label.chars().all(is_domain_char))
+            {
+                return Err(invalid("a domain such as github.com"));
+            }
+            Filter::Site(domain)
+        }
+        "score" => Filter::Score(parse_comparison(value).ok_or_else(|| invalid(NUMBER))?),
+        "comments" => Filter::Comments(parse_comparison(value).ok_or_else(|| invalid(NUMBER))?),
+        "type" => Filter::Type(match value.to_lowercase().as_str() {
+            "ask" => StoryType::Ask,
+            "show" => StoryType::Show,
+            "job" => StoryType::Job,
+            "poll" => StoryType::Poll,
+            "story" => StoryType::Story,
+            _ => return Err(invalid("ask, show, job, poll or story")),
+        }),
+        "after" => Filter::After(parse_when(value).ok_or_else(|| invalid(WHEN))?),
+        "before" => Filter::Before(parse_when(value).ok_or_else(|| invalid(WHEN))?),
+        _ => {
+            return Err(QueryError::UnknownFilter {
+                name: name.to_owned(),
+                column,
+            });
+        }
+    };
+    Ok(StoryQuery::Filter(filter))
+}
+
+const NUMBER: &str = "a number optionally prefixed with >, >=, < or <= such as >100";
+
+const WHEN: &str = "an age such as 7d or a date such as 2024-01-31";
+
+fn is_domain_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+fn parse_comparison(value: &str) -> Option<Comparison> {
+    let number = |s: &str| s.parse::<u64>().ok();
+    if let Some(n) = value.strip_prefix(">=") {
+        number(n).map(Comparison::GreaterOrEqual)
+    } else if let Some(n) = value.strip_prefix("<=") {
+        number(n).map(Comparison::LessOrEqual)
+    } else if let Some(n) = value.strip_prefix('>') {
+        number(n).map(Comparison::Greater)
+    } else if let Some(n) = value.strip_prefix('<') {
+        number(n).map(Comparison::Less)
+    } else {
+        number(value).map(Comparison::Equal)
+    }
+}
+
+fn parse_when(value: &str) -> Option<When> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(When::Date(date));
+    }
+    let unit_start = value.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = value.split_at(unit_start);
+    let count = count.parse::<i64>().ok()?;
+    let duration = match unit {
+        "h" => Duration::try_hours(count),
+        "d" => Duration::try_days(count),
+        "w" => Duration::try_weeks(count),
+        "y" => Duration::try_days(count.checked_mul(365)?),
+        _ => None,
+    }?;
+    // Ages past the earliest representable time are rejected.
+    Utc::now().checked_sub_signed(duration)?;
+    Some(When::Ago(duration))
+}
+
+#[cfg(test)]
+mod query_tests;
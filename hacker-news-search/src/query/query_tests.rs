@@ -0,0 +1,277 @@
+use super::{Comparison, Filter, QueryError, StoryQuery, StoryType, When};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+fn parse(s: &str) -> StoryQuery {
+    s.parse().unwrap()
+}
+
+fn error(s: &str) -> QueryError {
+    s.parse::<StoryQuery>().unwrap_err()
+}
+
+fn term(word: &str) -> StoryQuery {
+    StoryQuery::Term(word.to_owned())
+}
+
+fn not(query: StoryQuery) -> StoryQuery {
+    StoryQuery::Not(Box::new(query))
+}
+
+fn filter(s: &str) -> Filter {
+    match parse(s) {
+        StoryQuery::Filter(filter) => filter,
+        query => panic!("{s} is not a filter: {query:?}"),
+    }
+}
+
+fn invalid(s: &str) -> (String, String, usize) {
+    match error(s) {
+        QueryError::InvalidValue {
+            name,
+            value,
+            column,
+            ..
+        } => (name, value, column),
+        err => panic!("{s} is not an invalid value: {err:?}"),
+    }
+}
+
+#[test]
+fn terms_are_anded() {
+    assert_eq!(parse("rust"), term("rust"));
+    assert_eq!(
+        parse("rust  async\tawait"),
+        StoryQuery::And(vec![term("rust"), term("async"), term("await")])
+    );
+    assert_eq!(parse("rust AND async"), parse("rust async"));
+}
+
+#[test]
+fn or_binds_looser_than_and() {
+    assert_eq!(
+        parse("a b OR c"),
+        StoryQuery::Or(vec![StoryQuery::And(vec![term("a"), term("b")]), term("c")])
+    );
+    assert_eq!(
+        parse("a OR b OR c"),
+        StoryQuery::Or(vec![term("a"), term("b"), term("c")])
+    );
+    assert_eq!(
+        parse("(a OR b) c"),
+        StoryQuery::And(vec![StoryQuery::Or(vec![term("a"), term("b")]), term("c")])
+    );
+    // Lower case operators are words.
+    assert_eq!(
+        parse("a or b"),
+        StoryQuery::And(vec![term("a"), term("or"), term("b")])
+    );
+}
+
+#[test]
+fn negation() {
+    assert_eq!(parse("NOT a"), not(term("a")));
+    assert_eq!(parse("-a"), not(term("a")));
+    assert_eq!(parse("NOT -a"), not(not(term("a"))));
+    assert_eq!(
+        parse("a -(b OR c)"),
+        StoryQuery::And(vec![
+            term("a"),
+            not(StoryQuery::Or(vec![term("b"), term("c")]))
+        ])
+    );
+    assert_eq!(
+        parse("-by:pg"),
+        not(StoryQuery::Filter(Filter::By("pg".to_owned())))
+    );
+    // A dash before a space or inside a word is part of a word.
+    assert_eq!(
+        parse("a - b"),
+        StoryQuery::And(vec![term("a"), term("-"), term("b")])
+    );
+    assert_eq!(parse("e-mail"), term("e-mail"));
+}
+
+#[test]
+fn phrases() {
+    assert_eq!(
+        parse("\"rust async\""),
+        StoryQuery::Phrase("rust async".to_owned())
+    );
+    assert_eq!(
+        parse("-\"not this\" that"),
+        StoryQuery::And(vec![
+            not(StoryQuery::Phrase("not this".to_owned())),
+            term("that")
+        ])
+    );
+    // Operators and filters are plain words within quotes.
+    assert_eq!(
+        parse("\"a OR by:pg\""),
+        StoryQuery::Phrase("a OR by:pg".to_owned())
+    );
+}
+
+#[test]
+fn filters() {
+    assert_eq!(filter("by:pg"), Filter::By("pg".to_owned()));
+    // User names keep their case.
+    assert_eq!(filter("by:PG"), Filter::By("PG".to_owned()));
+    assert_eq!(
+        filter("site:www.GitHub.com"),
+        Filter::Site("github.com".to_owned())
+    );
+    assert_eq!(
+        filter("domain:blog.rust-lang.org"),
+        Filter::Site("blog.rust-lang.org".to_owned())
+    );
+    assert_eq!(
+        filter("score:>100"),
+        Filter::Score(Comparison::Greater(100))
+    );
+    assert_eq!(
+        filter("comments:50"),
+        Filter::Comments(Comparison::Equal(50))
+    );
+    assert_eq!(filter("type:Ask"), Filter::Type(StoryType::Ask));
+    assert_eq!(filter("type:show"), Filter::Type(StoryType::Show));
+    assert_eq!(filter("type:job"), Filter::Type(StoryType::Job));
+    assert_eq!(filter("type:poll"), Filter::Type(StoryType::Poll));
+    assert_eq!(filter("type:story"), Filter::Type(StoryType::Story));
+    assert_eq!(
+        filter("after:12h"),
+        Filter::After(When::Ago(Duration::hours(12)))
+    );
+    assert_eq!(
+        filter("after:7d"),
+        Filter::After(When::Ago(Duration::days(7)))
+    );
+    assert_eq!(
+        filter("after:2w"),
+        Filter::After(When::Ago(Duration::weeks(2)))
+    );
+    assert_eq!(
+        filter("before:1y"),
+        Filter::Before(When::Ago(Duration::days(365)))
+    );
+    assert_eq!(
+        filter("before:2024-01-31"),
+        Filter::Before(When::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()))
+    );
+}
+
+#[test]
+fn comparisons() {
+    let score = |value: &str| match filter(&format!("score:{value}")) {
+        Filter::Score(comparison) => comparison,
+        filter => panic!("not a score filter: {filter:?}"),
+    };
+    assert_eq!(score("10"), Comparison::Equal(10));
+    assert_eq!(score(">10"), Comparison::Greater(10));
+    assert_eq!(score(">=10"), Comparison::GreaterOrEqual(10));
+    assert_eq!(score("<10"), Comparison::Less(10));
+    assert_eq!(score("<=10"), Comparison::LessOrEqual(10));
+
+    for value in ["abc", ">", "=10", "-1", ">=x", "10k"] {
+        assert_eq!(
+            invalid(&format!("score:{value}")),
+            ("score".to_owned(), value.to_owned(), 1)
+        );
+    }
+}
+
+#[test]
+fn timestamps() {
+    let now = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+    assert_eq!(
+        When::Ago(Duration::days(1)).timestamp(now),
+        1_700_000_000 - 86_400
+    );
+    assert_eq!(
+        When::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()).timestamp(now),
+        1_706_659_200
+    );
+    // Before the epoch or past the earliest time clamp to the epoch.
+    assert_eq!(When::Ago(Duration::days(365 * 100)).timestamp(now), 0);
+    assert_eq!(When::Ago(Duration::MAX).timestamp(now), 0);
+}
+
+#[test]
+fn words_with_colons() {
+    assert_eq!(
+        error("std::io"),
+        QueryError::UnknownFilter {
+            name: "std".to_owned(),
+            column: 1
+        }
+    );
+    assert_eq!(
+        error("a word:with:colon"),
+        QueryError::UnknownFilter {
+            name: "word".to_owned(),
+            column: 3
+        }
+    );
+    assert_eq!(
+        parse("\"word:with:colon\""),
+        StoryQuery::Phrase("word:with:colon".to_owned())
+    );
+}
+
+#[test]
+fn error_columns() {
+    assert_eq!(error(""), QueryError::Empty);
+    assert_eq!(error("   "), QueryError::Empty);
+    assert_eq!(error("a \"open"), QueryError::UnclosedPhrase(3));
+    assert_eq!(error("a \"  \""), QueryError::EmptyPhrase(3));
+    assert_eq!(error("a (b"), QueryError::UnclosedGroup(3));
+    assert_eq!(error("a ("), QueryError::UnclosedGroup(3));
+    assert_eq!(error("a () b"), QueryError::EmptyGroup(3));
+    assert_eq!(error("a b)"), QueryError::UnexpectedClose(4));
+    assert_eq!(error(")"), QueryError::UnexpectedClose(1));
+    // Columns count characters rather than bytes.
+    assert_eq!(error("héllo wörld)"), QueryError::UnexpectedClose(12));
+
+    let missing = |operator, column| QueryError::MissingOperand { operator, column };
+    assert_eq!(error("a OR"), missing("OR", 3));
+    assert_eq!(error("a AND"), missing("AND", 3));
+    assert_eq!(error("a NOT"), missing("NOT", 3));
+    assert_eq!(error("OR a"), missing("OR", 1));
+    assert_eq!(error("a OR AND b"), missing("OR", 3));
+    assert_eq!(error("(a OR)"), missing("OR", 4));
+
+    assert_eq!(
+        error("a by:"),
+        QueryError::MissingValue {
+            name: "by".to_owned(),
+            column: 3
+        }
+    );
+    assert_eq!(
+        error("a scroe:>10"),
+        QueryError::UnknownFilter {
+            name: "scroe".to_owned(),
+            column: 3
+        }
+    );
+    assert_eq!(
+        invalid("a type:blog"),
+        ("type".to_owned(), "blog".to_owned(), 3)
+    );
+    assert_eq!(
+        invalid("site:bad..com"),
+        ("site".to_owned(), "bad..com".to_owned(), 1)
+    );
+    assert_eq!(
+        invalid("after:7m"),
+        ("after".to_owned(), "7m".to_owned(), 1)
+    );
+    assert_eq!(
+        invalid("before:2024-02-30"),
+        ("before".to_owned(), "2024-02-30".to_owned(), 1)
+    );
+    // Too far back to be a time.
+    assert_eq!(
+        invalid("rust after:100000000y"),
+        ("after".to_owned(), "100000000y".to_owned(), 6)
+    );
+}
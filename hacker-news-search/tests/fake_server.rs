@@ -0,0 +1,111 @@
+//! Index and watch stories served by the fake server while it changes.
+use futures::{StreamExt, channel::mpsc};
+use hacker_news_api::{ApiClient, ArticleType, SubscriptionStatus};
+use hacker_news_fake::{Dataset, FakeServer};
+use hacker_news_search::{
+    RebuildOptions, SearchContext, init_api_client, rebuild_index, watch_comment, watch_story,
+};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::{sync::watch, time::timeout};
+
+async fn next<T>(receiver: &mut mpsc::Receiver<T>) -> T {
+    timeout(Duration::from_secs(10), receiver.next())
+        .await
+        .expect("no update in time")
+        .unwrap()
+}
+
+async fn wait_for(
+    status: &mut watch::Receiver<SubscriptionStatus>,
+    f: impl FnMut(&SubscriptionStatus) -> bool,
+) {
+    timeout(Duration::from_secs(10), status.wait_for(f))
+        .await
+        .expect("status did not change in time")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn watch_and_reconnect() {
+    let server = FakeServer::start(Dataset::sample()).await.unwrap();
+    init_api_client(
+        ApiClient::builder()
+            .base_url(server.base_url())
+            .build()
+            .unwrap(),
+    );
+
+    let dir = std::env::temp_dir().join(format!("hn-search-fake-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let ctx = Arc::new(RwLock::new(
+        SearchContext::new(&dir, ArticleType::Top).unwrap(),
+    ));
+    let (progress_tx, _progress_rx) = mpsc::channel(100);
+    let stats = rebuild_index(
+        ctx.clone(),
+        ArticleType::Top,
+        RebuildOptions::default(),
+        progress_tx,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_comments, 4);
+
+    let (story, other_story, comment) = {
+        let g = ctx.read().unwrap();
+        let (comments, _) = g.comments(1, 10, 0).unwrap();
+        (
+            g.story(1).unwrap(),
+            g.story(6).unwrap(),
+            comments[0].clone(),
+        )
+    };
+    assert_eq!(comment.id, 2);
+    let mut story_watch = watch_story(ctx.clone(), story, RebuildOptions::default()).unwrap();
+    let mut other_story_watch =
+        watch_story(ctx.clone(), other_story, RebuildOptions::default()).unwrap();
+    let mut comment_watch = watch_comment(ctx.clone(), comment).unwrap();
+    let mut status = story_watch.status.clone();
+    wait_for(&mut status, |status| *status == SubscriptionStatus::Live).await;
+
+    // A reply changes the comment and the story.
+    let reply = server.add_comment(2, "erin", "Replying live").unwrap();
+    let story = next(&mut story_watch.receiver).await;
+    assert_eq!(story.descendants, 5);
+    let comment = next(&mut comment_watch.receiver).await;
+    assert_eq!(comment.kids, [3, reply]);
+    let (replies, _) = ctx.read().unwrap().comments(2, 10, 0).unwrap();
+    assert!(
+        replies
+            .iter()
+            .any(|comment| comment.body == "Replying live")
+    );
+
+    server.disconnect_streams();
+    wait_for(&mut status, |status| {
+        matches!(status, SubscriptionStatus::Reconnecting { .. })
+    })
+    .await;
+    wait_for(&mut status, |status| *status == SubscriptionStatus::Live).await;
+
+    // Changes after the reconnect still reach the watchers.
+    server.add_comment(6, "bob", "After the reconnect").unwrap();
+    let other_story = next(&mut other_story_watch.receiver).await;
+    assert_eq!(other_story.id, 6);
+    assert_eq!(other_story.descendants, 1);
+    let (comments, _) = ctx.read().unwrap().comments(6, 10, 0).unwrap();
+    assert_eq!(comments[0].body, "After the reconnect");
+
+    for handle in story_watch
+        .abort_handles
+        .iter()
+        .chain(&other_story_watch.abort_handles)
+        .chain(&comment_watch.abort_handles)
+    {
+        handle.abort();
+    }
+    let _ = std::fs::remove_dir_all(dir);
+}
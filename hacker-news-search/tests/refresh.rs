@@ -0,0 +1,102 @@
+//! Refresh an index in place while the fake server's stories change.
+use futures::channel::mpsc;
+use hacker_news_api::{ApiClient, ArticleType, Endpoint, Item};
+use hacker_news_fake::{Dataset, FakeServer};
+use hacker_news_search::{
+    RebuildOptions, SearchContext, api_client, init_api_client, rebuild_index, refresh_index,
+};
+use std::sync::{Arc, RwLock};
+
+fn item_requests() -> u64 {
+    api_client()
+        .metrics()
+        .endpoints
+        .get(&Endpoint::Item)
+        .map(|metrics| metrics.requests)
+        .unwrap_or_default()
+}
+
+async fn refresh(ctx: &Arc<RwLock<SearchContext>>) -> u64 {
+    let (progress_tx, _progress_rx) = mpsc::channel(100);
+    refresh_index(
+        ctx.clone(),
+        ArticleType::Top,
+        RebuildOptions::default(),
+        progress_tx,
+    )
+    .await
+    .unwrap()
+    .total_comments
+}
+
+#[tokio::test]
+async fn refresh_walks_only_what_changed() {
+    let server = FakeServer::start(Dataset::sample()).await.unwrap();
+    init_api_client(
+        ApiClient::builder()
+            .base_url(server.base_url())
+            .build()
+            .unwrap(),
+    );
+
+    let dir = std::env::temp_dir().join(format!("hn-search-refresh-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let ctx = Arc::new(RwLock::new(
+        SearchContext::new(&dir, ArticleType::Top).unwrap(),
+    ));
+    let (progress_tx, _progress_rx) = mpsc::channel(100);
+    let stats = rebuild_index(
+        ctx.clone(),
+        ArticleType::Top,
+        RebuildOptions::default(),
+        progress_tx,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_comments, 4);
+
+    // Story 1 has comments 2 -> 3 and 4 -> 5. A new top level comment is
+    // found on the first level so the replies aren't fetched again.
+    let reply = server.add_comment(1, "dave", "A new thread").unwrap();
+    let before = item_requests();
+    assert_eq!(refresh(&ctx).await, 5);
+    // Stories 1, 6, 7 and 10, poll options 8 and 9 and comments 2, 4 and
+    // the new one.
+    assert_eq!(item_requests() - before, 9);
+    {
+        let g = ctx.read().unwrap();
+        let (comments, _) = g.comments(1, 10, 0).unwrap();
+        let ids = comments
+            .iter()
+            .map(|comment| comment.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [2, 4, reply]);
+        assert_eq!(g.story(1).unwrap().descendants, 5);
+    }
+
+    // Comment 5 is dropped from its parent and the count goes down, so the
+    // whole tree is walked and the comment is removed.
+    let comment = server.item(4).unwrap();
+    server.put_item(Item {
+        kids: Vec::new(),
+        ..comment
+    });
+    let story = server.item(1).unwrap();
+    server.put_item(Item {
+        descendants: Some(4),
+        ..story
+    });
+    let before = item_requests();
+    assert_eq!(refresh(&ctx).await, 4);
+    // Stories, poll options and comments 2, 4, the new one and 3.
+    assert_eq!(item_requests() - before, 10);
+    {
+        let g = ctx.read().unwrap();
+        let (replies, _) = g.comments(4, 10, 0).unwrap();
+        assert!(replies.is_empty());
+        let (replies, _) = g.comments(2, 10, 0).unwrap();
+        assert_eq!(replies[0].id, 3);
+    }
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,134 @@
+//! Index and watch stories from recorded API traffic.
+use futures::{StreamExt, channel::mpsc};
+use hacker_news_api::{ApiClient, ArticleType, ReplayTiming, Transport};
+use hacker_news_search::{
+    RebuildOptions, RebuildProgress, SearchContext, init_api_client, rebuild_index, watch_comment,
+    watch_story,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::time::timeout;
+
+/// Install the client replaying the recording. Recorded delays are kept so
+/// the change feed events arrive after the watchers subscribed.
+fn replay_client() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replay");
+    init_api_client(
+        ApiClient::builder()
+            .transport(Transport::Replay {
+                dir,
+                timing: ReplayTiming::Original,
+            })
+            .build()
+            .unwrap(),
+    );
+}
+
+/// A new index in a temporary directory.
+fn search_context(name: &str, category: ArticleType) -> (PathBuf, Arc<RwLock<SearchContext>>) {
+    let dir = std::env::temp_dir().join(format!("hn-search-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let ctx = SearchContext::new(&dir, category).unwrap();
+    (dir, Arc::new(RwLock::new(ctx)))
+}
+
+async fn rebuild(ctx: &Arc<RwLock<SearchContext>>, category: ArticleType) -> Vec<RebuildProgress> {
+    let (progress_tx, progress_rx) = mpsc::channel(100);
+    let stats = rebuild_index(
+        ctx.clone(),
+        category,
+        RebuildOptions::default(),
+        progress_tx,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.category, category);
+    progress_rx.collect().await
+}
+
+#[tokio::test]
+async fn rebuild_index_from_recording() {
+    replay_client();
+    let (dir, ctx) = search_context("rebuild", ArticleType::Top);
+
+    let progress = rebuild(&ctx, ArticleType::Top).await;
+    assert!(matches!(
+        progress.first(),
+        Some(RebuildProgress::Started(2))
+    ));
+    assert!(matches!(progress.last(), Some(RebuildProgress::Completed)));
+
+    let g = ctx.read().unwrap();
+    let stories = g.top_stories(ArticleType::Top, 10, 0).unwrap();
+    assert_eq!(
+        stories.iter().map(|story| story.id).collect::<Vec<_>>(),
+        [1, 2]
+    );
+    assert_eq!(stories[0].title, "Replaying recorded traffic");
+
+    let (comments, count) = g.comments(1, 10, 0).unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(comments[0].body, "Nice tape");
+    assert_eq!(comments[0].kids, [4]);
+    let (replies, _) = g.comments(3, 10, 0).unwrap();
+    assert_eq!(replies[0].story_id, 1);
+    drop(g);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn watch_story_and_comment_from_recording() {
+    replay_client();
+    let (dir, ctx) = search_context("watch", ArticleType::Ask);
+    rebuild(&ctx, ArticleType::Ask).await;
+
+    let (story, comment) = {
+        let g = ctx.read().unwrap();
+        let story = g.story(10).unwrap();
+        let (comments, _) = g.comments(10, 10, 0).unwrap();
+        (story, comments.into_iter().next().unwrap())
+    };
+    assert_eq!(story.descendants, 1);
+    assert_eq!(comment.id, 11);
+
+    // Both watchers listen to the change feed before its first event.
+    let mut story_watch = watch_story(ctx.clone(), story, RebuildOptions::default()).unwrap();
+    let mut comment_watch = watch_comment(ctx.clone(), comment).unwrap();
+
+    let story = timeout(Duration::from_secs(5), story_watch.receiver.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(story.descendants, 2);
+    assert_eq!(story.score, 6);
+    let (comments, _) = ctx.read().unwrap().comments(10, 10, 0).unwrap();
+    assert_eq!(
+        comments
+            .iter()
+            .map(|comment| comment.id)
+            .collect::<Vec<_>>(),
+        [11, 12]
+    );
+
+    let comment = timeout(Duration::from_secs(5), comment_watch.receiver.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(comment.id, 11);
+    assert_eq!(comment.kids, [13]);
+    let (replies, _) = ctx.read().unwrap().comments(11, 10, 0).unwrap();
+    assert_eq!(replies[0].body, "A reply");
+
+    for handle in story_watch
+        .abort_handles
+        .iter()
+        .chain(&comment_watch.abort_handles)
+    {
+        handle.abort();
+    }
+    let _ = std::fs::remove_dir_all(dir);
+}
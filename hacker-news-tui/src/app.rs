@@ -6,11 +6,13 @@ use crate::{
     events::{AppEvent, EventManager, IndexRebuildState},
     footer::FooterWidget,
     help::HelpWidget,
+    profile::{ProfileState, ProfileWidget},
+    rebuild_options::{RebuildOptionsState, RebuildOptionsWidget},
     search::{InputMode, SearchState, SearchWidget},
 };
 use color_eyre::Result;
-use hacker_news_config::search_context;
-use hacker_news_search::{RebuildProgress, SearchContext, api_client};
+use hacker_news_config::{api_client_builder, init_item_source, search_context};
+use hacker_news_search::{IndexState, RebuildProgress, SearchContext, init_api_client};
 use log::error;
 use ratatui::{
     DefaultTerminal,
@@ -29,7 +31,7 @@ use std::{
     ops::Not as _,
     sync::{Arc, RwLock},
 };
-use tui_input::backend::crossterm::EventHandler;
+use tui_input::{Input, backend::crossterm::EventHandler};
 
 /// Active view
 #[derive(Clone, Copy)]
@@ -37,6 +39,7 @@ pub enum View {
     Articles,
     Search,
     Comments,
+    Profile,
 }
 
 /// Active viewing state.
@@ -45,6 +48,8 @@ pub enum Viewing {
     Comments(CommentState),
     /// Viewing search.
     Search(SearchState),
+    /// Viewing a user profile.
+    Profile(ProfileState),
 }
 
 /// The main application which holds the state and logic of the application.
@@ -53,20 +58,28 @@ pub struct App {
     /// Is the application running?
     running: bool,
     pub search_context: Arc<RwLock<SearchContext>>,
+    /// State of the index when it was opened.
+    pub index_state: IndexState,
     pub rebuild_progress: Option<IndexRebuildState>,
     pub viewing_state: Option<Viewing>,
     pub config: Config,
     articles_state: ArticlesState,
     show_help: bool,
+    /// Rebuild options popup.
+    rebuild_options: Option<RebuildOptionsState>,
 }
 
 impl App {
     /// Construct a new instance of [`App`].
     pub fn new(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
-        let _ = api_client();
+        init_api_client(api_client_builder().build()?);
+        init_item_source()?;
 
         let search_context = search_context()?;
-        let stories = search_context.read().unwrap().top_stories(75, 0)?;
+        let (stories, index_state) = {
+            let g = search_context.read().unwrap();
+            (g.top_stories(g.active_category(), 75, 0)?, g.index_state())
+        };
 
         let articles_state = ArticlesState {
             list_state: ListState::default().with_selected(stories.is_empty().not().then_some(0)),
@@ -80,17 +93,26 @@ impl App {
             event_manager: EventManager::new(),
             running: false,
             search_context,
+            index_state,
             rebuild_progress: None,
             viewing_state: None,
             config,
             articles_state,
             show_help: false,
+            rebuild_options: None,
         })
     }
 
     /// Run the application's main loop.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
         self.running = true;
+        if self.index_state.needs_rebuild() {
+            self.event_manager.rebuild_index(
+                self.search_context.clone(),
+                self.articles_state.article_type,
+                self.config.index_config.rebuild_options,
+            );
+        }
         while self.running {
             terminal.draw(|frame| {
                 if let Some(Viewing::Search(state)) = self.viewing_state.as_ref()
@@ -111,6 +133,19 @@ impl App {
                     frame.render_widget(Clear, area);
                     frame.render_widget(HelpWidget::new(self.viewing()), area);
                 }
+
+                if let Some(state) = self.rebuild_options.as_mut() {
+                    let area = centered_rect(30, 50, frame.area());
+                    frame.render_widget(Clear, area);
+                    if let Some(cursor) = state.cursor(area) {
+                        frame.set_cursor_position(cursor);
+                    }
+                    frame.render_stateful_widget(
+                        RebuildOptionsWidget::new(&self.config.index_config.rebuild_options),
+                        area,
+                        state,
+                    );
+                }
             })?;
             self.handle_event(self.event_manager.next()?);
         }
@@ -122,6 +157,7 @@ impl App {
             Some(state) => match state {
                 Viewing::Comments(_) => View::Comments,
                 Viewing::Search(_) => View::Search,
+                Viewing::Profile(_) => View::Profile,
             },
             None => View::Articles,
         }
@@ -134,7 +170,11 @@ impl App {
                 self.handle_rebuild_progress(rebuild_progress)
             }
             AppEvent::IndexingCompleted(index_stats) => {
-                let top_stories = self.search_context.read().unwrap().top_stories(75, 0);
+                self.index_state = IndexState::Ready;
+                let top_stories = {
+                    let g = self.search_context.read().unwrap();
+                    g.top_stories(g.active_category(), 75, 0)
+                };
                 match top_stories {
                     Ok(stories) => {
                         if !stories.is_empty() {
@@ -177,7 +217,22 @@ impl App {
                     .iter_mut()
                     .find(|s| s.id == story.id)
                 {
-                    *s = story;
+                    *s = *story;
+                }
+            }
+            AppEvent::ProfileLoaded(profile) => {
+                if let Some(Viewing::Profile(state)) = self.viewing_state.as_mut()
+                    && state.handle == profile.user.id
+                {
+                    state.loaded(*profile);
+                }
+            }
+            AppEvent::ProfileFailed { handle, error } => {
+                if let Some(Viewing::Profile(state)) = self.viewing_state.as_mut()
+                    && state.handle == handle
+                {
+                    state.loading = false;
+                    state.error = Some(error);
                 }
             }
         }
@@ -206,6 +261,11 @@ impl App {
         match event {
             // it's important to check KeyEventKind::Press to avoid handling key release events
             Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if self.rebuild_options.is_some() {
+                    self.on_rebuild_options_key_event(key, &event);
+                    return;
+                }
+
                 match self.viewing_state.as_mut() {
                     Some(Viewing::Search(search_state))
                         if matches!(search_state.input_mode, InputMode::Editing) =>
@@ -221,6 +281,7 @@ impl App {
                                     &search,
                                     10,
                                     search_state.offset,
+                                    true,
                                 ) {
                                     Ok((comments, total_comments)) => {
                                         search_state.comments = comments;
@@ -275,6 +336,11 @@ impl App {
                 position.y = position.y.saturating_sub(interval);
                 state.scroll_view_state.set_offset(position);
             }
+            Some(Viewing::Profile(state)) => {
+                let mut position = state.scroll_view_state.offset();
+                position.y = position.y.saturating_sub(interval);
+                state.scroll_view_state.set_offset(position);
+            }
             None => {
                 let selected = self
                     .articles_state
@@ -301,6 +367,11 @@ impl App {
                 position.y = position.y.saturating_add(interval);
                 state.scroll_view_state.set_offset(position);
             }
+            Some(Viewing::Profile(state)) => {
+                let mut position = state.scroll_view_state.offset();
+                position.y = position.y.saturating_add(interval);
+                state.scroll_view_state.set_offset(position);
+            }
             None => {
                 let selected = self
                     .articles_state
@@ -375,6 +446,10 @@ impl App {
                     Some(Viewing::Search(_state)) => {
                         self.viewing_state = None;
                     }
+                    // Return to where the profile was opened from.
+                    Some(Viewing::Profile(state)) => {
+                        self.viewing_state = state.previous.take().map(|previous| *previous);
+                    }
                     None => {
                         self.quit();
                     }
@@ -397,6 +472,9 @@ impl App {
                     Some(Viewing::Search(state)) => {
                         self.move_down(state.page_height);
                     }
+                    Some(Viewing::Profile(state)) => {
+                        self.move_down(state.page_height);
+                    }
                     None => {
                         self.move_down(self.articles_state.page_height);
                     }
@@ -412,6 +490,9 @@ impl App {
                     Some(Viewing::Search(state)) => {
                         self.move_up(state.page_height);
                     }
+                    Some(Viewing::Profile(state)) => {
+                        self.move_up(state.page_height);
+                    }
                     None => {
                         self.move_up(self.articles_state.page_height);
                     }
@@ -425,6 +506,9 @@ impl App {
                 Some(Viewing::Search(state)) => {
                     state.scroll_view_state.scroll_to_top();
                 }
+                Some(Viewing::Profile(state)) => {
+                    state.scroll_view_state.scroll_to_top();
+                }
                 None => {
                     self.articles_state.list_state.select(Some(0));
                     self.articles_state.scrollbar_state.first();
@@ -439,6 +523,9 @@ impl App {
                     Some(Viewing::Search(state)) => {
                         state.scroll_view_state.scroll_to_bottom();
                     }
+                    Some(Viewing::Profile(state)) => {
+                        state.scroll_view_state.scroll_to_bottom();
+                    }
                     None => {
                         self.articles_state
                             .list_state
@@ -452,8 +539,24 @@ impl App {
                 self.event_manager.rebuild_index(
                     self.search_context.clone(),
                     self.articles_state.article_type,
+                    self.config.index_config.rebuild_options,
                 );
             }
+            // Edit the rebuild options.
+            (_, KeyCode::Char('R')) if self.viewing_state.is_none() => {
+                self.rebuild_options = Some(RebuildOptionsState::default());
+            }
+            // Open the selected submission on the website.
+            (_, KeyCode::Char('o')) if matches!(self.viewing_state, Some(Viewing::Profile(_))) => {
+                if let Some(Viewing::Profile(state)) = &self.viewing_state
+                    && let Some(item) = state.selected_item()
+                {
+                    let url = format!("https://news.ycombinator.com/item?id={}", item.id);
+                    if let Err(err) = open::that(&url) {
+                        error!("Failed to open url {url}: {err}");
+                    }
+                }
+            }
             // Open URL for story.
             (_, KeyCode::Char('o')) => {
                 if let Some(url) = self.select_item_url()
@@ -499,35 +602,43 @@ impl App {
                         }
                     }
                     Some(Viewing::Search(_state)) => {}
+                    // Open an indexed submission in its story or thread.
+                    Some(Viewing::Profile(state)) => {
+                        let selected = state
+                            .selected_item()
+                            .filter(|item| {
+                                state
+                                    .profile
+                                    .as_ref()
+                                    .is_some_and(|profile| profile.is_indexed(item.id))
+                            })
+                            .map(|item| (item.id, item.parent, item.ty.clone()));
+                        match selected {
+                            Some((comment_id, Some(parent_id), ty)) if ty == "comment" => {
+                                self.show_thread(comment_id, parent_id);
+                            }
+                            Some((story_id, _, ty)) => {
+                                let selected_index = self
+                                    .articles_state
+                                    .stories
+                                    .iter()
+                                    .position(|story| story.id == story_id);
+                                self.articles_state.list_state.select(selected_index);
+                                self.open_comments(story_id, ty == "poll");
+                            }
+                            None => (),
+                        }
+                    }
                     // We are opening comments for a story
                     None => {
-                        if let Some(selected_item) = self
+                        if let Some((selected_item, is_poll)) = self
                             .articles_state
                             .list_state
                             .selected()
                             .and_then(|id| self.articles_state.stories.get(id))
-                            .map(|story| story.id)
+                            .map(|story| (story.id, story.ty == "poll"))
                         {
-                            let comments =
-                                self.search_context
-                                    .read()
-                                    .unwrap()
-                                    .comments(selected_item, 10, 0);
-
-                            match comments {
-                                Ok((comments, total)) => {
-                                    self.viewing_state = Some(Viewing::Comments(CommentState {
-                                        parent_id: selected_item,
-                                        limit: 10,
-                                        comments,
-                                        total_comments: total,
-                                        ..Default::default()
-                                    }));
-                                }
-                                Err(err) => {
-                                    error!("Failed to get comments: {err}");
-                                }
-                            }
+                            self.open_comments(selected_item, is_poll);
                         }
                     }
                 }
@@ -542,6 +653,16 @@ impl App {
                         Viewing::Search(search_state) => {
                             search_state.page_forward(self.search_context.clone());
                         }
+                        Viewing::Profile(profile_state) => {
+                            if let Some(offset) = profile_state.next_offset() {
+                                profile_state.loading = true;
+                                self.event_manager.fetch_profile(
+                                    self.search_context.clone(),
+                                    profile_state.handle.clone(),
+                                    offset,
+                                );
+                            }
+                        }
                     }
                 } else {
                     self.articles_state.next_article_type();
@@ -560,6 +681,16 @@ impl App {
                         Viewing::Search(search_state) => {
                             search_state.page_back(self.search_context.clone());
                         }
+                        Viewing::Profile(profile_state) => {
+                            if let Some(offset) = profile_state.previous_offset() {
+                                profile_state.loading = true;
+                                self.event_manager.fetch_profile(
+                                    self.search_context.clone(),
+                                    profile_state.handle.clone(),
+                                    offset,
+                                );
+                            }
+                        }
                     }
                 } else {
                     self.articles_state.previous_article_type();
@@ -582,6 +713,11 @@ impl App {
                                 *n = n.saturating_sub(1);
                             }
                         }
+                        Viewing::Profile(profile_state) => {
+                            if let Some(n) = profile_state.viewing.as_mut() {
+                                *n = n.saturating_sub(1);
+                            }
+                        }
                     }
                 }
             }
@@ -615,6 +751,23 @@ impl App {
                                 }
                             };
                         }
+                        Viewing::Profile(profile_state) => {
+                            let count = profile_state
+                                .profile
+                                .as_ref()
+                                .map(|profile| profile.submissions.items.len())
+                                .unwrap_or_default();
+                            match profile_state.viewing.as_mut() {
+                                Some(n) if n.saturating_add(1) < count => {
+                                    *n = n.saturating_add(1);
+                                }
+                                Some(_) => (),
+                                None if count > 0 => {
+                                    profile_state.viewing.replace(0);
+                                }
+                                None => (),
+                            };
+                        }
                     }
                 }
             }
@@ -627,8 +780,42 @@ impl App {
                     .and_then(|selected| self.articles_state.stories.get(selected))
                     .cloned();
                 if let Some(story) = story {
-                    self.event_manager
-                        .update_story(self.search_context.clone(), story);
+                    self.event_manager.update_story(
+                        self.search_context.clone(),
+                        story,
+                        self.config.index_config.rebuild_options,
+                    );
+                }
+            }
+            // Open the profile of the selected author.
+            (_, KeyCode::Char('p')) => {
+                let author = match &self.viewing_state {
+                    Some(Viewing::Comments(state)) => state
+                        .viewing
+                        .and_then(|viewing| state.comments.get(viewing))
+                        .map(|comment| comment.by.clone()),
+                    Some(Viewing::Search(state)) => state
+                        .viewing
+                        .and_then(|viewing| state.comments.get(viewing))
+                        .map(|comment| comment.by.clone()),
+                    Some(Viewing::Profile(_)) => None,
+                    None => self
+                        .articles_state
+                        .list_state
+                        .selected()
+                        .and_then(|selected| self.articles_state.stories.get(selected))
+                        .map(|story| story.by.clone()),
+                };
+                if let Some(handle) = author {
+                    self.event_manager.fetch_profile(
+                        self.search_context.clone(),
+                        handle.clone(),
+                        0,
+                    );
+                    self.viewing_state = Some(Viewing::Profile(ProfileState::new(
+                        handle,
+                        self.viewing_state.take(),
+                    )));
                 }
             }
             // Open search view
@@ -644,65 +831,18 @@ impl App {
                         .and_then(|index| search_state.comments.get(index))
                         .map(|comment| (comment.id, comment.parent_id))
                 {
-                    let result = self.search_context.read().unwrap().parents(comment_id);
-                    match result {
-                        Ok(stack) => {
-                            let child_stack = || {
-                                stack
-                                    .comments
-                                    .iter()
-                                    .rev()
-                                    .scan(stack.story.id, |parent_id, comment| {
-                                        let current_parent_id = *parent_id;
-                                        *parent_id = comment.parent_id;
-                                        Some(CommentStack {
-                                            parent_id: current_parent_id,
-                                            ..Default::default()
-                                        })
-                                    })
-                                    .skip(1)
-                                    .collect::<Vec<_>>()
-                            };
-
-                            let mut current_offset = 0;
-
-                            loop {
-                                let (comments, total_comments) = self
-                                    .search_context
-                                    .read()
-                                    .unwrap()
-                                    .comments(comment_parent_id, 10, current_offset)
-                                    .unwrap();
-                                let viewing =
-                                    comments.iter().position(|comment| comment.id == comment_id);
-                                if viewing.is_some() {
-                                    let comments_state = CommentState {
-                                        parent_id: comment_parent_id,
-                                        comments,
-                                        total_comments,
-                                        child_stack: child_stack(),
-                                        limit: 10,
-                                        viewing,
-                                        offset: current_offset,
-                                        ..Default::default()
-                                    };
-                                    let selected_index = self
-                                        .articles_state
-                                        .stories
-                                        .iter()
-                                        .position(|story| story.id == stack.story.id);
-                                    self.articles_state.list_state.select(selected_index);
-                                    self.viewing_state = Some(Viewing::Comments(comments_state));
-                                    break;
-                                } else {
-                                    current_offset += 10;
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            error!("Failed to build comment thread stack: {err}");
-                        }
-                    }
+                    self.show_thread(comment_id, comment_parent_id);
+                }
+            }
+            // Toggle showing the body of dead comments.
+            (_, KeyCode::Char('d')) => {
+                if let Some(Viewing::Search(state)) = &self.viewing_state
+                    && matches!(state.input_mode, InputMode::Editing)
+                {
+                    // ignore while editing a search input.
+                } else {
+                    self.config.index_config.show_dead = !self.config.index_config.show_dead;
+                    self.save_config();
                 }
             }
             (_, KeyCode::Char('?')) => {
@@ -719,6 +859,147 @@ impl App {
         }
     }
 
+    /// Select and edit a rebuild option while the popup is open.
+    fn on_rebuild_options_key_event(&mut self, key: KeyEvent, event: &Event) {
+        let Some(state) = self.rebuild_options.as_mut() else {
+            return;
+        };
+
+        match (state.input.as_mut(), key.code) {
+            (Some(_), KeyCode::Esc) => {
+                state.input = None;
+            }
+            (Some(input), KeyCode::Enter) => {
+                let value = input.value().to_owned();
+                let options = &mut self.config.index_config.rebuild_options;
+                let changed = state.setting().set(options, &value);
+                state.input = None;
+                if changed {
+                    self.save_config();
+                }
+            }
+            (Some(input), _) => {
+                input.handle_event(event);
+            }
+            (None, KeyCode::Esc | KeyCode::Char('q')) => {
+                self.rebuild_options = None;
+            }
+            (None, KeyCode::Down | KeyCode::Char('j')) => state.select_next(),
+            (None, KeyCode::Up | KeyCode::Char('k')) => state.select_previous(),
+            (None, KeyCode::Enter) => {
+                let value = state
+                    .setting()
+                    .value(&self.config.index_config.rebuild_options);
+                state.input = Some(Input::new(value));
+            }
+            _ => {}
+        }
+    }
+
+    /// Save the configuration in the background.
+    fn save_config(&self) {
+        let config = self.config.clone();
+        tokio::spawn(async {
+            if let Err(err) = save_config(config).await {
+                error!("Failed to save config: {err}");
+            }
+        });
+    }
+
+    /// Open the comments of a story.
+    fn open_comments(&mut self, selected_item: u64, is_poll: bool) {
+        let search_context = self.search_context.read().unwrap();
+        let comments = search_context.comments(selected_item, 10, 0);
+        let poll_options = if is_poll {
+            search_context
+                .poll_options(selected_item)
+                .inspect_err(|err| {
+                    error!("Failed to get poll options: {err}");
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        match comments {
+            Ok((comments, total)) => {
+                self.viewing_state = Some(Viewing::Comments(CommentState {
+                    parent_id: selected_item,
+                    limit: 10,
+                    comments,
+                    total_comments: total,
+                    poll_options,
+                    ..Default::default()
+                }));
+            }
+            Err(err) => {
+                error!("Failed to get comments: {err}");
+            }
+        }
+    }
+
+    /// Open a comment within its thread.
+    fn show_thread(&mut self, comment_id: u64, comment_parent_id: u64) {
+        let result = self.search_context.read().unwrap().parents(comment_id);
+        match result {
+            Ok(stack) => {
+                let child_stack = || {
+                    stack
+                        .comments
+                        .iter()
+                        .rev()
+                        .scan(stack.story.id, |parent_id, comment| {
+                            let current_parent_id = *parent_id;
+                            *parent_id = comment.parent_id;
+                            Some(CommentStack {
+                                parent_id: current_parent_id,
+                                ..Default::default()
+                            })
+                        })
+                        .skip(1)
+                        .collect::<Vec<_>>()
+                };
+
+                let mut current_offset = 0;
+
+                loop {
+                    let (comments, total_comments) = self
+                        .search_context
+                        .read()
+                        .unwrap()
+                        .comments(comment_parent_id, 10, current_offset)
+                        .unwrap();
+                    let viewing = comments.iter().position(|comment| comment.id == comment_id);
+                    if viewing.is_some() {
+                        let comments_state = CommentState {
+                            parent_id: comment_parent_id,
+                            comments,
+                            total_comments,
+                            child_stack: child_stack(),
+                            limit: 10,
+                            viewing,
+                            offset: current_offset,
+                            ..Default::default()
+                        };
+                        let selected_index = self
+                            .articles_state
+                            .stories
+                            .iter()
+                            .position(|story| story.id == stack.story.id);
+                        self.articles_state.list_state.select(selected_index);
+                        self.viewing_state = Some(Viewing::Comments(comments_state));
+                        break;
+                    } else {
+                        current_offset += 10;
+                    }
+                }
+            }
+            Err(err) => {
+                error!("Failed to build comment thread stack: {err}");
+            }
+        }
+    }
+
     pub fn select_item_url(&self) -> Option<&str> {
         self.articles_state
             .list_state
@@ -733,12 +1014,17 @@ impl App {
     }
 
     fn update_stories(&mut self) {
+        let article_type = self.articles_state.article_type;
         self.search_context
             .write()
             .unwrap()
-            .activate_index(self.articles_state.article_type)
-            .unwrap();
-        match self.search_context.read().unwrap().top_stories(75, 0) {
+            .activate_index(article_type);
+        match self
+            .search_context
+            .read()
+            .unwrap()
+            .top_stories(article_type, 75, 0)
+        {
             Ok(stories) => {
                 self.articles_state.stories = stories;
                 self.articles_state.list_state.select(Some(0));
@@ -782,10 +1068,17 @@ impl Widget for &mut App {
 
                 CommentsWidget::new(selected_title, selected_body)
                     .style(style)
+                    .show_dead(self.config.index_config.show_dead)
                     .render(content_area, buf, comment_state);
             }
             Some(Viewing::Search(state)) => {
                 SearchWidget::default()
+                    .style(style)
+                    .show_dead(self.config.index_config.show_dead)
+                    .render(content_area, buf, state);
+            }
+            Some(Viewing::Profile(state)) => {
+                ProfileWidget::default()
                     .style(style)
                     .render(content_area, buf, state);
             }
@@ -812,7 +1105,7 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Length(18),
+            Constraint::Length(20),
             Constraint::Percentage((100 - percent_y) / 2),
         ])
         .split(r);
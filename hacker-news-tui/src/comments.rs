@@ -2,7 +2,7 @@
 use crate::styles::{selected_style, top_header_style};
 use hacker_news_search::{
     SearchContext,
-    api::{AgeLabel, Comment},
+    api::{AgeLabel, Comment, Highlight, PollOption},
 };
 use html_sanitizer::{Anchor, Element};
 use log::error;
@@ -37,6 +37,7 @@ pub struct CommentState {
     pub scroll_view_state: ScrollViewState,
     pub child_stack: Vec<CommentStack>,
     pub page_height: u16,
+    pub poll_options: Vec<PollOption>,
 }
 
 impl CommentState {
@@ -95,6 +96,7 @@ pub struct CommentsWidget<'a> {
     article_title: &'a str,
     article_body: Option<&'a str>,
     style: Style,
+    show_dead: bool,
 }
 
 impl<'a> CommentsWidget<'a> {
@@ -104,6 +106,7 @@ impl<'a> CommentsWidget<'a> {
             article_title,
             article_body: body,
             style: Style::default(),
+            show_dead: false,
         }
     }
 
@@ -114,6 +117,13 @@ impl<'a> CommentsWidget<'a> {
         self
     }
 
+    /// Show the body of dead comments.
+    #[must_use = "method moves the value of self and returns the modified value"]
+    pub fn show_dead(mut self, show_dead: bool) -> Self {
+        self.show_dead = show_dead;
+        self
+    }
+
     fn render_comments(
         &self,
         buf: &mut Buffer,
@@ -121,10 +131,19 @@ impl<'a> CommentsWidget<'a> {
         body: Rect,
         article_body: Option<Paragraph<'_>>,
     ) {
+        let poll_paragraph = (!state.poll_options.is_empty())
+            .then(|| render_poll_options(&state.poll_options, self.style));
         let paragraph_widgets = article_body
             .into_iter()
+            .chain(poll_paragraph)
             .chain(state.comments.iter().zip(0..).map(|(item, index)| {
-                render_comment(item, state.viewing == Some(index), self.style, None)
+                render_comment(
+                    item,
+                    state.viewing == Some(index),
+                    self.style,
+                    None,
+                    self.show_dead,
+                )
             }))
             .collect::<Vec<_>>();
 
@@ -133,6 +152,33 @@ impl<'a> CommentsWidget<'a> {
     }
 }
 
+/// Width of the bar for the poll option with the most votes.
+const POLL_BAR_WIDTH: f32 = 30.;
+
+/// Render poll options with a bar relative to the leading option.
+fn render_poll_options(options: &[PollOption], style: Style) -> Paragraph<'_> {
+    let lines = options.iter().flat_map(|option| {
+        let elements = html_sanitizer::parse_elements(&option.text);
+        let bar_width = (option.relative_score(options) * POLL_BAR_WIDTH).round() as usize;
+        spans(elements, style, None)
+            .into_iter()
+            .chain([Line::from_iter([
+                Span::styled("█".repeat(bar_width), style.fg(Color::LightYellow)),
+                Span::styled(format!(" {} votes", option.score), style.italic()),
+            ])])
+    });
+
+    Paragraph::new(lines.collect::<Vec<_>>())
+        .wrap(Wrap { trim: false })
+        .style(style)
+        .block(
+            Block::bordered()
+                .title("Poll")
+                .border_type(BorderType::Rounded)
+                .padding(Padding::horizontal(1)),
+        )
+}
+
 /// Render `Paragraph` comments.
 pub fn render_comments(
     buf: &mut Buffer,
@@ -234,14 +280,15 @@ pub fn render_comment<'a>(
     item: &'a Comment,
     selected: bool,
     style: Style,
-    search: Option<&str>,
+    highlight: Option<&Highlight>,
+    show_dead: bool,
 ) -> Paragraph<'a> {
-    let elements = html_sanitizer::parse_elements(&item.body);
+    let elements = html_sanitizer::parse_elements(item.display_body(show_dead));
 
     let lines = spans(
         elements,
         if selected { selected_style() } else { style },
-        search,
+        highlight,
     )
     .into_iter()
     .collect::<Vec<_>>();
@@ -276,7 +323,11 @@ pub fn render_comment<'a>(
 }
 
 /// Convert the parsed `Element` markup into ratatui `Span`s and `Line`s.
-fn spans<'a>(elements: Vec<Element<'a>>, base_style: Style, search: Option<&str>) -> Vec<Line<'a>> {
+pub fn spans<'a>(
+    elements: Vec<Element<'a>>,
+    base_style: Style,
+    highlight: Option<&Highlight>,
+) -> Vec<Line<'a>> {
     let mut lines: Vec<Line<'_>> = Vec::new();
     lines.push(Line::styled("", base_style));
 
@@ -291,7 +342,7 @@ fn spans<'a>(elements: Vec<Element<'a>>, base_style: Style, search: Option<&str>
                     let mut skip_first_line = false;
                     // if append_last_line
                     if let Some(next_line) = s.lines().next() {
-                        last_line.extend(split_search(next_line, search, base_style));
+                        last_line.extend(split_search(next_line, highlight, base_style));
                         skip_first_line = true;
                     }
 
@@ -301,12 +352,12 @@ fn spans<'a>(elements: Vec<Element<'a>>, base_style: Style, search: Option<&str>
                             .flat_map(|line| {
                                 [
                                     Line::styled("", base_style),
-                                    Line::from_iter(split_search(line, search, base_style)),
+                                    Line::from_iter(split_search(line, highlight, base_style)),
                                 ]
                             }),
                     );
                 } else {
-                    last_line.extend(split_search(s, search, base_style));
+                    last_line.extend(split_search(s, highlight, base_style));
                 }
             }
             Element::Link(anchor) => {
@@ -331,14 +382,14 @@ fn spans<'a>(elements: Vec<Element<'a>>, base_style: Style, search: Option<&str>
                 last_line.extend(sub_spans(
                     elements,
                     base_style.add_modifier(Modifier::ITALIC),
-                    search,
+                    highlight,
                 ));
             }
             Element::Bold(elements) => {
                 last_line.extend(sub_spans(
                     elements,
                     base_style.add_modifier(Modifier::BOLD),
-                    search,
+                    highlight,
                 ));
             }
         }
@@ -352,13 +403,13 @@ fn spans<'a>(elements: Vec<Element<'a>>, base_style: Style, search: Option<&str>
 fn sub_spans<'a>(
     elements: Vec<Element<'a>>,
     base_style: Style,
-    search: Option<&str>,
+    highlight: Option<&Highlight>,
 ) -> Vec<Span<'a>> {
     let mut text_spans = Vec::new();
     for element in elements {
         match element {
             Element::Text(s) => {
-                text_spans.extend(split_search(s, search, base_style));
+                text_spans.extend(split_search(s, highlight, base_style));
             }
             Element::Escaped(c) => {
                 text_spans.push(Span::styled(c.to_string(), base_style));
@@ -367,14 +418,14 @@ fn sub_spans<'a>(
                 text_spans.extend(sub_spans(
                     elements,
                     base_style.add_modifier(Modifier::ITALIC),
-                    search,
+                    highlight,
                 ));
             }
             Element::Bold(elements) => {
                 text_spans.extend(sub_spans(
                     elements,
                     base_style.add_modifier(Modifier::BOLD),
-                    search,
+                    highlight,
                 ));
             }
             // Sub elements won't have this
@@ -393,26 +444,26 @@ fn maybe_span(anchor: Anchor<'_>, style: Style) -> Option<Span<'_>> {
         .map(|href_value| Span::styled(href_value, style.add_modifier(Modifier::UNDERLINED)))
 }
 
-fn split_search<'a>(line: &'a str, search: Option<&str>, style: Style) -> Vec<Span<'a>> {
-    match search {
-        Some(search) => {
+/// Split a line into spans highlighting the words a search matched.
+fn split_search<'a>(line: &'a str, highlight: Option<&Highlight>, style: Style) -> Vec<Span<'a>> {
+    match highlight {
+        Some(highlight) => {
             let mut spans = Vec::new();
             let mut last_index = 0;
 
-            for (index, matched) in line.match_indices(search) {
-                let (unmatched, _) = line.split_at(index);
-                if !unmatched.is_empty() {
-                    let segment = &unmatched[last_index..];
+            for range in highlight.ranges_in(line) {
+                let segment = &line[last_index..range.start];
+                if !segment.is_empty() {
                     spans.push(Span::styled(segment, style));
                 }
 
                 spans.push(Span::styled(
-                    matched,
+                    &line[range.clone()],
                     style
                         .bg(Color::from_u32(0xe6e600))
                         .fg(Color::from_u32(0x000000)),
                 ));
-                last_index = index + search.len();
+                last_index = range.end;
             }
 
             let remaining = &line[last_index..];
@@ -1,7 +1,11 @@
 //! Background events
 use futures::StreamExt as _;
 use hacker_news_api::ArticleType;
-use hacker_news_search::{IndexStats, RebuildProgress, SearchContext, api::Story, update_story};
+use hacker_news_search::{
+    IndexStats, RebuildOptions, RebuildProgress, SearchContext,
+    api::{Story, UserProfile, user_profile},
+    update_story,
+};
 use log::error;
 use ratatui::crossterm::event;
 use std::{
@@ -36,7 +40,11 @@ pub enum AppEvent {
     /// Indexing completed
     IndexingCompleted(IndexStats),
     /// Story updated
-    StoryUpdated(Story),
+    StoryUpdated(Box<Story>),
+    /// Profile page fetched
+    ProfileLoaded(Box<UserProfile>),
+    /// Profile page failed to fetch
+    ProfileFailed { handle: String, error: String },
 }
 
 /// Event manager.
@@ -75,6 +83,7 @@ impl EventManager {
         &self,
         search_context: Arc<RwLock<SearchContext>>,
         article_type: ArticleType,
+        options: RebuildOptions,
     ) {
         let (tx, mut rx) = futures::channel::mpsc::channel::<RebuildProgress>(100);
 
@@ -90,18 +99,24 @@ impl EventManager {
             tx,
             self.sender.clone(),
             article_type,
+            options,
         ));
     }
 
     /// Update a single story.
-    pub fn update_story(&self, search_context: Arc<RwLock<SearchContext>>, story: Story) {
+    pub fn update_story(
+        &self,
+        search_context: Arc<RwLock<SearchContext>>,
+        story: Story,
+        options: RebuildOptions,
+    ) {
         let tx = self.sender.clone();
         tokio::spawn(async move {
-            let result = update_story(search_context, story).await;
+            let result = update_story(search_context, story, options).await;
             match result {
                 Ok(story) => {
                     if let Some(story) = story {
-                        tx.send(AppEvent::StoryUpdated(story)).unwrap();
+                        tx.send(AppEvent::StoryUpdated(Box::new(story))).unwrap();
                     }
                 }
                 Err(err) => {
@@ -110,6 +125,29 @@ impl EventManager {
             }
         });
     }
+
+    /// Fetch a page of a user profile.
+    pub fn fetch_profile(
+        &self,
+        search_context: Arc<RwLock<SearchContext>>,
+        handle: String,
+        offset: usize,
+    ) {
+        let tx = self.sender.clone();
+        tokio::spawn(async move {
+            let event = match user_profile(search_context, handle.clone(), offset).await {
+                Ok(profile) => AppEvent::ProfileLoaded(Box::new(profile)),
+                Err(err) => {
+                    error!("Failed to fetch profile {handle}: {err}");
+                    AppEvent::ProfileFailed {
+                        handle,
+                        error: err.to_string(),
+                    }
+                }
+            };
+            tx.send(event).unwrap();
+        });
+    }
 }
 
 async fn rebuild(
@@ -117,8 +155,10 @@ async fn rebuild(
     tx_progress: futures::channel::mpsc::Sender<RebuildProgress>,
     tx_result: Sender<AppEvent>,
     article_type: ArticleType,
+    options: RebuildOptions,
 ) {
-    let stats = hacker_news_search::rebuild_index(search_context, article_type, tx_progress).await;
+    let stats =
+        hacker_news_search::refresh_index(search_context, article_type, options, tx_progress).await;
     match stats {
         Ok(stats) => {
             tx_result.send(AppEvent::IndexingCompleted(stats)).unwrap();
@@ -2,6 +2,7 @@
 use crate::{App, app::Viewing};
 use chrono::{DateTime, Utc};
 use chrono_tz::Tz;
+use hacker_news_search::{IndexState, api_client};
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Layout, Rect},
@@ -60,6 +61,10 @@ impl<'a> Widget for FooterWidget<'a> {
 
                 block.render(area, buf);
 
+                let [url, metrics] =
+                    Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .areas(url);
+
                 match &self.app.viewing_state {
                     Some(Viewing::Search(state)) => {
                         Line::raw(format!("Found {}", state.total_comments))
@@ -68,9 +73,15 @@ impl<'a> Widget for FooterWidget<'a> {
                 }
                 .render(url, buf);
 
+                Line::raw(api_client().metrics().to_string())
+                    .alignment(Alignment::Right)
+                    .render(metrics, buf);
+
                 let active_index = self.app.search_context.read().unwrap().active_category();
 
-                if let Some(stats) = self
+                if self.app.index_state != IndexState::Ready {
+                    Line::raw(self.app.index_state.to_string()).render(index_stats, buf);
+                } else if let Some(stats) = self
                     .app
                     .config
                     .index_config
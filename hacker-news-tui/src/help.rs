@@ -36,6 +36,7 @@ impl Widget for HelpWidget {
             View::Articles => Table::new(article_help(), constraints),
             View::Search => Table::new(search_help(), constraints),
             View::Comments => Table::new(comment_help(), constraints),
+            View::Profile => Table::new(profile_help(), constraints),
         }
         .block(block)
         .header(Row::new(["Key", "Usage"]).bottom_margin(1))
@@ -60,10 +61,12 @@ fn article_help<'a>() -> impl IntoIterator<Item = Row<'a>> {
         Row::new(["end", "Scroll to end"]),
         Row::new(["->", "Next category"]),
         Row::new(["<-", "Previous category"]),
-        Row::new(["r", "Rebuild category index"]),
+        Row::new(["r", "Refresh category index"]),
+        Row::new(["R", "Rebuild options"]),
         Row::new(["u", "Update selected article"]),
         Row::new(["o", "open article url"]),
         Row::new(["c", "open comments"]),
+        Row::new(["p", "open author profile"]),
         Row::new(["/", "open comment search"]),
         Row::new(["q/Esc", "close/quit"]),
     ]
@@ -82,6 +85,8 @@ fn search_help<'a>() -> impl IntoIterator<Item = Row<'a>> {
         Row::new(["Tab", "Select next comment"]),
         Row::new(["Shift+Tab", "Select previous comment"]),
         Row::new(["t", "open comment in thread"]),
+        Row::new(["p", "open author profile"]),
+        Row::new(["d", "toggle dead comments"]),
         Row::new(["o", "open article url"]),
         Row::new(["c", "open comments"]),
         Row::new(["/", "open comment search"]),
@@ -101,9 +106,29 @@ fn comment_help<'a>() -> impl IntoIterator<Item = Row<'a>> {
         Row::new(["<-", "Previous page"]),
         Row::new(["Tab", "Select next comment"]),
         Row::new(["Shift+Tab", "Select previous comment"]),
+        Row::new(["p", "open author profile"]),
+        Row::new(["d", "toggle dead comments"]),
         Row::new(["o", "open article url"]),
         Row::new(["c", "open comments"]),
         Row::new(["/", "open comment search"]),
         Row::new(["q/Esc", "close/quit"]),
     ]
 }
+
+fn profile_help<'a>() -> impl IntoIterator<Item = Row<'a>> {
+    [
+        Row::new(["j", "down"]),
+        Row::new(["k", "up"]),
+        Row::new(["pgup/ctrl+u", "page up"]),
+        Row::new(["pgdwn/ctrl+f", "page down"]),
+        Row::new(["home", "Scroll to top"]),
+        Row::new(["end", "Scroll to end"]),
+        Row::new(["->", "Next page"]),
+        Row::new(["<-", "Previous page"]),
+        Row::new(["Tab", "Select next submission"]),
+        Row::new(["Shift+Tab", "Select previous submission"]),
+        Row::new(["c", "open indexed submission"]),
+        Row::new(["o", "open submission in browser"]),
+        Row::new(["q/Esc", "close profile"]),
+    ]
+}
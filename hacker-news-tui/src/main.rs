@@ -6,8 +6,6 @@ use crate::{app::App, config::load_config};
 use color_eyre::eyre::Context;
 
 use hacker_news_config::init_logger;
-#[cfg(target_family = "unix")]
-use hacker_news_config::limits::check_nofiles_limit;
 use log::{debug, error};
 
 mod app;
@@ -17,6 +15,8 @@ mod config;
 mod events;
 mod footer;
 mod help;
+mod profile;
+mod rebuild_options;
 mod search;
 mod styles;
 
@@ -29,9 +29,6 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     init_logger("hacker-news-tui")?;
 
-    #[cfg(target_family = "unix")]
-    check_nofiles_limit();
-
     let config = load_config()
         .inspect_err(|err| {
             error!("No config file: {err}");
@@ -0,0 +1,219 @@
+//! User profile view and state
+use crate::{
+    app::Viewing,
+    comments::{render_comments, spans},
+    styles::{selected_style, top_header_style},
+};
+use hacker_news_api::Item;
+use hacker_news_search::api::{AgeLabel as _, PROFILE_PAGE_SIZE, UserProfile};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Style, Stylize as _},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Padding, Paragraph, StatefulWidget, Widget, Wrap},
+};
+use tui_scrollview::ScrollViewState;
+
+/// User profile state.
+pub struct ProfileState {
+    pub handle: String,
+    /// Fetched profile with the viewing page of submissions.
+    pub profile: Option<UserProfile>,
+    /// A page is being fetched.
+    pub loading: bool,
+    /// Last fetch error.
+    pub error: Option<String>,
+    pub viewing: Option<usize>,
+    pub scroll_view_state: ScrollViewState,
+    pub page_height: u16,
+    /// View to return to when the profile is closed.
+    pub previous: Option<Box<Viewing>>,
+}
+
+impl ProfileState {
+    /// Create a profile state that is loading the first page.
+    pub fn new(handle: String, previous: Option<Viewing>) -> Self {
+        Self {
+            handle,
+            profile: None,
+            loading: true,
+            error: None,
+            viewing: None,
+            scroll_view_state: ScrollViewState::default(),
+            page_height: 0,
+            previous: previous.map(Box::new),
+        }
+    }
+
+    /// Offset of the next page when not already loading.
+    pub fn next_offset(&self) -> Option<usize> {
+        self.profile
+            .as_ref()
+            .filter(|_| !self.loading)
+            .and_then(|profile| profile.submissions.next_offset())
+    }
+
+    /// Offset of the previous page when not already loading.
+    pub fn previous_offset(&self) -> Option<usize> {
+        self.profile
+            .as_ref()
+            .filter(|_| !self.loading)
+            .and_then(|profile| profile.submissions.previous_offset(PROFILE_PAGE_SIZE))
+    }
+
+    /// The selected submission.
+    pub fn selected_item(&self) -> Option<&Item> {
+        self.profile
+            .as_ref()
+            .zip(self.viewing)
+            .and_then(|(profile, viewing)| profile.submissions.items.get(viewing))
+    }
+
+    /// Show a fetched page.
+    pub fn loaded(&mut self, profile: UserProfile) {
+        self.profile = Some(profile);
+        self.loading = false;
+        self.error = None;
+        self.viewing = None;
+        self.scroll_view_state.scroll_to_top();
+    }
+}
+
+/// Profile Widget
+#[derive(Default)]
+pub struct ProfileWidget {
+    style: Style,
+}
+
+impl ProfileWidget {
+    /// Set the style
+    #[must_use = "method moves the value of self and returns the modified value"]
+    pub fn style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style = style.into();
+        self
+    }
+}
+
+impl StatefulWidget for ProfileWidget {
+    type State = ProfileState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let [title_area, content_area, pagination_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let Some(profile) = &state.profile else {
+            Line::styled(state.handle.as_str(), selected_style()).render(title_area, buf);
+            Line::raw(match &state.error {
+                Some(err) => err.as_str(),
+                None => "Loading...",
+            })
+            .centered()
+            .render(pagination_area, buf);
+            return;
+        };
+
+        // User summary
+        Line::from_iter([
+            Span::raw(profile.user.id.as_str()),
+            Span::raw(format!(" {} karma", profile.user.karma)),
+            Span::raw(format!(
+                " joined {}",
+                profile.age_label().unwrap_or_default()
+            )),
+            Span::raw(format!(" {} submissions", profile.submissions.total)),
+        ])
+        .style(selected_style())
+        .render(title_area, buf);
+
+        // Rendered about and submissions
+        let about = profile
+            .user
+            .about
+            .as_deref()
+            .filter(|about| !about.is_empty())
+            .map(|about| {
+                let elements = html_sanitizer::parse_elements(about);
+                Paragraph::new(spans(elements, top_header_style(), None))
+                    .wrap(Wrap { trim: false })
+                    .style(top_header_style())
+                    .block(
+                        Block::bordered()
+                            .border_type(BorderType::Rounded)
+                            .padding(Padding::horizontal(1)),
+                    )
+            });
+        let paragraph_widgets = about
+            .into_iter()
+            .chain(
+                profile
+                    .submissions
+                    .items
+                    .iter()
+                    .zip(0..)
+                    .map(|(item, index)| {
+                        render_item(
+                            item,
+                            state.viewing == Some(index),
+                            profile.is_indexed(item.id),
+                            self.style,
+                        )
+                    }),
+            )
+            .collect::<Vec<_>>();
+
+        state.page_height = content_area.height;
+        render_comments(
+            buf,
+            content_area,
+            paragraph_widgets,
+            &mut state.scroll_view_state,
+        );
+
+        // Pagination
+        Line::raw(match (&state.error, state.loading) {
+            (Some(err), _) => err.to_owned(),
+            (None, true) => "Loading...".to_owned(),
+            (None, false) => format!("{} of {}", profile.page(), profile.page_count()),
+        })
+        .centered()
+        .render(pagination_area, buf);
+    }
+}
+
+/// Render a story or comment the user submitted.
+fn render_item(item: &Item, selected: bool, indexed: bool, style: Style) -> Paragraph<'_> {
+    let style = if selected { selected_style() } else { style };
+    let lines = match (&item.title, &item.text) {
+        (Some(title), _) => vec![Line::styled(title.as_str(), style.bold())],
+        (None, Some(text)) => spans(html_sanitizer::parse_elements(text), style, None),
+        (None, None) => Vec::new(),
+    };
+
+    let title = Line::from_iter([
+        Span::raw(item.ty.as_str()),
+        Span::raw(" "),
+        Span::raw(item.age_label().unwrap_or_default()),
+        Span::raw(if indexed { " [indexed]" } else { "" }),
+    ])
+    .style(style.italic());
+
+    Paragraph::new(lines)
+        .block(
+            Block::bordered()
+                .border_type(if selected {
+                    BorderType::Thick
+                } else {
+                    BorderType::Rounded
+                })
+                .title_bottom(title)
+                .title_alignment(Alignment::Right)
+                .padding(Padding::horizontal(1)),
+        )
+        .style(style)
+        .wrap(Wrap { trim: false })
+}
@@ -0,0 +1,101 @@
+//! Rebuild options popup
+use crate::styles::selected_style;
+use hacker_news_search::{RebuildOptions, RebuildSetting};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Position, Rect},
+    style::{Color, Style},
+    widgets::{Block, BorderType, Row, StatefulWidget, Table, Widget},
+};
+use tui_input::Input;
+
+/// Width of the setting label column.
+const LABEL_WIDTH: u16 = 22;
+
+/// Rebuild options popup state.
+#[derive(Default)]
+pub struct RebuildOptionsState {
+    /// Index of the selected setting in [`RebuildSetting::ALL`].
+    pub selected: usize,
+    /// Input for the selected setting while it is edited.
+    pub input: Option<Input>,
+}
+
+impl RebuildOptionsState {
+    /// The selected setting.
+    pub fn setting(&self) -> RebuildSetting {
+        RebuildSetting::ALL[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(RebuildSetting::ALL.len() - 1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Terminal cursor position while editing when the popup is rendered in
+    /// `area`.
+    pub fn cursor(&self, area: Rect) -> Option<Position> {
+        self.input.as_ref().map(|input| Position {
+            x: area.x + 1 + LABEL_WIDTH + 1 + input.visual_cursor() as u16,
+            // Border, header and header margin.
+            y: area.y + 3 + self.selected as u16,
+        })
+    }
+}
+
+/// Displays and edits the options used when rebuilding the index.
+pub struct RebuildOptionsWidget<'a> {
+    options: &'a RebuildOptions,
+}
+
+impl<'a> RebuildOptionsWidget<'a> {
+    /// Create a new rebuild options widget.
+    pub fn new(options: &'a RebuildOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl StatefulWidget for RebuildOptionsWidget<'_> {
+    type State = RebuildOptionsState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title_alignment(Alignment::Right)
+            .title("Rebuild options")
+            .title_bottom("enter edit, empty for all");
+
+        let rows = RebuildSetting::ALL
+            .into_iter()
+            .zip(0..)
+            .map(|(setting, index)| {
+                let value = match &state.input {
+                    Some(input) if index == state.selected => input.value().to_owned(),
+                    _ => match setting.value(self.options) {
+                        value if value.is_empty() => "all".to_owned(),
+                        value => value,
+                    },
+                };
+                let row = Row::new([setting.label().to_owned(), value]);
+                if index == state.selected {
+                    row.style(selected_style())
+                } else {
+                    row
+                }
+            });
+
+        let table = Table::new(rows, [Constraint::Length(LABEL_WIDTH), Constraint::Fill(1)])
+            .block(block)
+            .header(Row::new(["Setting", "Value"]).bottom_margin(1))
+            .column_spacing(1)
+            .style(
+                Style::new()
+                    .bg(Color::from_u32(0xb3ccff))
+                    .fg(Color::from_u32(0x00000)),
+            );
+        Widget::render(table, area, buf);
+    }
+}
@@ -63,6 +63,7 @@ impl SearchState {
             self.search.as_deref().unwrap_or_default(),
             10,
             self.offset,
+            true,
         );
         match result {
             Ok((comments, total_comments)) => {
@@ -93,6 +94,7 @@ impl SearchState {
 #[derive(Default)]
 pub struct SearchWidget {
     style: Style,
+    show_dead: bool,
 }
 
 impl SearchWidget {
@@ -103,6 +105,13 @@ impl SearchWidget {
         self
     }
 
+    /// Show the body of dead comments.
+    #[must_use = "method moves the value of self and returns the modified value"]
+    pub fn show_dead(mut self, show_dead: bool) -> Self {
+        self.show_dead = show_dead;
+        self
+    }
+
     fn render_comments(&self, buf: &mut Buffer, state: &mut SearchState, body: Rect) {
         let paragraph_widgets = state
             .comments
@@ -113,7 +122,8 @@ impl SearchWidget {
                     item,
                     state.viewing == Some(index),
                     self.style,
-                    state.search.as_deref(),
+                    item.body_highlight.as_ref(),
+                    self.show_dead,
                 )
             })
             .collect::<Vec<_>>();
@@ -44,6 +44,17 @@ pub enum Element<'a> {
     Bold(Vec<Element<'a>>),
 }
 
+/// Decode the html escaped characters in text such as `&amp;` and `&#x27;`.
+/// Entities are decoded once so `&amp;lt;` becomes `&lt;`.
+pub fn decode_entities(input: &str) -> String {
+    parser::parse_escaped_text(input)
+        .map(|(_, text)| text)
+        .unwrap_or_else(|err| {
+            error!("Failed to decode entities: {err}");
+            input.to_owned()
+        })
+}
+
 /// Parse the input str into elements.
 pub fn parse_elements(input: &str) -> Vec<Element<'_>> {
     parser::parse_nodes(input)
@@ -43,7 +43,7 @@ fn parse_italic(input: &str) -> ParseResult<'_, Element<'_>> {
     context("parse_italic", map(italic, Element::Italic)).parse(input)
 }
 
-fn parse_escaped_text(input: &str) -> ParseResult<'_, String> {
+pub fn parse_escaped_text(input: &str) -> ParseResult<'_, String> {
     map(
         many0(alt((parse_escaped_character, parse_escaped_tag, anychar))),
         |v| v.into_iter().collect(),
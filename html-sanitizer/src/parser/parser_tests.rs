@@ -4,6 +4,19 @@ use super::{
 use cool_asserts::assert_matches;
 use nom::Err;
 
+#[test]
+fn decode_entities() {
+    assert_eq!(
+        crate::decode_entities("&quot;a&quot; it&#x27;s a&#x2F;b &lt;c&gt; &amp; &apos;"),
+        r#""a" it's a/b <c> & '"#
+    );
+    assert_eq!(crate::decode_entities("&amp;lt;"), "&lt;");
+    assert_eq!(
+        crate::decode_entities("no entities & more"),
+        "no entities & more"
+    );
+}
+
 #[test]
 fn parse_url() {
     let anchor = r#"<a href="http://www.google.com">Google</a><br/>"#;